@@ -0,0 +1,135 @@
+extern crate bincode;
+extern crate serde;
+extern crate serde_json;
+extern crate serde_path_to_error;
+
+#[allow(dead_code)]
+#[path = "src/resources/types.rs"]
+mod types;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::{collections::HashSet, env, fs, path::Path, process};
+
+use types::*;
+
+/// Directory with the name corpora used by the name styles, one name per line.
+const CORPORA_DIR: &str = "utils";
+
+/// Reads the name corpora, keyed by file stem, keeping each name once in
+/// lower case and skipping names with digits or other symbols.
+fn corpora() -> Map<String, Value> {
+    println!("cargo:rerun-if-changed={}", CORPORA_DIR);
+    let mut corpora = Map::new();
+    for path in fs::read_dir(CORPORA_DIR)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+    {
+        println!("cargo:rerun-if-changed={}", path.display());
+        let content = fs::read_to_string(&path).unwrap_or_else(|msg| {
+            eprintln!("Failed reading corpus {}: {}", path.display(), msg);
+            process::exit(1);
+        });
+        let mut seen = HashSet::new();
+        let names = content
+            .lines()
+            .map(|line| line.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .filter(|name| {
+                name.chars()
+                    .all(|c| c.is_ascii_lowercase() || c == ' ' || c == '\'' || c == '-')
+            })
+            .filter(|name| seen.insert(name.clone()))
+            .map(Value::String)
+            .collect();
+        let stem = path.file_stem().unwrap().to_string_lossy().into_owned();
+        corpora.insert(stem, Value::Array(names));
+    }
+    corpora
+}
+
+/// Reads and deserializes the resource with the given key from the resource
+/// directory, exiting on failure. The name styles get the corpora from the
+/// utils directory.
+fn read<T: DeserializeOwned>(key: &str) -> T {
+    let path = Path::new("res").join(format!("{}.json", key));
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let content = fs::read_to_string(&path).unwrap_or_else(|msg| {
+        eprintln!("Failed reading resource {}: {}", path.display(), msg);
+        process::exit(1);
+    });
+    let mut value: Value = serde_json::from_str(&content).unwrap_or_else(|msg| {
+        eprintln!("Invalid resource {}:\n  {}", path.display(), msg);
+        process::exit(1);
+    });
+    if key == "name_styles" {
+        if let Value::Object(ref mut value) = value {
+            value.insert(String::from("corpora"), Value::Object(corpora()));
+        }
+    }
+    serde_path_to_error::deserialize(value).unwrap_or_else(|msg| {
+        eprintln!(
+            "Invalid resource {}:\n  {}: {}",
+            path.display(),
+            msg.path(),
+            msg.inner()
+        );
+        process::exit(1);
+    })
+}
+
+/// Writes the resource with the given key in bincode to the output
+/// directory, unless it has problems which are printed instead. Returns
+/// whether the resource was valid.
+fn write<T: Serialize>(key: &str, resource: &T, problems: &[Problem]) -> bool {
+    if !problems.is_empty() {
+        eprintln!("Invalid resource res/{}.json:", key);
+        for problem in problems {
+            eprintln!("  {}: {}", problem.path, problem.message);
+        }
+        return false;
+    }
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join(format!("{}.bin", key));
+    fs::write(&path, bincode::serialize(resource).unwrap()).unwrap();
+    true
+}
+
+/// Deserializes and validates the bundled resources, and writes them in
+/// bincode to the output directory to be included in the binary. Invalid
+/// resources fail the build, mods are layered and validated at runtime.
+fn main() {
+    let commodities: CommodityResource = read("commodities");
+    let factions: FactionResource = read("factions");
+    let agents: AgentResource = read("economic_agents");
+    let ships: ShipResource = read("ships");
+    let scenarios: ScenarioResource = read("scenarios");
+    let names: AstronomicalNamesResource = read("astronomical_names");
+    let name_styles: NameStyleResource = read("name_styles");
+
+    let valid = [
+        write("commodities", &commodities, &commodities.problems()),
+        write(
+            "factions",
+            &factions,
+            &factions.problems(Some(&commodities)),
+        ),
+        write(
+            "economic_agents",
+            &agents,
+            &agents.problems(Some(&factions), Some(&commodities)),
+        ),
+        write("ships", &ships, &ships.problems(Some(&factions))),
+        write(
+            "scenarios",
+            &scenarios,
+            &scenarios.problems(Some(&ships), Some(&factions), Some(&commodities)),
+        ),
+        write("astronomical_names", &names, &names.problems()),
+        write("name_styles", &name_styles, &name_styles.problems()),
+    ];
+    if valid.iter().any(|valid| !valid) {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,14 @@
+use astronomicals::planet::PlanetEconomy;
+
+/// Represents a belt of asteroids orbiting the star, mined by its settlers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AsteroidBelt {
+    pub name: String,
+    /// Total mass relative to the Earth.
+    pub mass: f64,
+    /// Distance in light seconds to the star.
+    pub orbit_distance: f64,
+    /// Time in days to complete an orbit.
+    pub orbital_period: f64,
+    pub economic_type: PlanetEconomy,
+}
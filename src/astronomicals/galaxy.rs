@@ -1,8 +1,12 @@
 use super::*;
+use astronomicals::{planet::PlanetEconomy, system::SystemId};
+use entities::Relations;
+use resources::{fetch_resource, FactionResource};
 
 use rayon::prelude::*;
 use spade::{rtree::RTree, BoundingRect};
 use std::{
+    cmp::Reverse,
     collections::{BinaryHeap, HashMap},
     u32::MAX,
 };
@@ -12,12 +16,14 @@ use std::{
 pub struct Galaxy {
     pub sectors: Vec<sector::Sector>,
     pub map: RTree<Point>,
-    pub systems: HashMap<Point, system::System>,
+    pub systems: HashMap<SystemId, system::System>,
+    pub relations: Relations,
+    locations: HashMap<Point, SystemId>,
 }
 
 impl Galaxy {
     /// Create a new galaxy with the given sectors and systems.
-    pub fn new(sectors: Vec<sector::Sector>, systems: Vec<system::System>) -> Self {
+    pub fn new(mut sectors: Vec<sector::Sector>, systems: Vec<system::System>) -> Self {
         let map = RTree::bulk_load(
             systems
                 .iter()
@@ -26,26 +32,71 @@ impl Galaxy {
         );
 
         let mut systems_map = HashMap::new();
+        let mut locations = HashMap::new();
 
         for system in systems {
-            systems_map.insert(system.location, system);
+            locations.insert(system.location, system.id);
+            systems_map.insert(system.id, system);
+        }
+
+        // Find bordering sectors.
+        let neighbors = territory::sector_neighbors(&sectors, &systems_map);
+        for (sector, neighbors) in sectors.iter_mut().zip(neighbors) {
+            sector.neighbors = neighbors;
+            sector.border = convex_hull(
+                &sector
+                    .system_ids
+                    .iter()
+                    .map(|id| systems_map[id].location)
+                    .collect::<Vec<_>>(),
+            );
         }
 
         Galaxy {
             sectors,
             map,
             systems: systems_map,
+            relations: Relations::new(&fetch_resource::<FactionResource>().unwrap()),
+            locations,
+        }
+    }
+
+    /// Returns the most common planet economy in the given sector, if any
+    /// planet has one.
+    pub fn dominant_economy(&self, sector: &sector::Sector) -> Option<PlanetEconomy> {
+        let mut counts = HashMap::new();
+        for planet in sector
+            .system_ids
+            .iter()
+            .filter_map(|id| self.systems.get(id))
+            .flat_map(|system| system.satelites.iter())
+            .filter(|planet| planet.economic_type != PlanetEconomy::None)
+        {
+            *counts.entry(&planet.economic_type).or_insert(0) += 1;
         }
+        // Ties are broken by the order of the economies.
+        PlanetEconomy::values()
+            .filter_map(|economy| counts.get(economy).map(|&count| (economy, count)))
+            .fold(
+                None,
+                |best: Option<(&PlanetEconomy, u32)>, (economy, count)| match best {
+                    Some((_, best_count)) if best_count >= count => best,
+                    _ => Some((economy, count)),
+                },
+            )
+            .map(|(economy, _)| economy.clone())
     }
 
-    /// Returns a reference to the system at the given location.
-    pub fn system(&self, location: &Point) -> Option<&system::System> {
-        self.systems.get(location)
+    /// Returns a reference to the system with the given id.
+    pub fn system(&self, id: SystemId) -> Option<&system::System> {
+        self.systems.get(&id)
     }
 
-    /// Returns a mutable reference to the system at the given location.
-    pub fn system_mut(&mut self, location: &Point) -> Option<&mut system::System> {
-        self.systems.get_mut(location)
+    /// Returns a reference to the system at exactly the given location.
+    pub fn system_at(&self, location: &Point) -> Option<&system::System> {
+        self.locations
+            .get(location)
+            .and_then(|id| self.systems.get(id))
     }
 
     /// Returns references to all systems.
@@ -53,11 +104,6 @@ impl Galaxy {
         self.systems.values()
     }
 
-    /// Returns mutable references to all systems.
-    pub fn systems_mut(&mut self) -> impl Iterator<Item = &mut System> {
-        self.systems.values_mut()
-    }
-
     /// Finds the system with the closest matching name.
     pub fn search_name(&self, query: &str) -> Option<&system::System> {
         self.systems
@@ -84,54 +130,53 @@ impl Galaxy {
 
     /// Finds the shortest path from start to goal with at most range along
     /// any edge and a maximum max_steps number of nodes visited.
+    /// The path consists of the id and location of each system along the way.
     pub fn route(
         &self,
-        start: Point,
-        goal: Point,
+        start: SystemId,
+        goal: SystemId,
         range: f64,
         max_steps: u32,
-    ) -> Option<(u32, Vec<Point>)> {
+    ) -> Option<(u32, Vec<(SystemId, Point)>)> {
+        self.system(start)?;
+        self.system(goal)?;
+
         // Node -> steps, cost mapping.
-        let mut dist = HashMap::<Point, u32>::new();
+        let mut dist = HashMap::<SystemId, u32>::new();
         let mut frontier = BinaryHeap::new();
-        let mut previous = HashMap::<Point, Point>::new();
+        let mut previous = HashMap::<SystemId, SystemId>::new();
 
         // We're at `start`, with a zero cost
         dist.insert(start, 0);
-        frontier.push(OrdPoint {
-            weight: 0,
-            point: start,
-        });
+        frontier.push((Reverse(0), start));
 
         let mut cost = None;
         // Examine the frontier with lower cost nodes first (min-heap)
-        while let Some(OrdPoint { point, weight }) = frontier.pop() {
+        while let Some((Reverse(weight), id)) = frontier.pop() {
             // Alternatively we could have continued to find all shortest paths
-            if point == goal {
+            if id == goal {
                 cost = Some(weight);
                 break;
             }
 
             // Important as we may have already found a better way
-            if weight > *dist.get(&point).unwrap_or(&MAX) {
+            if weight > *dist.get(&id).unwrap_or(&MAX) {
                 continue;
             }
 
             // For each node we can reach, see if we can find a way with
             // a lower cost going through this node
-            for neighbor in self.reachable(&point, (range).max(0.)) {
-                let next = OrdPoint {
-                    weight: weight + 1,
-                    point: *neighbor,
-                };
+            let location = self.systems[&id].location;
+            for neighbor in self.reachable(&location, (range).max(0.)) {
+                let next = self.locations[neighbor];
+                let next_weight = weight + 1;
 
                 // If so, add it to the frontier and continue
-                if next.weight <= max_steps && next.weight < *dist.get(&next.point).unwrap_or(&MAX)
-                {
-                    frontier.push(next);
+                if next_weight <= max_steps && next_weight < *dist.get(&next).unwrap_or(&MAX) {
+                    frontier.push((Reverse(next_weight), next));
                     // Relaxation, we have now found a better way
-                    dist.insert(next.point, next.weight);
-                    previous.insert(next.point, point);
+                    dist.insert(next, next_weight);
+                    previous.insert(next, id);
                 }
             }
         }
@@ -141,7 +186,7 @@ impl Galaxy {
                 let mut path = vec![];
                 let mut current = goal;
                 while current != start {
-                    path.push(current);
+                    path.push((current, self.systems[&current].location));
                     current = previous.remove(&current).unwrap();
                 }
                 path.reverse();
@@ -158,6 +203,8 @@ impl Default for Galaxy {
             sectors: vec![],
             map: RTree::new(),
             systems: HashMap::new(),
+            relations: Relations::default(),
+            locations: HashMap::new(),
         }
     }
 }
@@ -170,3 +217,57 @@ impl Updatable for Galaxy {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        star::{Star, StarType},
+        system::{SystemBuilder, SystemSecurity, SystemState},
+    };
+    use entities::Faction;
+
+    fn system(id: u32, x: f64) -> System {
+        SystemBuilder::default()
+            .id(SystemId(id))
+            .location(Point::new(x, 0.))
+            .name(format!("System {}", id))
+            .faction(Faction::new("Empire"))
+            .security(SystemSecurity::Medium)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_system_index() {
+        // Ids need not follow the order of the systems.
+        let galaxy = Galaxy::new(vec![], vec![system(7, 0.), system(3, 1.)]);
+        assert_eq!(galaxy.system(SystemId(3)).unwrap().name, "System 3");
+        assert_eq!(
+            galaxy.system_at(&Point::new(0., 0.)).unwrap().id,
+            SystemId(7)
+        );
+        assert!(galaxy.system(SystemId(0)).is_none());
+        assert!(galaxy.system_at(&Point::new(2., 0.)).is_none());
+    }
+
+    #[test]
+    fn test_route() {
+        let galaxy = Galaxy::new(vec![], (0..5).map(|id| system(id, f64::from(id))).collect());
+        let (cost, path) = galaxy.route(SystemId(0), SystemId(4), 1.5, 10).unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(
+            path.iter().map(|&(id, _)| id).collect::<Vec<_>>(),
+            (1..5).map(SystemId).collect::<Vec<_>>()
+        );
+        assert_eq!(path[3].1, Point::new(4., 0.));
+
+        // Out of range or steps.
+        assert!(galaxy.route(SystemId(0), SystemId(4), 0.5, 10).is_none());
+        assert!(galaxy.route(SystemId(0), SystemId(4), 1.5, 3).is_none());
+        assert!(galaxy.route(SystemId(0), SystemId(9), 1.5, 10).is_none());
+    }
+}
@@ -1,15 +1,19 @@
 use game::Updatable;
-use utils::{edit_distance, OrdPoint, Point};
+use utils::{convex_hull, edit_distance, Point};
 
+pub mod belt;
 pub mod galaxy;
 pub mod planet;
 pub mod sector;
 pub mod star;
+pub mod station;
 pub mod system;
+pub mod territory;
 
 // Useful shorthand imports.
 pub use self::galaxy::Galaxy;
 pub use self::planet::Planet;
 pub use self::sector::Sector;
 pub use self::star::Star;
+pub use self::station::Station;
 pub use self::system::System;
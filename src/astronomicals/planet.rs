@@ -1,16 +1,27 @@
 use std::fmt;
 
+pub use resources::types::PlanetEconomy;
+
 #[derive(Serialize, Deserialize, Debug, Builder, Clone)]
 #[builder(field(public))]
-/// Represents a visitable planet in game with some attributes.
+/// Represents a visitable planet or moon in game with some attributes. Mass,
+/// radius and surface gravity are relative to the Earth.
 pub struct Planet {
     pub name: String,
     pub mass: f64,
+    #[builder(default)]
+    pub radius: f64,
     pub gravity: f64,
+    /// Distance in light seconds to the star, or the planet for moons.
     pub orbit_distance: f64,
+    /// Time in days to complete an orbit.
+    #[builder(default)]
+    pub orbital_period: f64,
     pub surface_temperature: f64,
     pub planet_type: PlanetType,
     pub economic_type: PlanetEconomy,
+    #[builder(default)]
+    pub moons: Vec<Planet>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -37,18 +48,6 @@ impl fmt::Display for PlanetType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
-/// Different types of planet economies, depends on the planet type.
-pub enum PlanetEconomy {
-    Agriculture,
-    Extraction,
-    HighTech,
-    Industrial,
-    None,
-    Military,
-    Refinary,
-}
-
 impl fmt::Display for PlanetEconomy {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
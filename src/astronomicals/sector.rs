@@ -1,10 +1,33 @@
-use super::*;
+use astronomicals::system::SystemId;
 use entities::Faction;
+use utils::Point;
 
 /// Represents a group of systems in close proximity within the same faction.
 /// Markets in the economy is handled on this level of scale.
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Sector {
+    pub name: String,
     pub faction: Faction,
-    pub system_locations: Vec<Point>,
+    pub system_ids: Vec<SystemId>,
+    /// Convex hull of the system locations, counter-clockwise.
+    pub border: Vec<Point>,
+    /// Indices of the closest sectors, which are considered bordering.
+    pub neighbors: Vec<usize>,
+    /// Number of updates the sector has been in civil war.
+    pub contested_for: u32,
+}
+
+impl Sector {
+    /// Returns the area enclosed by the border.
+    pub fn area(&self) -> f64 {
+        let border = &self.border;
+        (0..border.len())
+            .map(|index| {
+                let (a, b) = (border[index], border[(index + 1) % border.len()]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f64>()
+            .abs()
+            / 2.
+    }
 }
@@ -1,21 +1,65 @@
 use std::fmt;
+use tui::style::Color;
+
+/// Light seconds in an astronomical unit.
+const ASTRONOMICAL_UNIT: f64 = 499.;
+
+/// Surface temperature of the Sun in Kelvin.
+pub const SOLAR_TEMPERATURE: f64 = 5778.;
 
 #[derive(Serialize, Deserialize, Debug, Clone, Builder)]
-/// Represents a Star in a system.
+/// Represents a Star in a system, where mass, luminosity and radius are
+/// relative to the Sun.
 pub struct Star {
     pub mass: f64,
     pub luminosity: f64,
     pub startype: StarType,
+    /// Surface temperature in Kelvin.
+    #[builder(default)]
+    pub temperature: f64,
+    #[builder(default)]
+    pub radius: f64,
+    #[builder(default)]
+    pub class: SpectralClass,
+    /// The second star of binary systems.
+    #[builder(default)]
+    pub companion: Option<Box<Star>>,
 }
 
 impl Star {
+    /// Create a new star with the given mass, luminosity and type, which has
+    /// the temperature, radius and class of the Sun.
     pub fn new(mass: f64, luminosity: f64, kind: StarType) -> Self {
         Star {
             mass,
             luminosity,
             startype: kind,
+            temperature: SOLAR_TEMPERATURE,
+            radius: 1.,
+            class: SpectralClass::G,
+            companion: None,
         }
     }
+
+    /// Returns the mass of the star together with its companion.
+    pub fn total_mass(&self) -> f64 {
+        self.mass + self.companion.as_ref().map_or(0., |star| star.mass)
+    }
+
+    /// Returns the luminosity of the star together with its companion.
+    pub fn total_luminosity(&self) -> f64 {
+        self.luminosity + self.companion.as_ref().map_or(0., |star| star.luminosity)
+    }
+
+    /// Returns the inner and outer edge of the habitable zone in light
+    /// seconds, where water stays liquid on the surface of planets.
+    pub fn habitable_zone(&self) -> (f64, f64) {
+        let luminosity = self.total_luminosity();
+        (
+            (luminosity / 1.1).sqrt() * ASTRONOMICAL_UNIT,
+            (luminosity / 0.53).sqrt() * ASTRONOMICAL_UNIT,
+        )
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -34,3 +78,69 @@ impl fmt::Display for StarType {
         write!(f, "{}", type_str)
     }
 }
+
+/// Morgan-Keenan spectral class of main sequence stars, from the hottest to
+/// the coolest, followed by stellar remnants.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpectralClass {
+    O,
+    B,
+    A,
+    F,
+    G,
+    K,
+    M,
+    WhiteDwarf,
+    NeutronStar,
+    BlackHole,
+}
+
+impl SpectralClass {
+    /// Returns the class of a main sequence star with the given surface
+    /// temperature in Kelvin.
+    pub fn from_temperature(temperature: f64) -> SpectralClass {
+        match temperature {
+            x if x >= 30_000. => SpectralClass::O,
+            x if x >= 10_000. => SpectralClass::B,
+            x if x >= 7_500. => SpectralClass::A,
+            x if x >= 6_000. => SpectralClass::F,
+            x if x >= 5_200. => SpectralClass::G,
+            x if x >= 3_700. => SpectralClass::K,
+            _ => SpectralClass::M,
+        }
+    }
+
+    /// Returns the color of stars of the class.
+    pub fn color(&self) -> Color {
+        match *self {
+            SpectralClass::O => Color::Blue,
+            SpectralClass::B => Color::LightBlue,
+            SpectralClass::A => Color::White,
+            SpectralClass::F => Color::LightYellow,
+            SpectralClass::G => Color::Yellow,
+            SpectralClass::K => Color::LightRed,
+            SpectralClass::M => Color::Red,
+            SpectralClass::WhiteDwarf => Color::Gray,
+            SpectralClass::NeutronStar => Color::Cyan,
+            SpectralClass::BlackHole => Color::DarkGray,
+        }
+    }
+}
+
+/// Stars are assumed to be like the Sun unless given a class.
+impl Default for SpectralClass {
+    fn default() -> Self {
+        SpectralClass::G
+    }
+}
+
+impl fmt::Display for SpectralClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SpectralClass::WhiteDwarf => write!(f, "White Dwarf"),
+            SpectralClass::NeutronStar => write!(f, "Neutron Star"),
+            SpectralClass::BlackHole => write!(f, "Black Hole"),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
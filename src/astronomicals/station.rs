@@ -0,0 +1,121 @@
+use std::{fmt, slice::Iter};
+
+use astronomicals::{
+    belt::AsteroidBelt,
+    planet::{Planet, PlanetEconomy, PlanetType},
+};
+
+/// Represents an artificial body orbiting a planet which can be docked at.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Station {
+    pub name: String,
+    pub kind: StationKind,
+    /// Index of the orbited planet in the system.
+    pub planet: usize,
+    pub economic_type: PlanetEconomy,
+    pub services: Vec<Service>,
+}
+
+/// Different kinds of stations, where outposts are small and remote.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StationKind {
+    Station,
+    Outpost,
+}
+
+impl fmt::Display for StationKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Services offered to docked ships.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Service {
+    Market,
+    Shipyard,
+    Repair,
+    Fuel,
+    BlackMarket,
+}
+
+impl Service {
+    pub fn values() -> Iter<'static, Service> {
+        static SERVICES: [Service; 5] = [
+            Service::Market,
+            Service::Shipyard,
+            Service::Repair,
+            Service::Fuel,
+            Service::BlackMarket,
+        ];
+        SERVICES.iter()
+    }
+}
+
+impl fmt::Display for Service {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Service::Market => "Market",
+                Service::Shipyard => "Shipyard",
+                Service::Repair => "Repair",
+                Service::Fuel => "Fuel",
+                Service::BlackMarket => "Black Market",
+            }
+        )
+    }
+}
+
+/// A body in a system which can be docked at, where moons are planets.
+#[derive(Debug, Clone, Copy)]
+pub enum Dockable<'a> {
+    Planet(&'a Planet),
+    Station(&'a Station),
+    Belt(&'a AsteroidBelt),
+}
+
+impl<'a> Dockable<'a> {
+    /// Returns the name of the body.
+    pub fn name(&self) -> &'a str {
+        match *self {
+            Dockable::Planet(planet) => &planet.name,
+            Dockable::Station(station) => &station.name,
+            Dockable::Belt(belt) => &belt.name,
+        }
+    }
+
+    /// Returns the economy of the body.
+    pub fn economy(&self) -> &'a PlanetEconomy {
+        match *self {
+            Dockable::Planet(planet) => &planet.economic_type,
+            Dockable::Station(station) => &station.economic_type,
+            Dockable::Belt(belt) => &belt.economic_type,
+        }
+    }
+
+    /// Returns the services offered by the body. Gas giants only offer fuel
+    /// while other planets offer all but a black market, and mining colonies
+    /// in belts trade and sell fuel.
+    pub fn services(&self) -> Vec<Service> {
+        match *self {
+            Dockable::Planet(planet) if planet.planet_type == PlanetType::GasGiant => {
+                vec![Service::Fuel]
+            }
+            Dockable::Planet(_) => vec![
+                Service::Market,
+                Service::Shipyard,
+                Service::Repair,
+                Service::Fuel,
+            ],
+            Dockable::Station(station) => station.services.clone(),
+            Dockable::Belt(_) => vec![Service::Market, Service::Fuel],
+        }
+    }
+
+    /// Returns true if the body offers the given service.
+    pub fn offers(&self, service: Service) -> bool {
+        self.services().contains(&service)
+    }
+}
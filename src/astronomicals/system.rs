@@ -1,93 +1,112 @@
 use super::*;
+use astronomicals::{belt::AsteroidBelt, station::Dockable};
 use entities::Faction;
 use game::Updatable;
 use std::{
+    cmp::Ordering,
     fmt,
     hash::{Hash, Hasher},
 };
 use utils::Point;
 
+pub use resources::types::SystemSecurity;
+
+/// Unique identifier of a system, stable for the lifetime of a galaxy.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SystemId(pub u32);
+
 #[derive(Serialize, Deserialize, Debug, Builder, Clone)]
 #[builder(field(public))]
 /// Represets a single star system with at a given location with the given
 /// star and planets.
 pub struct System {
+    pub id: SystemId,
     pub location: Point,
     pub name: String,
     pub faction: Faction,
     pub security: SystemSecurity,
     pub state: SystemState,
-    pub reputation: Reputation,
     pub star: Star,
     pub satelites: Vec<Planet>,
+    #[builder(default)]
+    pub stations: Vec<Station>,
+    #[builder(default)]
+    pub belts: Vec<AsteroidBelt>,
 }
 
-impl Updatable for System {
-    /// Updates the system one time step.
-    fn update(&mut self) {
-        // Update repuation levels.
-        self.reputation.update();
+impl System {
+    /// Returns the body with the given index which can be docked at, in the
+    /// order of `dockables`.
+    pub fn dockable(&self, index: usize) -> Option<Dockable<'_>> {
+        self.dockables().into_iter().nth(index)
     }
-}
 
-impl Hash for System {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        Hash::hash(&self.location, state);
+    /// Returns all bodies which can be docked at. Planets are followed by
+    /// stations, then the moons of each planet in turn and lastly belts.
+    pub fn dockables(&self) -> Vec<Dockable<'_>> {
+        self.satelites
+            .iter()
+            .map(Dockable::Planet)
+            .chain(self.stations.iter().map(Dockable::Station))
+            .chain(
+                self.satelites
+                    .iter()
+                    .flat_map(|planet| planet.moons.iter())
+                    .map(Dockable::Planet),
+            )
+            .chain(self.belts.iter().map(Dockable::Belt))
+            .collect()
     }
-}
 
-impl PartialEq for System {
-    fn eq(&self, other: &System) -> bool {
-        self.location == other.location
+    /// Returns the index in `dockables` of each body together with its depth
+    /// in the system. Planets and belts are ordered by distance to the star,
+    /// each planet followed by its moons and stations.
+    pub fn hierarchy(&self) -> Vec<(usize, usize)> {
+        let planets = self.satelites.len();
+        let mut moon_index = planets + self.stations.len();
+        let mut orbits = vec![];
+        for (index, planet) in self.satelites.iter().enumerate() {
+            let mut bodies = vec![(index, 0)];
+            bodies.extend((0..planet.moons.len()).map(|moon| (moon_index + moon, 1)));
+            moon_index += planet.moons.len();
+            bodies.extend(
+                self.stations
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, station)| station.planet == index)
+                    .map(|(station, _)| (planets + station, 1)),
+            );
+            orbits.push((planet.orbit_distance, bodies));
+        }
+        for index in 0..self.belts.len() {
+            orbits.push((
+                self.belts[index].orbit_distance,
+                vec![(moon_index + index, 0)],
+            ));
+        }
+        orbits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        orbits.into_iter().flat_map(|(_, bodies)| bodies).collect()
     }
 }
 
-impl Eq for System {}
-
-/// Represents the current player level of reputation with the system.
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Reputation(i32);
-
-impl Updatable for Reputation {
-    /// Updates the reputation level, one time step.
-    fn update(&mut self) {
-        // "Extreme" repuation levels converges towards lower levels.
-        self.0 += match self.0 {
-            -1000...-300 => 5,
-            300...1000 => -5,
-            _ => 0,
-        }
-    }
+impl Updatable for System {
+    /// Updates the system one time step.
+    fn update(&mut self) {}
 }
 
-impl Default for Reputation {
-    fn default() -> Reputation {
-        Reputation { 0: 0 }
+impl Hash for System {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&self.id, state);
     }
 }
 
-impl fmt::Display for Reputation {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let out_str = match self.0 {
-            -1000...-300 => "Hostile",
-            -299...-100 => "Unfriendly",
-            -99...100 => "Neutral",
-            101...300 => "Friendly",
-            301...1000 => "Allied",
-            _ => "Neutral",
-        };
-        write!(f, "{}", out_str)
+impl PartialEq for System {
+    fn eq(&self, other: &System) -> bool {
+        self.id == other.id
     }
 }
 
-/// Represents the different security levels a system is in at a given point.
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum SystemSecurity {
-    Anarchy,
-    Low,
-    Medium,
-    High,
-}
+impl Eq for System {}
 
 impl fmt::Display for SystemSecurity {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -102,7 +121,7 @@ impl fmt::Display for SystemSecurity {
 }
 
 /// Represents the different states a system is in at a given point.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum SystemState {
     Contested,
     CivilWar,
@@ -127,3 +146,76 @@ impl fmt::Display for SystemState {
         write!(f, "{}", state_str)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        planet::{PlanetBuilder, PlanetEconomy, PlanetType},
+        star::StarType,
+        station::StationKind,
+    };
+
+    fn planet(orbit_distance: f64, moons: usize) -> Planet {
+        let mut planet = PlanetBuilder::default()
+            .name(String::new())
+            .mass(1.)
+            .gravity(1.)
+            .orbit_distance(orbit_distance)
+            .surface_temperature(288.)
+            .planet_type(PlanetType::Rocky)
+            .economic_type(PlanetEconomy::Industrial)
+            .build()
+            .unwrap();
+        planet.moons = vec![planet.clone(); moons];
+        planet
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let station = Station {
+            name: String::new(),
+            kind: StationKind::Station,
+            planet: 1,
+            economic_type: PlanetEconomy::HighTech,
+            services: vec![],
+        };
+        let belt = AsteroidBelt {
+            name: String::new(),
+            mass: 0.001,
+            orbit_distance: 1500.,
+            orbital_period: 1000.,
+            economic_type: PlanetEconomy::Extraction,
+        };
+        let system = SystemBuilder::default()
+            .id(SystemId(0))
+            .location(Point::origin())
+            .name(String::from("Test"))
+            .faction(Faction::new("Empire"))
+            .security(SystemSecurity::Medium)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![planet(2000., 1), planet(1000., 2)])
+            .stations(vec![station])
+            .belts(vec![belt])
+            .build()
+            .unwrap();
+
+        // Dockables are the planets, the station, the moons and the belt.
+        assert_eq!(system.dockables().len(), 7);
+        assert_eq!(
+            system.hierarchy(),
+            vec![
+                // The inner planet with its moons and station.
+                (1, 0),
+                (4, 1),
+                (5, 1),
+                (2, 1),
+                (6, 0),
+                // The outer planet with its moon.
+                (0, 0),
+                (3, 1),
+            ]
+        );
+    }
+}
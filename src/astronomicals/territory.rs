@@ -0,0 +1,245 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use super::*;
+use astronomicals::system::{SystemId, SystemState};
+use entities::{Faction, Relations, Stance};
+
+/// Number of closest sectors considered to be bordering each sector.
+const SECTOR_NEIGHBORS: usize = 4;
+
+/// Economic strength advantage needed for a faction to take over a sector.
+const TAKEOVER_RATIO: f64 = 1.5;
+
+/// Number of updates a sector stays in civil war before changing owner.
+const TAKEOVER_UPDATES: u32 = 30;
+
+/// Improvement in relations once a war has ended in a takeover.
+const PEACE_SETTLEMENT: i32 = 30;
+
+/// Finds the bordering sectors of each sector, i.e. the sectors with the
+/// closest centroids. Borders are symmetric and empty sectors have none.
+pub fn sector_neighbors(
+    sectors: &[Sector],
+    systems: &HashMap<SystemId, System>,
+) -> Vec<Vec<usize>> {
+    let centroids = sectors
+        .iter()
+        .map(|sector| {
+            if sector.system_ids.is_empty() {
+                return None;
+            }
+            let mut centroid = sector
+                .system_ids
+                .iter()
+                .fold(Point::origin(), |sum, id| sum + systems[id].location);
+            centroid *= 1. / sector.system_ids.len() as f64;
+            Some(centroid)
+        })
+        .collect::<Vec<_>>();
+
+    let mut neighbors = vec![vec![]; sectors.len()];
+    for (index, centroid) in centroids.iter().enumerate() {
+        if let Some(ref centroid) = *centroid {
+            let mut others = centroids
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != index)
+                .filter_map(|(other, location)| {
+                    location.map(|location| (other, centroid.distance(&location)))
+                })
+                .collect::<Vec<_>>();
+            others.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal));
+
+            for (other, _) in others.into_iter().take(SECTOR_NEIGHBORS) {
+                if !neighbors[index].contains(&other) {
+                    neighbors[index].push(other);
+                }
+                if !neighbors[other].contains(&index) {
+                    neighbors[other].push(index);
+                }
+            }
+        }
+    }
+    neighbors
+}
+
+/// Returns the state of a sector given the economic strength of it and of its
+/// strongest neighbour at war with it, if any.
+fn sector_state(strength: f64, enemy_strength: Option<f64>) -> Option<SystemState> {
+    match enemy_strength {
+        Some(enemy_strength) if enemy_strength > strength * TAKEOVER_RATIO => {
+            Some(SystemState::CivilWar)
+        }
+        Some(_) => Some(SystemState::Contested),
+        None => None,
+    }
+}
+
+impl Galaxy {
+    /// Simulates faction relations and territory one step using the economic
+    /// strength of each sector. Sectors bordering a faction at war are
+    /// contested, and fall into civil war when the enemy is much stronger,
+    /// eventually changing owner.
+    pub fn update_territory(&mut self, strengths: &[f64]) {
+        let strength = |index: usize| *strengths.get(index).unwrap_or(&0.);
+
+        // Shared borders worsen relations.
+        {
+            let sectors = &self.sectors;
+            let borders =
+                Relations::count_borders(sectors.iter().enumerate().flat_map(|(index, sector)| {
+                    sector
+                        .neighbors
+                        .iter()
+                        .filter(move |&&other| other > index)
+                        .map(move |&other| (&sector.faction, &sectors[other].faction))
+                }));
+            self.relations.update(&borders);
+        }
+
+        // Find the strongest enemy of each sector.
+        let enemies = self
+            .sectors
+            .iter()
+            .map(|sector| {
+                sector
+                    .neighbors
+                    .iter()
+                    .cloned()
+                    .filter(|&other| {
+                        self.relations
+                            .stance(&sector.faction, &self.sectors[other].faction)
+                            == Stance::War
+                    })
+                    .max_by(|&a, &b| {
+                        strength(a)
+                            .partial_cmp(&strength(b))
+                            .unwrap_or(Ordering::Equal)
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let factions = self
+            .sectors
+            .iter()
+            .map(|sector| sector.faction.clone())
+            .collect::<Vec<Faction>>();
+
+        let relations = &mut self.relations;
+        for (index, sector) in self.sectors.iter_mut().enumerate() {
+            let enemy = enemies[index];
+            let state = sector_state(strength(index), enemy.map(|enemy| strength(enemy)));
+
+            // Sectors in civil war are eventually taken over by the enemy.
+            sector.contested_for = match state {
+                Some(SystemState::CivilWar) => sector.contested_for + 1,
+                _ => 0,
+            };
+            let mut state = state;
+            if sector.contested_for >= TAKEOVER_UPDATES {
+                let new_owner = factions[enemy.unwrap()].clone();
+                info!(
+                    "Sector {} was taken over by the {} from the {}",
+                    index, new_owner, sector.faction
+                );
+                relations.change(&sector.faction, &new_owner, PEACE_SETTLEMENT);
+                sector.faction = new_owner;
+                sector.contested_for = 0;
+                state = None;
+            }
+
+            for id in &sector.system_ids {
+                if let Some(system) = self.systems.get_mut(id) {
+                    system.faction = sector.faction.clone();
+                    system.state = match (state.clone(), system.state.clone()) {
+                        (Some(state), _) => state,
+                        // Conflicts are over, the economy recovers.
+                        (None, SystemState::Contested) | (None, SystemState::CivilWar) => {
+                            SystemState::Boom
+                        }
+                        (None, state) => state,
+                    };
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        star::{Star, StarType},
+        system::{SystemBuilder, SystemSecurity},
+    };
+
+    fn sector(id: u32, faction: &str) -> (Sector, System) {
+        let faction = Faction::new(faction);
+        let system = SystemBuilder::default()
+            .id(SystemId(id))
+            .location(Point::new(f64::from(id), 0.))
+            .name(format!("System {}", id))
+            .faction(faction.clone())
+            .security(SystemSecurity::Medium)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![])
+            .build()
+            .unwrap();
+        let sector = Sector {
+            name: format!("Sector {}", id),
+            faction,
+            system_ids: vec![SystemId(id)],
+            border: vec![],
+            neighbors: vec![],
+            contested_for: 0,
+        };
+        (sector, system)
+    }
+
+    #[test]
+    fn test_takeover() {
+        // The Empire and the Cartel start out at war.
+        let (empire, empire_system) = sector(0, "Empire");
+        let (cartel, cartel_system) = sector(1, "Cartel");
+        let mut galaxy = Galaxy::new(vec![empire, cartel], vec![empire_system, cartel_system]);
+        let before = galaxy
+            .relations
+            .relation(&Faction::new("Empire"), &Faction::new("Cartel"));
+
+        // The much stronger Cartel eventually takes over the Empire sector.
+        let strengths = [100., 200.];
+        for _ in 0..TAKEOVER_UPDATES - 1 {
+            galaxy.update_territory(&strengths);
+        }
+        assert_eq!(galaxy.sectors[0].contested_for, TAKEOVER_UPDATES - 1);
+        assert_eq!(
+            galaxy.system(SystemId(0)).unwrap().state,
+            SystemState::CivilWar
+        );
+        assert_eq!(
+            galaxy.system(SystemId(1)).unwrap().state,
+            SystemState::Contested
+        );
+
+        galaxy.update_territory(&strengths);
+        assert_eq!(galaxy.sectors[0].faction, Faction::new("Cartel"));
+        assert_eq!(galaxy.sectors[0].contested_for, 0);
+        let system = galaxy.system(SystemId(0)).unwrap();
+        assert_eq!(system.faction, Faction::new("Cartel"));
+        assert_eq!(system.state, SystemState::Boom);
+        assert!(
+            galaxy
+                .relations
+                .relation(&Faction::new("Empire"), &Faction::new("Cartel"))
+                > before
+        );
+    }
+
+    #[test]
+    fn test_sector_state() {
+        assert_eq!(sector_state(100., None), None);
+        assert_eq!(sector_state(100., Some(120.)), Some(SystemState::Contested));
+        assert_eq!(sector_state(100., Some(200.)), Some(SystemState::CivilWar));
+    }
+}
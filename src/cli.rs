@@ -0,0 +1,567 @@
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
+
+use astronomicals::Galaxy;
+use economy::{Commodity, Economy};
+use entities::Faction;
+use event;
+use export::{
+    data::{export_data, DataFormat, Level},
+    filter::Filter,
+    map::{export_map, ImageFormat, MapOptions},
+};
+use game::{Game, DEFAULT_SLOT};
+use game_config::GameConfig;
+use generators::generate_galaxy;
+use gui;
+use headless::{self, OutputFormat};
+use player::Player;
+use scenario::StartLocation;
+use simulator::Simulator;
+
+/// How the interactive game is started.
+enum Start {
+    Title,
+    NewGame,
+    LoadGame,
+}
+
+/// Creates the command line interface.
+pub fn app() -> App<'static, 'static> {
+    let slot = Arg::with_name("slot")
+        .long("slot")
+        .value_name("SLOT")
+        .default_value(DEFAULT_SLOT)
+        .help("Save slot to use");
+    let seed = Arg::with_name("seed")
+        .long("seed")
+        .value_name("SEED")
+        .help("Overrides the map seed of the config");
+    let difficulty = Arg::with_name("difficulty")
+        .long("difficulty")
+        .value_name("DIFFICULTY")
+        .possible_values(&["easy", "normal", "hard"])
+        .case_insensitive(true)
+        .help("Sets the credits and security of the given difficulty");
+    let systems = Arg::with_name("systems")
+        .long("systems")
+        .value_name("N")
+        .help("Overrides the number of systems of the config");
+    let sectors = Arg::with_name("sectors")
+        .long("sectors")
+        .value_name("N")
+        .help("Overrides the number of sectors, by default at most the number of systems");
+    let save = Arg::with_name("save")
+        .long("save")
+        .value_name("SAVE")
+        .help("Uses a saved game instead of generating a new galaxy");
+    let output = Arg::with_name("output")
+        .long("output")
+        .short("o")
+        .value_name("PATH")
+        .help("Writes to the given file instead of stdout");
+
+    App::new("gemini")
+        .about("Space trading and exploration in the terminal")
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("PATH")
+                .global(true)
+                .help("Game config to use instead of the one in the data directory"),
+        )
+        .arg(
+            Arg::with_name("data-dir")
+                .long("data-dir")
+                .value_name("PATH")
+                .global(true)
+                .help("Directory for config, saves, mods and logs"),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .global(true)
+                .possible_values(&["off", "error", "warn", "info", "debug", "trace"])
+                .default_value("info")
+                .help("Level of detail of the debug log"),
+        )
+        .subcommand(
+            SubCommand::with_name("play")
+                .about("Starts the game at the title page (default)")
+                .arg(slot.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("new")
+                .about("Starts a new game")
+                .arg(seed.clone())
+                .arg(difficulty.clone())
+                .arg(systems.clone())
+                .arg(sectors.clone())
+                .arg(
+                    Arg::with_name("scenario")
+                        .long("scenario")
+                        .value_name("NAME")
+                        .help("Starts from the given scenario"),
+                )
+                .arg(
+                    Arg::with_name("start-faction")
+                        .long("start-faction")
+                        .value_name("FACTION")
+                        .conflicts_with("scenario")
+                        .help("Starts at a random system of the given faction"),
+                )
+                .arg(slot.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("load")
+                .about("Continues the game saved in the given slot")
+                .arg(
+                    Arg::with_name("slot")
+                        .value_name("SLOT")
+                        .default_value(DEFAULT_SLOT)
+                        .help("Save slot to load"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("generate")
+                .about("Generates a galaxy and writes it as JSON")
+                .arg(seed.clone())
+                .arg(difficulty.clone())
+                .arg(systems.clone())
+                .arg(sectors.clone())
+                .arg(output.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("map")
+                .about("Renders a galaxy as an SVG or PNG image")
+                .arg(save.clone())
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["svg", "png"])
+                        .help("Image format, by default guessed from the output file"),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .long("width")
+                        .value_name("PIXELS")
+                        .default_value("1024")
+                        .help("Width of the image"),
+                )
+                .arg(
+                    Arg::with_name("height")
+                        .long("height")
+                        .value_name("PIXELS")
+                        .default_value("1024")
+                        .help("Height of the image"),
+                )
+                .arg(
+                    Arg::with_name("heatmap")
+                        .long("heatmap")
+                        .value_name("COMMODITY")
+                        .help("Colors systems by the price of the given commodity"),
+                )
+                .arg(
+                    Arg::with_name("star-colors")
+                        .long("star-colors")
+                        .conflicts_with("heatmap")
+                        .help("Colors systems by the spectral class of their star"),
+                )
+                .arg(
+                    Arg::with_name("route")
+                        .long("route")
+                        .value_names(&["FROM", "TO"])
+                        .multiple(true)
+                        .number_of_values(2)
+                        .help("Draws the route between the systems with the given names"),
+                )
+                .arg(
+                    Arg::with_name("range")
+                        .long("range")
+                        .value_name("DISTANCE")
+                        .default_value("20")
+                        .help("Jump range used when finding routes"),
+                )
+                .arg(seed.clone())
+                .arg(difficulty.clone())
+                .arg(systems.clone())
+                .arg(sectors.clone())
+                .arg(output.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Exports systems or planets as CSV, JSON or GeoJSON")
+                .arg(save)
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["csv", "json", "geojson"])
+                        .help("Output format, by default guessed from the output file"),
+                )
+                .arg(
+                    Arg::with_name("level")
+                        .long("level")
+                        .value_name("LEVEL")
+                        .possible_values(&["systems", "planets"])
+                        .default_value("systems")
+                        .help("Exports one record per system or per planet"),
+                )
+                .arg(
+                    Arg::with_name("filter")
+                        .long("filter")
+                        .value_name("EXPRESSION")
+                        .help(
+                            "Only exports records matching the expression, \
+                             e.g. \"faction == Empire && star_mass > 1.5\"",
+                        ),
+                )
+                .arg(seed.clone())
+                .arg(difficulty.clone())
+                .arg(systems.clone())
+                .arg(sectors.clone())
+                .arg(output.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("simulate")
+                .about("Runs the economy without the interface, writing market statistics")
+                .arg(
+                    Arg::with_name("days")
+                        .long("days")
+                        .alias("ticks")
+                        .value_name("N")
+                        .default_value("100")
+                        .help("Number of days to simulate"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .possible_values(&["csv", "json"])
+                        .default_value("csv")
+                        .help("Output format"),
+                )
+                .arg(seed)
+                .arg(difficulty)
+                .arg(systems)
+                .arg(sectors)
+                .arg(output),
+        )
+        .subcommand(
+            SubCommand::with_name("inspect")
+                .about("Prints a summary of a saved game")
+                .arg(
+                    Arg::with_name("save")
+                        .value_name("SAVE")
+                        .default_value(DEFAULT_SLOT)
+                        .help("Save slot or directory of the saved game"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validate-resources")
+                .about("Checks the built-in resources and all mods for problems"),
+        )
+}
+
+/// Runs the subcommand given on the command line.
+pub fn run(matches: &ArgMatches) -> Result<(), String> {
+    let config = match matches.value_of("config") {
+        Some(path) => GameConfig::load(Path::new(path))
+            .ok_or_else(|| format!("Failed to load config: {}", path))?,
+        None => GameConfig::retrieve(),
+    };
+
+    match matches.subcommand() {
+        ("new", Some(args)) => play(with_overrides(config, args)?, args, Start::NewGame),
+        ("load", Some(args)) => play(config, args, Start::LoadGame),
+        ("generate", Some(args)) => {
+            let config = with_overrides(config, args)?;
+            headless::generate(&config, &mut output(args)?).map_err(|err| err.to_string())
+        }
+        ("map", Some(args)) => {
+            let format = match args.value_of("format") {
+                Some("png") => ImageFormat::Png,
+                Some(_) => ImageFormat::Svg,
+                None => args
+                    .value_of("output")
+                    .and_then(ImageFormat::from_path)
+                    .unwrap_or(ImageFormat::Svg),
+            };
+            with_galaxy(config, args, |galaxy, economy, player| {
+                let mut options = map_options(galaxy, args)?;
+                // Include the route the player is currently traveling.
+                if let Some(route) = player.and_then(|player| player.route()) {
+                    options.routes.push(route.into_iter().cloned().collect());
+                }
+                export_map(galaxy, Some(economy), &options, format, &mut output(args)?)
+                    .map_err(|err| err.to_string())
+            })
+        }
+        ("export", Some(args)) => {
+            let format = match args.value_of("format") {
+                Some("csv") => DataFormat::Csv,
+                Some("geojson") => DataFormat::GeoJson,
+                Some(_) => DataFormat::Json,
+                None => args
+                    .value_of("output")
+                    .and_then(DataFormat::from_path)
+                    .unwrap_or(DataFormat::Json),
+            };
+            let level = match args.value_of("level") {
+                Some("planets") => Level::Planets,
+                _ => Level::Systems,
+            };
+            let filter = match args.value_of("filter") {
+                Some(expression) => Some(Filter::parse(expression)?),
+                None => None,
+            };
+            with_galaxy(config, args, |galaxy, _, _| {
+                export_data(galaxy, level, filter.as_ref(), format, &mut output(args)?)
+                    .map_err(|err| err.to_string())
+            })
+        }
+        ("simulate", Some(args)) => {
+            let config = with_overrides(config, args)?;
+            let days = parse(args, "days")?.unwrap_or(100);
+            let format = match args.value_of("format") {
+                Some("json") => OutputFormat::Json,
+                _ => OutputFormat::Csv,
+            };
+            headless::simulate(&config, days, format, &mut output(args)?)
+                .map_err(|err| err.to_string())
+        }
+        ("inspect", Some(args)) => {
+            let game = load_save(args.value_of("save").unwrap_or(DEFAULT_SLOT))?;
+            let stdout = io::stdout();
+            let mut handle = stdout.lock();
+            headless::inspect(&game, &mut handle).map_err(|err| err.to_string())
+        }
+        ("play", Some(args)) => play(config, args, Start::Title),
+        _ => play(config, matches, Start::Title),
+    }
+}
+
+/// Starts the interactive game.
+fn play(config: GameConfig, args: &ArgMatches, start: Start) -> Result<(), String> {
+    let slot = args.value_of("slot").unwrap_or(DEFAULT_SLOT);
+    config
+        .validate()
+        .map_err(|err| format!("Invalid config: {}", err))?;
+
+    // Start event handler
+    event::EventHandler::start();
+
+    // Start simulator
+    let mut simulator = Simulator::new(config, slot);
+    let game_state = match start {
+        Start::Title => None,
+        Start::NewGame => {
+            info!("Creating a new galaxy in slot {}", slot);
+            Some(simulator.new_game())
+        }
+        Start::LoadGame => Some(
+            simulator
+                .load_game()
+                .ok_or_else(|| format!("No saved game found in slot {}", slot))?,
+        ),
+    };
+
+    // Init and start gui
+    let mut gui = gui::Gui::new(simulator);
+    if let Some(game_state) = game_state {
+        gui.start_main_game(&game_state);
+    }
+    gui.start();
+    Ok(())
+}
+
+/// Loads a saved game given either by slot or by directory.
+fn load_save(save: &str) -> Result<Arc<Game>, String> {
+    let path = if Path::new(save).is_dir() {
+        Path::new(save).to_path_buf()
+    } else {
+        Game::save_path(save)
+    };
+    Game::load_from(&path, save).ok_or_else(|| format!("No saved game found at {}", path.display()))
+}
+
+/// Runs the given function on the saved game given as argument, or on a newly
+/// generated galaxy with its initial economy and no player.
+fn with_galaxy<F>(config: GameConfig, args: &ArgMatches, f: F) -> Result<(), String>
+where
+    F: FnOnce(&Galaxy, &Economy, Option<&Player>) -> Result<(), String>,
+{
+    match args.value_of("save") {
+        Some(save) => {
+            let game = load_save(save)?;
+            let galaxy = game.galaxy.lock().unwrap();
+            let economy = game.economy.lock().unwrap();
+            let player = game.player.lock().unwrap();
+            f(&galaxy, &economy, Some(&player))
+        }
+        None => {
+            let config = with_overrides(config, args)?;
+            let galaxy = generate_galaxy(&config);
+            let economy = Economy::new(&galaxy, config.agent_credits);
+            f(&galaxy, &economy, None)
+        }
+    }
+}
+
+/// Creates the map export options given as arguments, finding any routes
+/// asked for in the given galaxy.
+fn map_options(galaxy: &Galaxy, args: &ArgMatches) -> Result<MapOptions, String> {
+    let mut options = MapOptions::default();
+    options.width = parse(args, "width")?.unwrap_or(options.width);
+    options.height = parse(args, "height")?.unwrap_or(options.height);
+
+    if let Some(id) = args.value_of("heatmap") {
+        let commodity = Commodity::values()
+            .find(|commodity| commodity.id() == id)
+            .ok_or_else(|| format!("Unknown commodity: {}", id))?;
+        options.heatmap = Some(commodity.clone());
+    }
+
+    options.star_colors = args.is_present("star-colors");
+
+    let range = parse(args, "range")?.unwrap_or(20.);
+    let names = args
+        .values_of("route")
+        .map_or(vec![], |values| values.collect());
+    for pair in names.chunks(2) {
+        let (from, to) = match (galaxy.search_name(pair[0]), galaxy.search_name(pair[1])) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Err(String::from("Routes need a non-empty galaxy")),
+        };
+        let (_, route) = galaxy
+            .route(from.id, to.id, range, u32::MAX)
+            .ok_or_else(|| format!("No route found from {} to {}", from.name, to.name))?;
+        options
+            .routes
+            .push(route.into_iter().map(|(_, location)| location).collect());
+    }
+    Ok(options)
+}
+
+/// Applies the difficulty, seed, number of systems and sectors and start given
+/// as arguments to the config, which is then validated.
+fn with_overrides(mut config: GameConfig, args: &ArgMatches) -> Result<GameConfig, String> {
+    if let Some(difficulty) = parse(args, "difficulty")? {
+        config.set_difficulty(difficulty);
+    }
+    if let Some(seed) = parse(args, "seed")? {
+        config.map_seed = seed;
+    }
+    if let Some(systems) = parse(args, "systems")? {
+        config.number_of_systems = systems;
+        config.number_of_sectors = config.number_of_sectors.min(systems as usize);
+    }
+    if let Some(sectors) = parse(args, "sectors")? {
+        config.number_of_sectors = sectors;
+    }
+    if let Some(scenario) = args.value_of("scenario") {
+        config.scenario = Some(String::from(scenario));
+    }
+    if let Some(faction) = args.value_of("start-faction") {
+        config.scenario = None;
+        config.start = StartLocation::Random {
+            faction: Some(Faction::new(faction)),
+            security: None,
+            economy: None,
+        };
+    }
+    config
+        .validate()
+        .map_err(|err| format!("Invalid config: {}", err))?;
+    Ok(config)
+}
+
+/// Parses the value of the given argument, if present.
+fn parse<T: FromStr>(args: &ArgMatches, name: &str) -> Result<Option<T>, String> {
+    match args.value_of(name) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid value for --{}: {}", name, value)),
+        None => Ok(None),
+    }
+}
+
+/// Opens the output file given as argument, or stdout if none.
+fn output(args: &ArgMatches) -> Result<Box<dyn Write>, String> {
+    match args.value_of("output") {
+        Some(path) => File::create(path)
+            .map(|file| Box::new(BufWriter::new(file)) as Box<dyn Write>)
+            .map_err(|err| format!("Failed to create {}: {}", path, err)),
+        None => Ok(Box::new(BufWriter::new(io::stdout()))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_config::Difficulty;
+
+    /// Parses the given arguments of the new subcommand and applies them to
+    /// the default config.
+    fn new_game(args: &[&str]) -> Result<GameConfig, String> {
+        let matches = app()
+            .get_matches_from_safe(["gemini", "new"].iter().chain(args))
+            .map_err(|err| err.message)?;
+        with_overrides(
+            GameConfig::default(),
+            matches.subcommand_matches("new").unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_overrides() {
+        let config =
+            new_game(&["--seed", "7", "--systems", "500", "--difficulty", "HARD"]).unwrap();
+        assert_eq!(config.map_seed, 7);
+        assert_eq!(config.number_of_systems, 500);
+        assert_eq!(config.difficulty, Difficulty::Hard);
+        assert_eq!(config.starting_credits, 250);
+
+        // Small galaxies get at most one sector per system.
+        let config = new_game(&["--systems", "50"]).unwrap();
+        assert_eq!(config.number_of_sectors, 50);
+        let config = new_game(&["--systems", "50", "--sectors", "5"]).unwrap();
+        assert_eq!(config.number_of_sectors, 5);
+
+        let config = new_game(&["--start-faction", "Empire"]).unwrap();
+        assert_eq!(config.scenario, None);
+        match config.start {
+            StartLocation::Random {
+                faction: Some(faction),
+                ..
+            } => assert_eq!(faction, Faction::new("Empire")),
+            _ => panic!("Expected a random start in the Empire"),
+        }
+
+        // Unchanged parameters are kept.
+        assert_eq!(new_game(&[]).unwrap(), GameConfig::default());
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert!(new_game(&["--seed", "abc"]).unwrap_err().contains("--seed"));
+        assert!(new_game(&["--difficulty", "insane"]).is_err());
+        assert!(new_game(&["--systems", "0"]).is_err());
+        assert!(new_game(&["--systems", "50", "--sectors", "60"]).is_err());
+        assert!(new_game(&["--start-faction", "Aliens"]).is_err());
+        assert!(new_game(&["--scenario", "Smuggler", "--start-faction", "Empire"]).is_err());
+        assert!(app()
+            .get_matches_from_safe(vec!["gemini", "simulate", "--format", "png"])
+            .is_err());
+    }
+}
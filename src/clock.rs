@@ -0,0 +1,259 @@
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Source of the current real world time.
+pub trait TimeSource: Send + Sync {
+    /// Returns the current real world time.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Time source backed by the system wall clock.
+pub struct SystemTime;
+
+impl TimeSource for SystemTime {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Time source which only moves when explicitly advanced, useful for testing.
+#[cfg(test)]
+pub struct ManualTime {
+    time: Mutex<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl ManualTime {
+    /// Creates a new manual time source starting at the given time.
+    pub fn new(start: DateTime<Utc>) -> Self {
+        ManualTime {
+            time: Mutex::new(start),
+        }
+    }
+
+    /// Moves the time source forward by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        let mut time = self.time.lock().unwrap();
+        *time = *time + duration;
+    }
+}
+
+#[cfg(test)]
+impl TimeSource for ManualTime {
+    fn now(&self) -> DateTime<Utc> {
+        *self.time.lock().unwrap()
+    }
+}
+
+/// Persistent part of the clock, stored together with the rest of the game.
+/// The stored time scale is the one used when the game is loaded again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ClockState {
+    pub time: DateTime<Utc>,
+    pub time_scale: f64,
+    pub paused: bool,
+    /// Real time at which the state was stored.
+    pub saved_at: DateTime<Utc>,
+}
+
+/// Simulation clock which maps real time onto game time.
+/// Game time passes `time_scale` times faster than real time and does not
+/// pass at all while the clock is paused.
+pub struct Clock {
+    source: Arc<dyn TimeSource>,
+    time_scale: f64,
+    paused: bool,
+    real_anchor: DateTime<Utc>,
+    game_anchor: DateTime<Utc>,
+}
+
+impl Clock {
+    /// Creates a new clock using the given time source, starting at the given
+    /// game time.
+    pub fn new(source: Arc<dyn TimeSource>, start: DateTime<Utc>, time_scale: f64) -> Self {
+        let real_anchor = source.now();
+        Clock {
+            source,
+            time_scale,
+            paused: false,
+            real_anchor,
+            game_anchor: start,
+        }
+    }
+
+    /// Creates a new clock where game time follows the system wall clock.
+    pub fn realtime() -> Self {
+        Clock::new(Arc::new(SystemTime), Utc::now(), 1.)
+    }
+
+    /// Restores a clock from a previously stored state. Unless paused, game
+    /// time kept passing while the game was closed.
+    pub fn restore(source: Arc<dyn TimeSource>, state: ClockState) -> Self {
+        let mut clock = Clock::new(source, state.time, state.time_scale);
+        clock.paused = state.paused;
+        if !clock.paused {
+            let real_elapsed = clock
+                .source
+                .now()
+                .signed_duration_since(state.saved_at)
+                .num_milliseconds()
+                .max(0);
+            clock.advance(Duration::milliseconds(
+                (real_elapsed as f64 * clock.time_scale) as i64,
+            ));
+        }
+        clock
+    }
+
+    /// Returns the current state of the clock suitable for storing.
+    pub fn state(&self) -> ClockState {
+        ClockState {
+            time: self.now(),
+            time_scale: self.time_scale,
+            paused: self.paused,
+            saved_at: self.source.now(),
+        }
+    }
+
+    /// Returns the current game time.
+    pub fn now(&self) -> DateTime<Utc> {
+        if self.paused {
+            return self.game_anchor;
+        }
+        let real_elapsed = self
+            .source
+            .now()
+            .signed_duration_since(self.real_anchor)
+            .num_milliseconds();
+        self.game_anchor + Duration::milliseconds((real_elapsed as f64 * self.time_scale) as i64)
+    }
+
+    /// Converts the given game time into real time, returns None if the clock is
+    /// paused since the game time will then never be reached.
+    pub fn to_real(&self, game_time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.paused || self.time_scale <= 0. {
+            return None;
+        }
+        let game_remaining = game_time
+            .signed_duration_since(self.now())
+            .num_milliseconds();
+        Some(
+            self.source.now()
+                + Duration::milliseconds((game_remaining as f64 / self.time_scale) as i64),
+        )
+    }
+
+    /// Returns the number of game seconds passing per real second.
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    /// Sets the number of game seconds passing per real second.
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.rebase();
+        self.time_scale = time_scale.max(0.);
+    }
+
+    /// Returns true if the clock is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Stops game time from passing.
+    pub fn pause(&mut self) {
+        self.rebase();
+        self.paused = true;
+    }
+
+    /// Lets game time pass again after a pause.
+    pub fn resume(&mut self) {
+        self.rebase();
+        self.paused = false;
+    }
+
+    /// Moves game time forward by the given duration, regardless of pausing.
+    pub fn advance(&mut self, duration: Duration) {
+        self.rebase();
+        self.game_anchor = self.game_anchor + duration;
+    }
+
+    /// Moves the anchors to the current time so that later changes to the
+    /// scale or pausing only affects time from now on.
+    fn rebase(&mut self) {
+        self.game_anchor = self.now();
+        self.real_anchor = self.source.now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn manual_clock(time_scale: f64) -> (Arc<ManualTime>, Clock) {
+        let source = Arc::new(ManualTime::new(Utc.ymd(2018, 1, 1).and_hms(0, 0, 0)));
+        let clock = Clock::new(
+            source.clone(),
+            Utc.ymd(2020, 1, 1).and_hms(0, 0, 0),
+            time_scale,
+        );
+        (source, clock)
+    }
+
+    #[test]
+    fn test_time_scale() {
+        // One game day per real minute.
+        let (source, clock) = manual_clock(1440.);
+        source.advance(Duration::minutes(3));
+        assert_eq!(clock.now(), Utc.ymd(2020, 1, 4).and_hms(0, 0, 0));
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let (source, mut clock) = manual_clock(1.);
+        source.advance(Duration::hours(1));
+        clock.pause();
+        source.advance(Duration::hours(5));
+        assert_eq!(clock.now(), Utc.ymd(2020, 1, 1).and_hms(1, 0, 0));
+        assert_eq!(clock.to_real(clock.now()), None);
+
+        clock.resume();
+        source.advance(Duration::hours(1));
+        assert_eq!(clock.now(), Utc.ymd(2020, 1, 1).and_hms(2, 0, 0));
+    }
+
+    #[test]
+    fn test_restore() {
+        let (source, clock) = manual_clock(24.);
+        let state = clock.state();
+        let paused = ClockState {
+            paused: true,
+            ..state.clone()
+        };
+
+        // Game time passes while the game is closed, unless paused.
+        source.advance(Duration::hours(2));
+        let restored = Clock::restore(source.clone(), state);
+        assert_eq!(restored.now(), Utc.ymd(2020, 1, 3).and_hms(0, 0, 0));
+        assert_eq!(restored.time_scale(), 24.);
+        let restored = Clock::restore(source.clone(), paused);
+        assert_eq!(restored.now(), Utc.ymd(2020, 1, 1).and_hms(0, 0, 0));
+    }
+
+    #[test]
+    fn test_advance_and_rescale() {
+        let (source, mut clock) = manual_clock(1.);
+        clock.advance(Duration::days(2));
+        assert_eq!(clock.now(), Utc.ymd(2020, 1, 3).and_hms(0, 0, 0));
+
+        clock.set_time_scale(24.);
+        source.advance(Duration::hours(1));
+        assert_eq!(clock.now(), Utc.ymd(2020, 1, 4).and_hms(0, 0, 0));
+        assert_eq!(
+            clock.to_real(Utc.ymd(2020, 1, 5).and_hms(0, 0, 0)),
+            Some(source.now() + Duration::hours(1))
+        );
+    }
+}
@@ -7,8 +7,12 @@ use std::{
 };
 
 use super::*;
-use astronomicals::{planet::PlanetType, system::System};
-use resources::{fetch_resource, AgentResource};
+use astronomicals::{
+    planet::PlanetType,
+    station::{Dockable, StationKind},
+    system::System,
+};
+use resources::{fetch_resource, AgentResource, FactionResource};
 
 /// Economic agent, able to take part in transactions.
 #[derive(Serialize, Deserialize, Debug)]
@@ -23,27 +27,45 @@ pub struct Agent {
 }
 
 impl Agent {
-    // TODO: Move to config?
-    const STARTING_CREDITS: u64 = 100_000;
-    const INITIAL_LOWER_BELIEF: i64 = 1000;
-    const INITIAL_UPPER_BELIEF: i64 = 10000;
-    const DEFAULT_PRICE: i64 = (Agent::INITIAL_LOWER_BELIEF + Agent::INITIAL_UPPER_BELIEF) / 2;
+    const DEFAULT_BASE_PRICE: i64 = 1000;
+    /// Initial price beliefs range from the base price up to this multiple.
+    const INITIAL_BELIEF_SPREAD: i64 = 10;
     const MIN_PRICE: i64 = 10;
     const POPULATION_FACTOR: f64 = 1.;
 
-    /// Create a new economic agent for the given system.
-    pub fn new(system: &System) -> Self {
+    /// Create a new economic agent for the given system, starting with the
+    /// given credits.
+    pub fn new(system: &System, credits: u64) -> Self {
         let resource = fetch_resource::<AgentResource>().unwrap();
+        let factions = fetch_resource::<FactionResource>().unwrap();
 
-        // Setup ideal consumption.
-        let ideals = system.satelites.iter().fold(vec![], |mut ideals, planet| {
+        // Faction definitions may override the consumption and production of
+        // the agent resource.
+        let definition = factions.get(&system.faction);
+        let empty = HashMap::new();
+        let faction_ideals = match definition {
+            Some(definition) if !definition.ideals.is_empty() => &definition.ideals,
+            _ => resource
+                .faction_ideals
+                .get(&system.faction)
+                .unwrap_or(&empty),
+        };
+        let faction_production = match definition {
+            Some(definition) if !definition.production.is_empty() => &definition.production,
+            _ => resource
+                .faction_production
+                .get(&system.faction)
+                .unwrap_or(&empty),
+        };
+
+        // Setup ideal consumption, stations follow the planets.
+        let dockables = system.dockables();
+        let ideals = dockables.iter().fold(vec![], |mut ideals, body| {
             ideals.push(
                 Commodity::values()
                     .map(|commodity| {
-                        let planet_ideal = *resource.faction_ideals[&system.faction]
-                            .get(&commodity)
-                            .unwrap_or(&0)
-                            + *resource.planet_ideals[&planet.economic_type]
+                        let planet_ideal = *faction_ideals.get(&commodity).unwrap_or(&0)
+                            + *resource.planet_ideals[body.economy()]
                                 .get(&commodity)
                                 .unwrap_or(&0);
                         (commodity.clone(), planet_ideal)
@@ -54,42 +76,41 @@ impl Agent {
         });
 
         // Setup system commodity production.
-        let productions = system
-            .satelites
-            .iter()
-            .fold(vec![], |mut productions, planet| {
-                productions.push(
-                    Commodity::values()
-                        .map(|commodity| {
-                            let planet_production = *resource.faction_production[&system.faction]
+        let productions = dockables.iter().fold(vec![], |mut productions, body| {
+            productions.push(
+                Commodity::values()
+                    .map(|commodity| {
+                        let planet_production = *faction_production.get(&commodity).unwrap_or(&0)
+                            + *resource.planet_production[body.economy()]
                                 .get(&commodity)
-                                .unwrap_or(&0)
-                                + *resource.planet_production[&planet.economic_type]
-                                    .get(&commodity)
-                                    .unwrap_or(&0);
-                            (commodity.clone(), planet_production)
-                        })
-                        .collect(),
-                );
-                productions
-            });
+                                .unwrap_or(&0);
+                        (commodity.clone(), planet_production)
+                    })
+                    .collect(),
+            );
+            productions
+        });
 
         // Create initial price beliefs.
-        let price_beliefs = HashMap::from_iter(Commodity::values().map(|commodity| {
-            (
-                commodity.clone(),
-                Agent::INITIAL_LOWER_BELIEF..Agent::INITIAL_UPPER_BELIEF,
-            )
-        }));
+        let price_beliefs = HashMap::from_iter(
+            Commodity::values()
+                .map(|commodity| (commodity.clone(), Agent::initial_belief(commodity))),
+        );
 
         Agent {
-            seed: system.location.hash() as u32,
-            populations: system
-                .satelites
+            seed: system.id.0,
+            populations: dockables
                 .iter()
-                .map(|planet| Agent::initial_population(planet.mass, &planet.planet_type))
+                .map(|body| match *body {
+                    Dockable::Planet(planet) => {
+                        Agent::initial_population(planet.mass, &planet.planet_type)
+                    }
+                    Dockable::Station(station) => Agent::station_population(station.kind),
+                    // Belts are settled by a few miners.
+                    Dockable::Belt(_) => 1.,
+                })
                 .collect(),
-            credits: Agent::STARTING_CREDITS,
+            credits,
             inventory: HashMap::new(),
             ideals,
             productions,
@@ -97,6 +118,17 @@ impl Agent {
         }
     }
 
+    /// Returns the initial price belief for the given commodity based on its
+    /// base price.
+    fn initial_belief(commodity: &Commodity) -> Range<i64> {
+        let base_price = commodity
+            .definition()
+            .map_or(Agent::DEFAULT_BASE_PRICE, |definition| {
+                definition.base_price as i64
+            });
+        base_price..base_price * Agent::INITIAL_BELIEF_SPREAD
+    }
+
     /// Calculates the initial planet population based on mass and planet type.
     fn initial_population(mass: f64, kind: &PlanetType) -> f64 {
         let mass_factor = Gamma::new(7., 5.).unwrap();
@@ -110,22 +142,44 @@ impl Agent {
         mass_factor.pdf(mass) * type_factor * 100.
     }
 
-    /// Returns the hash of the system to which the agent is associated.
-    pub fn hash(&self) -> u32 {
-        self.seed
+    /// Returns the initial crew of a station of the given kind.
+    fn station_population(kind: StationKind) -> f64 {
+        match kind {
+            StationKind::Station => 10.,
+            StationKind::Outpost => 0.5,
+        }
     }
 
-    /// Returns the system populations.
+    /// Returns the populations of the system bodies, planets followed by
+    /// stations.
     pub fn populations(&self) -> Vec<f64> {
         self.populations.clone()
     }
 
+    /// Returns the credits currently held by the agent.
+    pub fn credits(&self) -> u64 {
+        self.credits
+    }
+
+    /// Returns the amount of the given commodity currently in inventory.
+    pub fn stock(&self, commodity: &Commodity) -> u64 {
+        *self.inventory.get(commodity).unwrap_or(&0)
+    }
+
+    /// Returns the believed price of the given commodity, if known.
+    pub fn price(&self, commodity: &Commodity) -> Option<i64> {
+        self.price_beliefs
+            .get(commodity)
+            .map(|range| (range.start + range.end) / 2)
+    }
+
     /// Returns the prices for all commodities known.
     pub fn prices(&self) -> Vec<(Commodity, i64)> {
         Commodity::values().fold(vec![], |mut prices, commodity| {
             if let Some(range) = self.price_beliefs.get(&commodity) {
                 let price = (range.start + range.end) / 2;
-                if price != Agent::DEFAULT_PRICE {
+                let initial = Agent::initial_belief(commodity);
+                if price != (initial.start + initial.end) / 2 {
                     prices.push((commodity.clone(), (range.start + range.end) / 2));
                 }
             }
@@ -155,9 +209,24 @@ impl Agent {
         *current_stock = (*current_stock as i64 + delta).max(0) as u64;
     }
 
-    /// Adds delta amount of credits.
-    pub fn update_credits(&mut self, delta: i64) {
-        self.credits = (self.credits as i64 + delta as i64) as u64;
+    /// Adds delta amount of credits, fails without changing the balance if
+    /// the balance would overflow or become negative.
+    pub fn update_credits(&mut self, delta: i64) -> Result<(), Violation> {
+        let new_balance = if delta < 0 {
+            self.credits.checked_sub(delta.wrapping_neg() as u64)
+        } else {
+            self.credits.checked_add(delta as u64)
+        };
+        match new_balance {
+            Some(balance) => {
+                self.credits = balance;
+                Ok(())
+            }
+            None => Err(Violation::CreditOverflow {
+                balance: self.credits,
+                delta,
+            }),
+        }
     }
 
     /// Updates the price beliefs for the given commodity based on the given unit price.
@@ -239,13 +308,15 @@ impl Agent {
     pub fn update_population(&mut self, demand_supply: &[(Commodity, u64, u64)]) {
         for (index, population) in self.populations.iter_mut().enumerate() {
             let productions = &self.productions[index];
-            let potential_earnings = demand_supply.iter().fold(
-                0,
-                |acc, (commodity, demand, supply)| match productions.get(commodity) {
-                    Some(0) | None => acc,
-                    Some(prod) => acc + ((demand - supply) as i64) / (*prod as i64),
-                },
-            );
+            let potential_earnings =
+                demand_supply
+                    .iter()
+                    .fold(0, |acc, (commodity, demand, supply)| {
+                        match productions.get(commodity) {
+                            Some(0) | None => acc,
+                            Some(prod) => acc + (*demand as i64 - *supply as i64) / (*prod as i64),
+                        }
+                    });
             *population += match potential_earnings.signum() {
                 1 => (*population * 0.1).min(10.),
                 -1 => (*population * -0.1).max(-10.).max(-*population),
@@ -255,9 +326,20 @@ impl Agent {
     }
 }
 
+/// Returns the part of the stock which spoils in one time step given the
+/// perishability of the commodity.
+fn spoiled(stock: u64, perishability: f64) -> u64 {
+    (stock as f64 * perishability) as u64
+}
+
 impl Updatable for Agent {
-    /// Updates the inventory based on the consumption and production.
+    /// Updates the inventory based on spoilage, consumption and production.
     fn update(&mut self) {
+        for (commodity, stock) in &mut self.inventory {
+            if let Some(definition) = commodity.definition() {
+                *stock -= spoiled(*stock, definition.perishability);
+            }
+        }
         for (index, (commodity, amount)) in self
             .productions
             .clone()
@@ -286,3 +368,23 @@ impl Updatable for Agent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spoilage() {
+        assert_eq!(spoiled(1000, 0.), 0);
+        assert_eq!(spoiled(1000, 0.02), 20);
+        // Partial units do not spoil.
+        assert_eq!(spoiled(10, 0.05), 0);
+        assert_eq!(spoiled(10, 1.), 10);
+
+        // Food spoils while metals keep.
+        let food = Commodity::new("Food").definition().unwrap().perishability;
+        let metals = Commodity::new("Metal").definition().unwrap().perishability;
+        assert!(spoiled(1000, food) > 0);
+        assert_eq!(spoiled(1000, metals), 0);
+    }
+}
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, fmt};
+
+use super::*;
+
+/// Totals of credits and goods held by all agents in a market.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MarketTotals {
+    pub credits: u64,
+    pub stock: HashMap<Commodity, u64>,
+}
+
+impl MarketTotals {
+    /// Sums up the credits and goods held by the given agents.
+    pub fn from_agents(agents: &[Arc<Mutex<Agent>>]) -> Self {
+        agents
+            .iter()
+            .fold(MarketTotals::default(), |mut totals, agent| {
+                let agent = agent.lock().unwrap();
+                totals.credits += agent.credits();
+                for commodity in Commodity::values() {
+                    *totals.stock.entry(commodity.clone()).or_insert(0) += agent.stock(commodity);
+                }
+                totals
+            })
+    }
+}
+
+/// A broken economic invariant discovered during a market update.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Violation {
+    /// Credits were created or destroyed while trading.
+    CreditsNotConserved { before: u64, after: u64 },
+    /// Goods were created or destroyed while trading.
+    GoodsNotConserved {
+        commodity: Commodity,
+        before: u64,
+        after: u64,
+    },
+    /// A credit transfer would have overflowed or made the balance negative.
+    CreditOverflow { balance: u64, delta: i64 },
+    /// A seller was asked to deliver more goods than it had in stock.
+    NegativeStock {
+        commodity: Commodity,
+        stock: u64,
+        delta: i64,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Violation::CreditsNotConserved { before, after } => write!(
+                f,
+                "Credits not conserved: {} before trading, {} after",
+                before, after
+            ),
+            Violation::GoodsNotConserved {
+                ref commodity,
+                before,
+                after,
+            } => write!(
+                f,
+                "{} not conserved: {} before trading, {} after",
+                commodity, before, after
+            ),
+            Violation::CreditOverflow { balance, delta } => write!(
+                f,
+                "Credit overflow: balance of {} changed by {}",
+                balance, delta
+            ),
+            Violation::NegativeStock {
+                ref commodity,
+                stock,
+                delta,
+            } => write!(
+                f,
+                "Negative stock: {} {} changed by {}",
+                stock, commodity, delta
+            ),
+        }
+    }
+}
+
+/// Checks that trading neither created nor destroyed any credits or goods by
+/// comparing the market totals before and after trading.
+pub fn check_conservation(before: &MarketTotals, after: &MarketTotals) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    if before.credits != after.credits {
+        violations.push(Violation::CreditsNotConserved {
+            before: before.credits,
+            after: after.credits,
+        });
+    }
+
+    for commodity in Commodity::values() {
+        let stock_before = *before.stock.get(commodity).unwrap_or(&0);
+        let stock_after = *after.stock.get(commodity).unwrap_or(&0);
+        if stock_before != stock_after {
+            violations.push(Violation::GoodsNotConserved {
+                commodity: commodity.clone(),
+                before: stock_before,
+                after: stock_after,
+            });
+        }
+    }
+
+    violations
+}
+
+/// Diagnostics report for the whole economy after the last update.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    pub ticks: u64,
+    pub total_violations: u64,
+    pub violations: Vec<(usize, Violation)>,
+}
+
+impl Diagnostics {
+    /// Records the violations found in each market during the last update and
+    /// reports them through the log.
+    pub fn record(&mut self, violations: Vec<(usize, Violation)>) {
+        for &(market, ref violation) in &violations {
+            warn!(
+                "Economy invariant violated in market {}: {}",
+                market, violation
+            );
+        }
+        self.ticks += 1;
+        self.total_violations += violations.len() as u64;
+        self.violations = violations;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        planet::{PlanetBuilder, PlanetEconomy, PlanetType},
+        star::{Star, StarType},
+        system::{SystemBuilder, SystemId, SystemSecurity, SystemState},
+    };
+    use entities::Faction;
+    use quickcheck::{Arbitrary, Gen};
+    use utils::Point;
+
+    /// Mass, type and economy of a planet in a generated market.
+    type PlanetSpec = (f64, PlanetType, PlanetEconomy);
+
+    /// Random market description used to generate markets for property tests.
+    #[derive(Clone, Debug)]
+    struct MarketSpec {
+        systems: Vec<(f64, f64, Faction, Vec<PlanetSpec>)>,
+        ticks: u8,
+    }
+
+    impl Arbitrary for MarketSpec {
+        fn arbitrary<G: Gen>(g: &mut G) -> MarketSpec {
+            let factions = [
+                Faction::new("Empire"),
+                Faction::new("Federation"),
+                Faction::new("Cartel"),
+                Faction::new("Independent"),
+            ];
+            let planet_types = [
+                PlanetType::Metal,
+                PlanetType::Icy,
+                PlanetType::Rocky,
+                PlanetType::GasGiant,
+                PlanetType::Earth,
+            ];
+            let economies = [
+                PlanetEconomy::Agriculture,
+                PlanetEconomy::Extraction,
+                PlanetEconomy::HighTech,
+                PlanetEconomy::Industrial,
+                PlanetEconomy::None,
+                PlanetEconomy::Military,
+                PlanetEconomy::Refinary,
+            ];
+            let num_systems = g.gen_range(1, 8);
+            let systems = (0..num_systems)
+                .map(|_| {
+                    let planets = (0..g.gen_range(1, 5))
+                        .map(|_| {
+                            (
+                                g.gen_range(0.1, 20.),
+                                g.choose(&planet_types).unwrap().clone(),
+                                g.choose(&economies).unwrap().clone(),
+                            )
+                        })
+                        .collect();
+                    (
+                        g.gen_range(-100., 100.),
+                        g.gen_range(-100., 100.),
+                        g.choose(&factions).unwrap().clone(),
+                        planets,
+                    )
+                })
+                .collect();
+            MarketSpec {
+                systems,
+                ticks: g.gen_range(1, 10),
+            }
+        }
+    }
+
+    /// Creates a market containing one agent per described system.
+    fn build_market(spec: &MarketSpec) -> Market {
+        let mut market = Market::new();
+        for (id, &(x, y, ref faction, ref planets)) in spec.systems.iter().enumerate() {
+            let satelites = planets
+                .iter()
+                .map(|&(mass, ref planet_type, ref economic_type)| {
+                    PlanetBuilder::default()
+                        .name(String::from("Test"))
+                        .mass(mass)
+                        .gravity(mass)
+                        .orbit_distance(1000.)
+                        .surface_temperature(290.)
+                        .planet_type(planet_type.clone())
+                        .economic_type(economic_type.clone())
+                        .build()
+                        .unwrap()
+                })
+                .collect();
+            let system = SystemBuilder::default()
+                .id(SystemId(id as u32))
+                .location(Point::new(x, y))
+                .name(String::from("Test"))
+                .faction(faction.clone())
+                .security(SystemSecurity::Medium)
+                .state(SystemState::Boom)
+                .star(Star::new(1., 1., StarType::Single))
+                .satelites(satelites)
+                .build()
+                .unwrap();
+            market.add_system(&system, 100_000);
+        }
+        market
+    }
+
+    quickcheck! {
+        fn prop_trading_conserves_credits_and_goods(spec: MarketSpec) -> bool {
+            let mut market = build_market(&spec);
+            (0..spec.ticks).all(|_| {
+                market.update();
+                market.violations().is_empty()
+            })
+        }
+    }
+
+    #[test]
+    fn test_conservation_violations() {
+        let before = MarketTotals {
+            credits: 100,
+            stock: vec![(Commodity::new("Food"), 10)].into_iter().collect(),
+        };
+
+        let mut after = before.clone();
+        assert!(check_conservation(&before, &after).is_empty());
+
+        after.credits = 90;
+        after.stock.insert(Commodity::new("Food"), 12);
+        assert_eq!(
+            check_conservation(&before, &after),
+            vec![
+                Violation::CreditsNotConserved {
+                    before: 100,
+                    after: 90,
+                },
+                Violation::GoodsNotConserved {
+                    commodity: Commodity::new("Food"),
+                    before: 10,
+                    after: 12,
+                },
+            ]
+        );
+    }
+}
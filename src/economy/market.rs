@@ -9,26 +9,60 @@ use super::*;
 pub struct Market {
     average_prices: HashMap<Commodity, u64>,
     agents: Vec<Arc<Mutex<Agent>>>,
+    #[serde(skip)]
+    traded_amount: u64,
+    #[serde(skip)]
+    traded_value: u64,
+    #[serde(skip)]
+    violations: Vec<Violation>,
 }
 
 impl Market {
     /// Creates a new empty market.
     pub fn new() -> Self {
         let average_prices: HashMap<Commodity, u64> = Commodity::values()
-            .map(|commodity| (commodity.clone(), 1000))
+            .map(|commodity| {
+                let price = commodity
+                    .definition()
+                    .map_or(1000, |definition| definition.base_price);
+                (commodity.clone(), price)
+            })
             .collect();
 
         Market {
             average_prices,
             agents: vec![],
+            traded_amount: 0,
+            traded_value: 0,
+            violations: vec![],
         }
     }
 
-    /// Returns the agent, if any, which is associated with the given system.
-    pub fn agent(&self, system_hash: u32) -> Option<&Arc<Mutex<Agent>>> {
-        self.agents
-            .iter()
-            .find(|ref agent| agent.lock().unwrap().hash() == system_hash)
+    /// Returns the invariant violations found during the last update.
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+
+    /// Returns statistics gathered for this market during the last update.
+    pub fn statistics(&self) -> MarketStatistics {
+        let (population, credits) =
+            self.agents
+                .iter()
+                .fold((0., 0), |(population, credits), agent| {
+                    let agent = agent.lock().unwrap();
+                    (
+                        population + agent.populations().iter().sum::<f64>(),
+                        credits + agent.credits(),
+                    )
+                });
+
+        MarketStatistics {
+            population,
+            credits,
+            traded_amount: self.traded_amount,
+            traded_value: self.traded_value,
+            average_prices: self.average_prices.clone(),
+        }
     }
 
     /// Returns a reference to all agents.
@@ -36,62 +70,96 @@ impl Market {
         &self.agents
     }
 
-    /// Adds the given system to this market.
-    pub fn add_system(&mut self, system: &System) {
-        self.agents.push(Arc::new(Mutex::new(Agent::new(system))));
+    /// Adds the given system to this market with an agent starting with the
+    /// given credits, returning the index of the agent.
+    pub fn add_system(&mut self, system: &System, credits: u64) -> usize {
+        self.agents
+            .push(Arc::new(Mutex::new(Agent::new(system, credits))));
+        self.agents.len() - 1
     }
 
     /// Attemps to resolve the bids and asks for the given commodity by matching
-    /// the highest asks with the lowest bids performing the transaction.
-    /// Returns the quantity traded.
-    fn resolve_offers(&mut self, commodity: &Commodity, mut bids: Vec<Bid>, mut asks: Vec<Ask>) {
-        bids.sort_unstable_by(|a, b| a.amount.partial_cmp(&b.unit_price).unwrap());
-        asks.sort_unstable_by(|a, b| b.amount.partial_cmp(&a.unit_price).unwrap());
-
-        let mut money_traded = 0;
-        let mut amount_traded = 0;
-
-        // Keep going until we are out of bids or asks.
-        while !bids.is_empty() && !asks.is_empty() {
-            let mut bid = bids.remove(0);
-            let mut ask = asks.remove(0);
-
-            let quantity_traded = bid.amount.min(ask.amount);
-            let clearing_price = (bid.unit_price + ask.unit_price) / 2;
-
-            if quantity_traded > 0 {
-                bid.amount -= quantity_traded;
-                ask.amount -= quantity_traded;
+    /// them in an order book, performing the transaction for each fill.
+    /// Any broken invariants are recorded as violations.
+    fn resolve_offers(&mut self, commodity: &Commodity, bids: Vec<Bid>, asks: Vec<Ask>) {
+        let mut book = OrderBook::new(
+            bids.iter()
+                .enumerate()
+                .map(|(id, bid)| Order::new(id, bid.amount, bid.unit_price))
+                .collect(),
+            asks.iter()
+                .enumerate()
+                .map(|(id, ask)| Order::new(id, ask.amount, ask.unit_price))
+                .collect(),
+        );
 
-                let mut buyer = bid.agent.lock().unwrap();
-                let mut seller = ask.agent.lock().unwrap();
+        // Keep going until no bid crosses any ask.
+        while let Some(best) = book.best_match() {
+            let mut buyer = bids[best.bid].agent.lock().unwrap();
+            let mut seller = asks[best.ask].agent.lock().unwrap();
 
-                // Log stats
-                money_traded += quantity_traded * clearing_price;
-                amount_traded += quantity_traded;
+            // Buyers can not spend more credits than they have.
+            let affordable = buyer
+                .credits()
+                .checked_div(best.price)
+                .unwrap_or(best.amount);
+            let quantity_traded = best.amount.min(affordable);
+            if quantity_traded == 0 {
+                book.cancel_bid();
+                continue;
+            }
+            let trade_value = quantity_traded * best.price;
 
+            // Sellers can not deliver more than they have in stock.
+            let stock = seller.stock(commodity);
+            let transfer = if stock < quantity_traded {
+                Err(Violation::NegativeStock {
+                    commodity: commodity.clone(),
+                    stock,
+                    delta: -(quantity_traded as i64),
+                })
+            } else {
                 // Transfer money.
-                buyer.update_credits(-(money_traded as i64));
-                seller.update_credits(money_traded as i64);
+                buyer.update_credits(-(trade_value as i64)).and_then(|_| {
+                    seller
+                        .update_credits(trade_value as i64)
+                        .map_err(|violation| {
+                            // Refund the buyer.
+                            buyer.update_credits(trade_value as i64).unwrap();
+                            violation
+                        })
+                })
+            };
 
-                // Transfer goods.
-                buyer.update_inventory(&commodity, quantity_traded as i64);
-                seller.update_inventory(&commodity, -(quantity_traded as i64));
+            match transfer {
+                Ok(()) => {
+                    book.fill(quantity_traded);
 
-                // Update agent price beliefs on success
-                buyer.update_price_belief(&commodity, clearing_price, true);
-                seller.update_price_belief(&commodity, clearing_price, true);
-            }
-            // Remove bid or ask if the seller/buyer is out of need or stock
-            if ask.amount > 0 {
-                asks.insert(0, ask);
-            }
+                    // Transfer goods.
+                    buyer.update_inventory(&commodity, quantity_traded as i64);
+                    seller.update_inventory(&commodity, -(quantity_traded as i64));
 
-            if bid.amount > 0 {
-                bids.insert(0, bid);
+                    // Update agent price beliefs on success
+                    buyer.update_price_belief(&commodity, best.price, true);
+                    seller.update_price_belief(&commodity, best.price, true);
+                }
+                Err(violation) => {
+                    self.violations.push(violation);
+                    // Drop the offers, no point in retrying.
+                    book.cancel_bid();
+                    book.cancel_ask();
+                }
             }
         }
 
+        // Log stats
+        let (amount_traded, money_traded) =
+            book.fills().iter().fold((0, 0), |(amount, value), fill| {
+                (amount + fill.amount, value + fill.amount * fill.price)
+            });
+        self.traded_amount += amount_traded;
+        self.traded_value += money_traded;
+
         // Use previous average if no trades were made.
         if amount_traded > 0 {
             let average_price = self.average_prices.get_mut(&commodity).unwrap();
@@ -101,13 +169,15 @@ impl Market {
         let average_price = self.average_prices[&commodity];
 
         // Update price beliefs for unsuccessful bids/asks.
-        for bid in bids {
+        for id in book.unfilled_bids() {
+            let bid = &bids[id];
             bid.agent
                 .lock()
                 .unwrap()
                 .update_price_belief(&bid.commodity, average_price, false);
         }
-        for ask in asks {
+        for id in book.unfilled_asks() {
+            let ask = &asks[id];
             ask.agent
                 .lock()
                 .unwrap()
@@ -120,11 +190,19 @@ impl Updatable for Market {
     /// Update all agents in this market generate and solve transactions to update
     /// prices for commodities.
     fn update(&mut self) {
+        self.traded_amount = 0;
+        self.traded_value = 0;
+
+        self.violations.clear();
+
         // Make agents generate items for this simulation round.
         for agent in &self.agents {
             agent.lock().unwrap().update();
         }
 
+        // Trading should only move credits and goods between agents.
+        let totals_before = MarketTotals::from_agents(&self.agents);
+
         let mut supply = HashMap::new();
         let mut demand = HashMap::new();
 
@@ -159,6 +237,10 @@ impl Updatable for Market {
             self.resolve_offers(commodity, bids, asks);
         }
 
+        let totals_after = MarketTotals::from_agents(&self.agents);
+        self.violations
+            .extend(check_conservation(&totals_before, &totals_after));
+
         // Update supply and demand.
         let demand_supply = Commodity::values()
             .map(|commodity| {
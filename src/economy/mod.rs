@@ -1,73 +1,132 @@
 use rayon::prelude::*;
 use std::{
+    collections::HashMap,
     fmt,
     slice::Iter,
     sync::{Arc, Mutex},
 };
 
-use astronomicals::{system::System, Galaxy};
+use astronomicals::{
+    system::{System, SystemId},
+    Galaxy,
+};
 use game::Updatable;
+pub use resources::types::{Commodity, CommodityDefinition};
+use resources::{fetch_resource, CommodityResource};
 
 mod agent;
+mod diagnostics;
 mod market;
+mod orderbook;
 
 use self::agent::Agent;
+pub use self::diagnostics::Diagnostics;
+use self::diagnostics::{check_conservation, MarketTotals, Violation};
 use self::market::Market;
+use self::orderbook::{Order, OrderBook};
 
 /// Holds the economic state for the entire game.
 #[derive(Default, Serialize, Deserialize)]
 pub struct Economy {
     markets: Vec<Market>,
+    /// Maps each system to the market and agent index responsible for it.
+    index: HashMap<SystemId, (usize, usize)>,
+    #[serde(skip)]
+    diagnostics: Diagnostics,
 }
 
 impl Economy {
-    /// Creates the game economy using the given galaxy.
-    pub fn new(galaxy: &Galaxy) -> Economy {
+    /// Creates the game economy using the given galaxy, where each agent
+    /// starts with the given credits.
+    pub fn new(galaxy: &Galaxy, agent_credits: u64) -> Economy {
         // Create one market per sector.
         let mut markets = vec![];
+        let mut index = HashMap::new();
         for sector in &galaxy.sectors {
             let mut market = Market::new();
             for system in sector
-                .system_locations
+                .system_ids
                 .iter()
-                .map(|loc| galaxy.system(loc).unwrap())
+                .map(|id| galaxy.system(*id).unwrap())
             {
-                market.add_system(system);
+                let agent = market.add_system(system, agent_credits);
+                index.insert(system.id, (markets.len(), agent));
             }
             markets.push(market);
         }
 
-        Economy { markets }
+        Economy {
+            markets,
+            index,
+            diagnostics: Diagnostics::default(),
+        }
+    }
+
+    /// Returns the agent, if any, which is associated with the given system.
+    fn agent(&self, id: SystemId) -> Option<&Arc<Mutex<Agent>>> {
+        self.index
+            .get(&id)
+            .map(|&(market, agent)| &self.markets[market].agents()[agent])
     }
 
     /// Returns the prices for the available commodities the the given system.
     pub fn commodity_prices(&self, system: &System) -> Vec<(Commodity, i64)> {
-        let mut prices = vec![];
-
-        let system_hash = system.location.hash();
-        for market in &self.markets {
-            if let Some(agent) = market.agent(system_hash as u32) {
-                prices = agent.lock().unwrap().prices();
-                break;
-            }
-        }
+        self.agent(system.id)
+            .map(|agent| agent.lock().unwrap().prices())
+            .unwrap_or_default()
+    }
 
-        prices
+    /// Returns the price of the given commodity in the given system, if
+    /// traded there.
+    pub fn price(&self, system: &System, commodity: &Commodity) -> Option<i64> {
+        self.agent(system.id)
+            .and_then(|agent| agent.lock().unwrap().price(commodity))
     }
 
     pub fn populations(&self, system: &System) -> Vec<f64> {
-        let mut populations = vec![];
+        self.agent(system.id)
+            .map(|agent| agent.lock().unwrap().populations())
+            .unwrap_or_default()
+    }
 
-        let system_hash = system.location.hash();
-        for market in &self.markets {
-            if let Some(agent) = market.agent(system_hash as u32) {
-                populations = agent.lock().unwrap().populations();
-                break;
-            }
-        }
+    /// Returns the diagnostics report gathered during the last update.
+    pub fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    /// Returns the economic strength of each market, i.e. the value traded
+    /// during the last update. Markets are in the same order as the galaxy
+    /// sectors.
+    pub fn strengths(&self) -> Vec<f64> {
+        self.markets
+            .iter()
+            .map(|market| market.statistics().traded_value as f64)
+            .collect()
+    }
 
-        populations
+    /// Returns the statistics gathered during the last update for the market
+    /// of the sector with the given index.
+    pub fn market_statistics(&self, sector: usize) -> Option<MarketStatistics> {
+        self.markets.get(sector).map(|market| market.statistics())
     }
+
+    /// Returns the statistics for each market gathered during the last update.
+    pub fn statistics(&self) -> Vec<MarketStatistics> {
+        self.markets
+            .iter()
+            .map(|market| market.statistics())
+            .collect()
+    }
+}
+
+/// Statistics about a single market gathered during the last update.
+#[derive(Serialize, Debug, Clone)]
+pub struct MarketStatistics {
+    pub population: f64,
+    pub credits: u64,
+    pub traded_amount: u64,
+    pub traded_value: u64,
+    pub average_prices: HashMap<Commodity, u64>,
 }
 
 impl Updatable for Economy {
@@ -76,6 +135,20 @@ impl Updatable for Economy {
         self.markets.par_iter_mut().for_each(|market| {
             market.update();
         });
+
+        // Check that the update did not break any economic invariants.
+        let violations = self
+            .markets
+            .iter()
+            .enumerate()
+            .flat_map(|(index, market)| {
+                market
+                    .violations()
+                    .iter()
+                    .map(move |violation| (index, violation.clone()))
+            })
+            .collect();
+        self.diagnostics.record(violations);
     }
 }
 
@@ -97,74 +170,66 @@ pub struct Ask {
     pub unit_price: u64,
 }
 
-/// A tradable and possibly producable commodity
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub enum Commodity {
-    Chemical,
-    ConsumerItem,
-    Food,
-    IllegalDrug,
-    IndustrialMaterial,
-    LegalDrug,
-    Machinery,
-    Medicine,
-    Metal,
-    Mineral,
-    Salvage,
-    Slavery,
-    Technology,
-    Textile,
-    Waste,
-    Weapon,
+lazy_static! {
+    /// Catalogue of all commodities in the game.
+    static ref COMMODITIES: Arc<CommodityResource> = fetch_resource::<CommodityResource>().unwrap();
+
+    /// Ids of all commodities in catalogue order.
+    static ref COMMODITY_IDS: Vec<Commodity> = COMMODITIES
+        .commodities
+        .iter()
+        .map(|definition| definition.id.clone())
+        .collect();
+
+    /// Index of each commodity in the catalogue.
+    static ref COMMODITY_INDEX: HashMap<Commodity, usize> = COMMODITY_IDS
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (id.clone(), index))
+        .collect();
 }
 
 impl Commodity {
+    /// Returns all commodities in the catalogue.
     pub fn values() -> Iter<'static, Commodity> {
-        static COMMODITIES: [Commodity; 16] = [
-            Commodity::Chemical,
-            Commodity::ConsumerItem,
-            Commodity::Food,
-            Commodity::IllegalDrug,
-            Commodity::IndustrialMaterial,
-            Commodity::LegalDrug,
-            Commodity::Machinery,
-            Commodity::Medicine,
-            Commodity::Metal,
-            Commodity::Mineral,
-            Commodity::Salvage,
-            Commodity::Slavery,
-            Commodity::Technology,
-            Commodity::Textile,
-            Commodity::Waste,
-            Commodity::Weapon,
-        ];
-        COMMODITIES.into_iter()
+        COMMODITY_IDS.iter()
+    }
+
+    /// Returns the catalogue definition of the commodity.
+    pub fn definition(&self) -> Option<&'static CommodityDefinition> {
+        COMMODITY_INDEX
+            .get(self)
+            .map(|&index| &COMMODITIES.commodities[index])
     }
 }
 
 impl fmt::Display for Commodity {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match *self {
-                Commodity::Chemical => "Chemicals",
-                Commodity::ConsumerItem => "Consumer Items",
-                Commodity::Food => "Food",
-                Commodity::IllegalDrug => "Illegal Drugs",
-                Commodity::IndustrialMaterial => "Industrial Materials",
-                Commodity::LegalDrug => "Legal Drugs",
-                Commodity::Machinery => "Machinery",
-                Commodity::Medicine => "Medicine",
-                Commodity::Metal => "Metals",
-                Commodity::Mineral => "Minerals",
-                Commodity::Salvage => "Salvage",
-                Commodity::Slavery => "Slaves",
-                Commodity::Technology => "Technology",
-                Commodity::Textile => "Textiles",
-                Commodity::Waste => "Waste",
-                Commodity::Weapon => "Weapons",
-            }
-        )
+        match self.definition() {
+            Some(definition) => write!(f, "{}", definition.name),
+            None => write!(f, "{}", self.id()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogue() {
+        assert!(Commodity::values().len() > 0);
+        for (index, commodity) in Commodity::values().enumerate() {
+            let definition = commodity.definition().unwrap();
+            assert_eq!(&definition.id, commodity);
+            assert_eq!(COMMODITY_INDEX[commodity], index);
+        }
+        let drugs = Commodity::new("IllegalDrug");
+        assert_eq!(drugs.to_string(), "Illegal Drugs");
+        assert!(!drugs.definition().unwrap().legal);
+
+        let unknown = Commodity::new("Unobtainium");
+        assert!(unknown.definition().is_none());
+        assert_eq!(unknown.to_string(), "Unobtainium");
     }
 }
@@ -0,0 +1,224 @@
+use std::cmp::Reverse;
+
+/// A single offer in the order book, identified by the index of the bid or ask
+/// it was created from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Order {
+    pub id: usize,
+    pub amount: u64,
+    pub unit_price: u64,
+}
+
+impl Order {
+    pub fn new(id: usize, amount: u64, unit_price: u64) -> Self {
+        Order {
+            id,
+            amount,
+            unit_price,
+        }
+    }
+}
+
+/// A potential trade between the best bid and the best ask in the book.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Match {
+    pub bid: usize,
+    pub ask: usize,
+    pub amount: u64,
+    pub price: u64,
+}
+
+/// A trade which has been carried out at the given clearing price.
+pub type Fill = Match;
+
+/// Order book for a single commodity which matches bids against asks using
+/// price priority, breaking ties by the order in which offers were placed.
+#[derive(Debug)]
+pub struct OrderBook {
+    bids: Vec<Order>,
+    asks: Vec<Order>,
+    next_bid: usize,
+    next_ask: usize,
+    fills: Vec<Fill>,
+}
+
+impl OrderBook {
+    /// Creates a new order book from the given bids and asks.
+    pub fn new(mut bids: Vec<Order>, mut asks: Vec<Order>) -> Self {
+        // Stable sorts keep offers with the same price in placement order.
+        bids.sort_by_key(|order| Reverse(order.unit_price));
+        asks.sort_by_key(|order| order.unit_price);
+
+        OrderBook {
+            bids,
+            asks,
+            next_bid: 0,
+            next_ask: 0,
+            fills: vec![],
+        }
+    }
+
+    /// Returns the trade between the highest bid and the lowest ask, if the
+    /// bid price crosses the ask price. Trades clear at the midpoint price.
+    pub fn best_match(&self) -> Option<Match> {
+        let bid = self.bids.get(self.next_bid)?;
+        let ask = self.asks.get(self.next_ask)?;
+        if bid.unit_price < ask.unit_price {
+            return None;
+        }
+
+        Some(Match {
+            bid: bid.id,
+            ask: ask.id,
+            amount: bid.amount.min(ask.amount),
+            price: ask.unit_price + (bid.unit_price - ask.unit_price) / 2,
+        })
+    }
+
+    /// Carries out the best match for the given amount, which may be less than
+    /// the matched amount, leaving the rest of the orders in the book.
+    pub fn fill(&mut self, amount: u64) -> Option<Fill> {
+        let mut fill = self.best_match()?;
+        fill.amount = fill.amount.min(amount);
+
+        self.bids[self.next_bid].amount -= fill.amount;
+        self.asks[self.next_ask].amount -= fill.amount;
+        if self.bids[self.next_bid].amount == 0 {
+            self.next_bid += 1;
+        }
+        if self.asks[self.next_ask].amount == 0 {
+            self.next_ask += 1;
+        }
+
+        if fill.amount > 0 {
+            self.fills.push(fill);
+        }
+        Some(fill)
+    }
+
+    /// Removes the best bid from the book without trading it.
+    pub fn cancel_bid(&mut self) {
+        self.next_bid = (self.next_bid + 1).min(self.bids.len());
+    }
+
+    /// Removes the best ask from the book without trading it.
+    pub fn cancel_ask(&mut self) {
+        self.next_ask = (self.next_ask + 1).min(self.asks.len());
+    }
+
+    /// Returns all trades carried out so far, in order.
+    pub fn fills(&self) -> &[Fill] {
+        &self.fills
+    }
+
+    /// Returns the ids of bids which were not completely filled.
+    pub fn unfilled_bids(&self) -> Vec<usize> {
+        unfilled(&self.bids)
+    }
+
+    /// Returns the ids of asks which were not completely filled.
+    pub fn unfilled_asks(&self) -> Vec<usize> {
+        unfilled(&self.asks)
+    }
+}
+
+fn unfilled(orders: &[Order]) -> Vec<usize> {
+    orders
+        .iter()
+        .filter(|order| order.amount > 0)
+        .map(|order| order.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Matches orders until the book no longer crosses, returning all fills.
+    fn clear(book: &mut OrderBook) -> Vec<Fill> {
+        while book.fill(u64::MAX).is_some() {}
+        book.fills().to_vec()
+    }
+
+    fn fill(bid: usize, ask: usize, amount: u64, price: u64) -> Fill {
+        Fill {
+            bid,
+            ask,
+            amount,
+            price,
+        }
+    }
+
+    #[test]
+    fn test_no_trade_without_crossing() {
+        let mut book = OrderBook::new(vec![Order::new(0, 10, 90)], vec![Order::new(0, 10, 100)]);
+        assert_eq!(book.best_match(), None);
+        assert!(clear(&mut book).is_empty());
+        assert_eq!(book.unfilled_bids(), vec![0]);
+        assert_eq!(book.unfilled_asks(), vec![0]);
+    }
+
+    #[test]
+    fn test_price_priority() {
+        let bids = vec![
+            Order::new(0, 5, 100),
+            Order::new(1, 5, 120),
+            Order::new(2, 5, 110),
+        ];
+        let asks = vec![Order::new(0, 5, 105), Order::new(1, 5, 95)];
+        let mut book = OrderBook::new(bids, asks);
+        assert_eq!(
+            clear(&mut book),
+            &[fill(1, 1, 5, 107), fill(2, 0, 5, 107)][..]
+        );
+        assert_eq!(book.unfilled_bids(), vec![0]);
+        assert!(book.unfilled_asks().is_empty());
+    }
+
+    #[test]
+    fn test_ties_keep_placement_order() {
+        let bids = vec![Order::new(0, 3, 100), Order::new(1, 3, 100)];
+        let asks = vec![Order::new(0, 4, 100)];
+        let mut book = OrderBook::new(bids, asks);
+        assert_eq!(
+            clear(&mut book),
+            &[fill(0, 0, 3, 100), fill(1, 0, 1, 100)][..]
+        );
+        assert_eq!(book.unfilled_bids(), vec![1]);
+    }
+
+    #[test]
+    fn test_partial_fills() {
+        let bids = vec![Order::new(0, 10, 200)];
+        let asks = vec![
+            Order::new(0, 4, 100),
+            Order::new(1, 4, 150),
+            Order::new(2, 4, 250),
+        ];
+        let mut book = OrderBook::new(bids, asks);
+
+        // Only part of the match may be carried out.
+        assert_eq!(book.fill(1), Some(fill(0, 0, 1, 150)));
+        assert_eq!(
+            clear(&mut book),
+            &[fill(0, 0, 1, 150), fill(0, 0, 3, 150), fill(0, 1, 4, 175)][..]
+        );
+        assert_eq!(book.unfilled_bids(), vec![0]);
+        assert_eq!(book.unfilled_asks(), vec![2]);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let bids = vec![Order::new(0, 5, 100), Order::new(1, 5, 90)];
+        let asks = vec![Order::new(0, 5, 80)];
+        let mut book = OrderBook::new(bids, asks);
+        book.cancel_bid();
+        assert_eq!(clear(&mut book), &[fill(1, 0, 5, 85)][..]);
+        assert_eq!(book.unfilled_bids(), vec![0]);
+
+        // Cancelling an empty book does nothing.
+        book.cancel_bid();
+        book.cancel_ask();
+        assert_eq!(book.best_match(), None);
+    }
+}
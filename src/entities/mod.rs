@@ -1,33 +1,48 @@
 use rand::Rng;
 use statrs::distribution::{Categorical, Distribution};
-use std::fmt;
 
-/// Represents a single Faction which is assigned on Sector level.
-#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq)]
-pub enum Faction {
-    Empire,
-    Federation,
-    Cartel,
-    Independent,
-}
+use astronomicals::system::SystemSecurity;
+use economy::Commodity;
+use game_config::SecurityWeights;
+
+pub mod relations;
+pub mod standing;
 
-impl Faction {
-    /// Generate a random faction according to the "distribution".
-    pub fn random_faction<R: Rng>(gen: &mut R) -> Faction {
-        let probs = Categorical::new(&[15., 45., 30., 10.]).unwrap();
+pub use self::relations::{Relations, Stance};
+pub use self::standing::{Standing, StandingEvent};
+pub use resources::types::{Faction, FactionDefinition};
 
-        match probs.sample::<R>(gen) as usize {
-            0 => Faction::Cartel,
-            1 => Faction::Empire,
-            2 => Faction::Federation,
-            3 => Faction::Independent,
-            _ => Faction::Independent,
+impl FactionDefinition {
+    /// Returns true if the given commodity may be traded in the faction's
+    /// systems, falling back to the legality of the commodity.
+    pub fn is_legal(&self, commodity: &Commodity) -> bool {
+        if self.illegal.contains(commodity) {
+            false
+        } else if self.legal.contains(commodity) {
+            true
+        } else {
+            commodity
+                .definition()
+                .map_or(true, |definition| definition.legal)
         }
     }
-}
 
-impl fmt::Display for Faction {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+    /// Generate a random security level according to the faction's
+    /// distribution, scaled by the given weights.
+    pub fn random_security<R: Rng>(&self, gen: &mut R, scale: &SecurityWeights) -> SystemSecurity {
+        let levels = [
+            SystemSecurity::Anarchy,
+            SystemSecurity::Low,
+            SystemSecurity::Medium,
+            SystemSecurity::High,
+        ];
+        let weights = levels
+            .iter()
+            .map(|level| *self.security.get(level).unwrap_or(&0.) * scale.get(level))
+            .collect::<Vec<_>>();
+        match Categorical::new(&weights) {
+            Ok(probs) => levels[probs.sample::<R>(gen) as usize].clone(),
+            Err(_) => SystemSecurity::Low,
+        }
     }
 }
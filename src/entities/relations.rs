@@ -0,0 +1,209 @@
+use std::{collections::HashMap, fmt};
+
+use entities::Faction;
+use resources::FactionResource;
+
+/// Stance between two factions derived from their relation.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stance {
+    War,
+    Hostile,
+    Neutral,
+    Allied,
+}
+
+impl fmt::Display for Stance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Symmetric matrix of relations between factions, ranging from -100 to 100.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Relations {
+    values: HashMap<(Faction, Faction), i32>,
+    /// Relations factions settle at when they share no borders.
+    baselines: HashMap<(Faction, Faction), i32>,
+    factions: Vec<Faction>,
+}
+
+impl Relations {
+    const MIN: i32 = -100;
+    const MAX: i32 = 100;
+
+    /// Relation lost for each sector border shared by two factions.
+    const BORDER_TENSION: i32 = 2;
+
+    /// Creates the relations between the factions of the given resource,
+    /// starting out at their initial relations.
+    pub fn new(resource: &FactionResource) -> Relations {
+        let mut baselines = HashMap::new();
+        for definition in &resource.factions {
+            for (other, &relation) in &definition.relations {
+                baselines.insert(Relations::key(&definition.id, other), relation);
+            }
+        }
+        let mut factions = resource
+            .factions
+            .iter()
+            .map(|definition| definition.id.clone())
+            .collect::<Vec<_>>();
+        factions.sort();
+
+        Relations {
+            values: HashMap::new(),
+            baselines,
+            factions,
+        }
+    }
+
+    /// Returns all factions with relations.
+    pub fn factions(&self) -> &[Faction] {
+        &self.factions
+    }
+
+    /// Returns the relation factions settle at when they share no borders.
+    fn baseline(&self, a: &Faction, b: &Faction) -> i32 {
+        *self.baselines.get(&Relations::key(a, b)).unwrap_or(&0)
+    }
+
+    /// Orders the pair so that each relation is only stored once.
+    fn key(a: &Faction, b: &Faction) -> (Faction, Faction) {
+        if a <= b {
+            (a.clone(), b.clone())
+        } else {
+            (b.clone(), a.clone())
+        }
+    }
+
+    /// Returns the relation between the given factions, factions are always
+    /// allied with themselves.
+    pub fn relation(&self, a: &Faction, b: &Faction) -> i32 {
+        if a == b {
+            return Relations::MAX;
+        }
+        *self
+            .values
+            .get(&Relations::key(a, b))
+            .unwrap_or(&self.baseline(a, b))
+    }
+
+    /// Returns the stance between the given factions.
+    pub fn stance(&self, a: &Faction, b: &Faction) -> Stance {
+        match self.relation(a, b) {
+            -100..=-75 => Stance::War,
+            -74..=-25 => Stance::Hostile,
+            -24..=50 => Stance::Neutral,
+            _ => Stance::Allied,
+        }
+    }
+
+    /// Changes the relation between the given factions by the given amount.
+    pub fn change(&mut self, a: &Faction, b: &Faction, delta: i32) {
+        if a != b {
+            let relation = (self.relation(a, b) + delta).clamp(Relations::MIN, Relations::MAX);
+            self.values.insert(Relations::key(a, b), relation);
+        }
+    }
+
+    /// Moves each relation one step towards its baseline worsened by the
+    /// number of sector borders the factions share.
+    pub fn update(&mut self, borders: &HashMap<(Faction, Faction), i32>) {
+        for (index, a) in self.factions.iter().enumerate() {
+            for b in self.factions.iter().skip(index + 1) {
+                let key = Relations::key(a, b);
+                let target = (self.baseline(a, b)
+                    - borders.get(&key).unwrap_or(&0) * Relations::BORDER_TENSION)
+                    .max(Relations::MIN);
+                let current = self.relation(a, b);
+                self.values
+                    .insert(key, current + (target - current).signum());
+            }
+        }
+    }
+
+    /// Counts the borders between sectors of different factions, given as
+    /// pairs of neighbouring sector factions.
+    pub fn count_borders<'a, I>(pairs: I) -> HashMap<(Faction, Faction), i32>
+    where
+        I: Iterator<Item = (&'a Faction, &'a Faction)>,
+    {
+        pairs
+            .filter(|&(a, b)| a != b)
+            .fold(HashMap::new(), |mut borders, (a, b)| {
+                *borders.entry(Relations::key(a, b)).or_insert(0) += 1;
+                borders
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use resources::fetch_resource;
+
+    fn relations() -> Relations {
+        Relations::new(&fetch_resource::<FactionResource>().unwrap())
+    }
+
+    #[test]
+    fn test_symmetric_relations() {
+        let relations = relations();
+        assert_eq!(
+            relations.relation(&Faction::new("Cartel"), &Faction::new("Empire")),
+            relations.relation(&Faction::new("Empire"), &Faction::new("Cartel"))
+        );
+        assert_eq!(
+            relations.stance(&Faction::new("Empire"), &Faction::new("Cartel")),
+            Stance::War
+        );
+        assert_eq!(
+            relations.stance(&Faction::new("Empire"), &Faction::new("Empire")),
+            Stance::Allied
+        );
+    }
+
+    #[test]
+    fn test_borders_cause_tension() {
+        let mut relations = relations();
+        let pairs = [
+            (Faction::new("Empire"), Faction::new("Federation")),
+            (Faction::new("Federation"), Faction::new("Empire")),
+            (Faction::new("Empire"), Faction::new("Empire")),
+        ];
+        let borders = Relations::count_borders(pairs.iter().map(|(a, b)| (a, b)));
+        assert_eq!(
+            borders[&(Faction::new("Empire"), Faction::new("Federation"))],
+            2
+        );
+
+        // Relations worsen until the target is reached.
+        for _ in 0..10 {
+            relations.update(&borders);
+        }
+        assert_eq!(
+            relations.relation(&Faction::new("Federation"), &Faction::new("Empire")),
+            -64
+        );
+        assert_eq!(
+            relations.relation(&Faction::new("Federation"), &Faction::new("Cartel")),
+            -50
+        );
+    }
+
+    #[test]
+    fn test_relations_drift_to_baseline() {
+        let mut relations = relations();
+        let (empire, federation) = (Faction::new("Empire"), Faction::new("Federation"));
+        let baseline = relations.relation(&empire, &federation);
+        relations.change(&empire, &federation, 10);
+
+        // Without borders, relations return one step at the time.
+        relations.update(&HashMap::new());
+        assert_eq!(relations.relation(&empire, &federation), baseline + 9);
+        for _ in 0..20 {
+            relations.update(&HashMap::new());
+        }
+        assert_eq!(relations.relation(&empire, &federation), baseline);
+    }
+}
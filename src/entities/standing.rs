@@ -0,0 +1,211 @@
+use std::{collections::HashMap, fmt};
+
+use astronomicals::system::{System, SystemId};
+use entities::Faction;
+use game::Updatable;
+
+pub use resources::types::Rank;
+
+/// Represents a level of reputation, ranging from -1000 to 1000.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub struct Reputation(i32);
+
+impl Reputation {
+    const MIN: i32 = -1000;
+    const MAX: i32 = 1000;
+
+    /// Changes the reputation level by the given amount, within bounds.
+    pub fn change(&mut self, delta: i32) {
+        self.0 = (self.0 + delta).clamp(Reputation::MIN, Reputation::MAX);
+    }
+
+    /// Returns the rank corresponding to the reputation level.
+    pub fn rank(&self) -> Rank {
+        match self.0 {
+            -1000..=-300 => Rank::Hostile,
+            -299..=-100 => Rank::Unfriendly,
+            -99..=100 => Rank::Neutral,
+            101..=300 => Rank::Friendly,
+            301..=1000 => Rank::Allied,
+            _ => Rank::Neutral,
+        }
+    }
+}
+
+impl Updatable for Reputation {
+    /// Updates the reputation level, one time step.
+    fn update(&mut self) {
+        // "Extreme" repuation levels converges towards lower levels.
+        self.0 += match self.0 {
+            -1000..=-300 => 5,
+            300..=1000 => -5,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for Reputation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.rank())
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Player actions which affect standing with a system and its faction.
+/// Missions and combat do not exist yet, so they do not affect standing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StandingEvent {
+    /// Trade of the given value in credits.
+    Trade(u32),
+    /// Getting caught with illegal cargo.
+    SmugglingBust,
+}
+
+impl StandingEvent {
+    /// Returns the change in reputation with the system where the event
+    /// took place, the faction is affected by half as much.
+    fn reputation_change(&self) -> i32 {
+        match *self {
+            StandingEvent::Trade(value) => (value / 1000).clamp(1, 20) as i32,
+            StandingEvent::SmugglingBust => -100,
+        }
+    }
+}
+
+/// Player standing with each faction and system.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Standing {
+    factions: HashMap<Faction, Reputation>,
+    systems: HashMap<SystemId, Reputation>,
+}
+
+impl Standing {
+    /// Discount given on prices in systems where the player is allied.
+    const ALLIED_DISCOUNT: f64 = 0.1;
+
+    /// Returns the reputation with the given faction.
+    pub fn faction(&self, faction: &Faction) -> Reputation {
+        self.factions.get(faction).cloned().unwrap_or_default()
+    }
+
+    /// Returns the reputation with the given system.
+    pub fn system(&self, system: SystemId) -> Reputation {
+        self.systems.get(&system).cloned().unwrap_or_default()
+    }
+
+    /// Returns the rank in the given system, which is the worst of the system
+    /// and faction ranks.
+    pub fn rank(&self, system: &System) -> Rank {
+        self.system(system.id)
+            .rank()
+            .min(self.faction(&system.faction).rank())
+    }
+
+    /// Records the given event which took place in the given system.
+    pub fn record(&mut self, event: StandingEvent, system: &System) {
+        let change = event.reputation_change();
+        self.systems.entry(system.id).or_default().change(change);
+        self.factions
+            .entry(system.faction.clone())
+            .or_default()
+            .change(change / 2);
+    }
+
+    /// Returns true if the player is allowed to dock in the given system.
+    pub fn can_dock(&self, system: &System) -> bool {
+        self.rank(system) > Rank::Hostile
+    }
+
+    /// Returns the factor applied to prices in the given system.
+    pub fn price_factor(&self, system: &System) -> f64 {
+        match self.rank(system) {
+            Rank::Allied => 1. - Standing::ALLIED_DISCOUNT,
+            _ => 1.,
+        }
+    }
+}
+
+impl Updatable for Standing {
+    /// Lets extreme reputation levels decay, one time step.
+    fn update(&mut self) {
+        for reputation in self.factions.values_mut().chain(self.systems.values_mut()) {
+            reputation.update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        star::{Star, StarType},
+        system::{SystemBuilder, SystemSecurity, SystemState},
+    };
+    use utils::Point;
+
+    fn system(id: u32, faction: Faction) -> System {
+        SystemBuilder::default()
+            .id(SystemId(id))
+            .location(Point::origin())
+            .name(String::from("Test"))
+            .faction(faction)
+            .security(SystemSecurity::Medium)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reputation_bounds() {
+        let mut reputation = Reputation::default();
+        assert_eq!(reputation.rank(), Rank::Neutral);
+        reputation.change(-5000);
+        assert_eq!(reputation, Reputation(-1000));
+        assert_eq!(reputation.rank(), Rank::Hostile);
+        reputation.change(5000);
+        assert_eq!(reputation.rank(), Rank::Allied);
+    }
+
+    #[test]
+    fn test_busts_refuse_docking() {
+        let home = system(0, Faction::new("Empire"));
+        let other = system(1, Faction::new("Empire"));
+        let mut standing = Standing::default();
+        for _ in 0..3 {
+            standing.record(StandingEvent::SmugglingBust, &home);
+        }
+
+        assert_eq!(standing.system(home.id).rank(), Rank::Hostile);
+        assert_eq!(
+            standing.faction(&Faction::new("Empire")).rank(),
+            Rank::Unfriendly
+        );
+        assert!(!standing.can_dock(&home));
+        assert!(standing.can_dock(&other));
+    }
+
+    #[test]
+    fn test_allied_discount() {
+        let home = system(0, Faction::new("Federation"));
+        let mut standing = Standing::default();
+        for _ in 0..16 {
+            standing.record(StandingEvent::Trade(20_000), &home);
+        }
+        assert_eq!(standing.system(home.id).rank(), Rank::Allied);
+        // The faction is not yet allied.
+        assert_eq!(standing.price_factor(&home), 1.);
+
+        for _ in 0..16 {
+            standing.record(StandingEvent::Trade(20_000), &home);
+        }
+        assert_eq!(standing.rank(&home), Rank::Allied);
+        assert_eq!(standing.price_factor(&home), 0.9);
+    }
+}
@@ -1,4 +1,5 @@
 use game::Game;
+use player::Docking;
 use std::{
     io,
     sync::{
@@ -17,8 +18,15 @@ pub enum Event {
     Update,
     Travel,
     Refuel,
+    Repair,
     Dock(usize),
     Undock(usize),
+    TogglePause,
+    AdvanceDays(i64),
+    CatchUpProgress(u32, u32),
+    DockingRefused,
+    /// The given units of illegal cargo were confiscated when docking.
+    SmugglingBust(u32),
     AutosaveStarted,
     AutosaveCompleted,
 }
@@ -91,21 +99,47 @@ pub fn add_player_handler(state: Arc<Game>) {
         loop {
             let evt = rx.recv().unwrap();
             match evt {
-                Event::Travel | Event::Dock(_) | Event::Undock(_) | Event::Refuel => {
-                    match evt {
-                        Event::Dock(planet_id) => {
+                // The player may not act while time is being advanced.
+                Event::Travel
+                | Event::Dock(_)
+                | Event::Undock(_)
+                | Event::Refuel
+                | Event::Repair
+                    if state.is_catching_up() => {}
+                Event::Travel
+                | Event::Dock(_)
+                | Event::Undock(_)
+                | Event::Refuel
+                | Event::Repair => {
+                    // Look up the current system without holding the player lock.
+                    let system_id = state.player.lock().unwrap().system();
+                    let system = state.galaxy.lock().unwrap().system(system_id).cloned();
+
+                    match (evt, system) {
+                        (Event::Dock(planet_id), Some(system)) => {
                             if let Ok(mut player) = state.player.lock() {
-                                player.dock(planet_id);
+                                match player.dock(planet_id, &system) {
+                                    Docking::Refused => sx.send(Event::DockingRefused).unwrap(),
+                                    Docking::Busted(amount) => {
+                                        sx.send(Event::SmugglingBust(amount)).unwrap()
+                                    }
+                                    Docking::Docked => {}
+                                }
                             }
                         }
-                        Event::Undock(_) => {
+                        (Event::Undock(_), _) => {
                             if let Ok(mut player) = state.player.lock() {
                                 player.undock();
                             }
                         }
-                        Event::Refuel => {
+                        (Event::Refuel, Some(system)) => {
+                            if let Ok(mut player) = state.player.lock() {
+                                player.refuel(&system);
+                            }
+                        }
+                        (Event::Repair, Some(system)) => {
                             if let Ok(mut player) = state.player.lock() {
-                                player.refuel();
+                                player.repair(&system);
                             }
                         }
                         _ => {}
@@ -121,6 +155,25 @@ pub fn add_player_handler(state: Arc<Game>) {
     });
 }
 
+/// Start listener for events that should control the game clock.
+pub fn add_clock_handler(state: Arc<Game>) {
+    let rx = HANDLER.recv_handle();
+    let sx = HANDLER.send_handle();
+    spawn(move || loop {
+        match rx.recv().unwrap() {
+            Event::TogglePause => {
+                state.toggle_pause();
+                sx.send(Event::Update).unwrap();
+            }
+            Event::AdvanceDays(days) => {
+                state.advance_days(days);
+                sx.send(Event::Update).unwrap();
+            }
+            _ => {}
+        }
+    });
+}
+
 /// Start listener for events that should run an update on the game state.
 pub fn add_update_handler(state: Arc<Game>) {
     let sx = HANDLER.send_handle();
@@ -128,8 +181,16 @@ pub fn add_update_handler(state: Arc<Game>) {
     let mut beginning_park = Instant::now();
     let mut timeout_remaining = timeout_freq;
     spawn(move || {
+        // Report progress when catching up on time passed.
+        let progress_sx = sx.clone();
+        let report_progress = move |done, total| {
+            progress_sx
+                .send(Event::CatchUpProgress(done, total))
+                .unwrap();
+        };
+
         // Update right away first time.
-        state.update();
+        state.update_with_progress(&report_progress);
         sx.send(Event::Update).unwrap();
         loop {
             // Wait uptil 10s, must check.
@@ -137,7 +198,7 @@ pub fn add_update_handler(state: Arc<Game>) {
             let elapsed = beginning_park.elapsed();
             // If timeout reached, send event and reset timer.
             if elapsed >= timeout_freq {
-                state.update();
+                state.update_with_progress(&report_progress);
                 sx.send(Event::Update).unwrap();
                 timeout_remaining = timeout_freq;
                 beginning_park = Instant::now();
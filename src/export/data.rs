@@ -0,0 +1,472 @@
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json;
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, Write},
+};
+
+use super::filter::Filter;
+use astronomicals::{system::SystemId, Galaxy};
+use utils::Point;
+
+/// Formats supported by the data export.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataFormat {
+    Csv,
+    Json,
+    GeoJson,
+}
+
+impl DataFormat {
+    /// Guesses the format from the extension of the given file name.
+    pub fn from_path(path: &str) -> Option<DataFormat> {
+        let path = path.to_lowercase();
+        if path.ends_with(".csv") {
+            Some(DataFormat::Csv)
+        } else if path.ends_with(".geojson") {
+            Some(DataFormat::GeoJson)
+        } else if path.ends_with(".json") {
+            Some(DataFormat::Json)
+        } else {
+            None
+        }
+    }
+}
+
+/// What each exported record describes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Systems,
+    Planets,
+}
+
+impl Level {
+    /// Returns the names of the fields of each record, in order.
+    pub fn fields(&self) -> &'static [&'static str] {
+        match *self {
+            Level::Systems => &[
+                "id",
+                "name",
+                "x",
+                "y",
+                "sector",
+                "sector_name",
+                "faction",
+                "security",
+                "state",
+                "star_type",
+                "star_class",
+                "star_temperature",
+                "star_radius",
+                "star_mass",
+                "star_luminosity",
+                "companion_class",
+                "planets",
+                "stations",
+                "belts",
+            ],
+            Level::Planets => &[
+                "system_id",
+                "system",
+                "x",
+                "y",
+                "sector",
+                "faction",
+                "name",
+                "mass",
+                "radius",
+                "gravity",
+                "orbit_distance",
+                "orbital_period",
+                "surface_temperature",
+                "planet_type",
+                "economy",
+                "moons",
+            ],
+        }
+    }
+}
+
+/// Value of a single exported field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    Integer(i64),
+    Number(f64),
+    Text(String),
+}
+
+impl Field {
+    /// Returns the numeric value of the field, if any.
+    pub fn as_number(&self) -> Option<f64> {
+        match *self {
+            Field::Integer(value) => Some(value as f64),
+            Field::Number(value) => Some(value),
+            Field::Text(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Field::Integer(value) => write!(f, "{}", value),
+            Field::Number(value) => write!(f, "{}", value),
+            Field::Text(ref value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Field::Integer(value) => serializer.serialize_i64(value),
+            Field::Number(value) => serializer.serialize_f64(value),
+            Field::Text(ref value) => serializer.serialize_str(value),
+        }
+    }
+}
+
+/// A single exported system or planet, as named fields in a fixed order.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub location: Point,
+    fields: Vec<(&'static str, Field)>,
+}
+
+impl Record {
+    fn new(location: Point) -> Self {
+        Record {
+            location,
+            fields: vec![],
+        }
+    }
+
+    fn integer(mut self, name: &'static str, value: i64) -> Self {
+        self.fields.push((name, Field::Integer(value)));
+        self
+    }
+
+    fn number(mut self, name: &'static str, value: f64) -> Self {
+        self.fields.push((name, Field::Number(value)));
+        self
+    }
+
+    fn text<T: ToString>(mut self, name: &'static str, value: T) -> Self {
+        self.fields.push((name, Field::Text(value.to_string())));
+        self
+    }
+
+    /// Returns the value of the field with the given name.
+    pub fn get(&self, name: &str) -> Option<&Field> {
+        self.fields
+            .iter()
+            .find(|&&(field, _)| field == name)
+            .map(|&(_, ref value)| value)
+    }
+}
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.fields.len()))?;
+        for &(name, ref value) in &self.fields {
+            map.serialize_entry(name, value)?;
+        }
+        map.end()
+    }
+}
+
+/// GeoJSON feature locating a record in the galaxy plane.
+#[derive(Serialize)]
+struct Feature<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    geometry: Geometry,
+    properties: &'a Record,
+}
+
+/// GeoJSON point geometry.
+#[derive(Serialize)]
+struct Geometry {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    coordinates: [f64; 2],
+}
+
+/// GeoJSON collection of all features.
+#[derive(Serialize)]
+struct FeatureCollection<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    features: Vec<Feature<'a>>,
+}
+
+/// Creates one record per system or planet of the galaxy, ordered by system
+/// id. Enumerations are given by their variant names.
+pub fn records(galaxy: &Galaxy, level: Level) -> Vec<Record> {
+    let sectors = galaxy
+        .sectors
+        .iter()
+        .enumerate()
+        .flat_map(|(index, sector)| sector.system_ids.iter().map(move |&id| (id, index)))
+        .collect::<HashMap<SystemId, usize>>();
+    let sector = |id| sectors.get(&id).map_or(-1, |&index| index as i64);
+    let sector_name = |id| {
+        sectors
+            .get(&id)
+            .map_or("", |&index| galaxy.sectors[index].name.as_str())
+    };
+
+    let mut systems = galaxy.systems().collect::<Vec<_>>();
+    systems.sort_by_key(|system| system.id);
+
+    match level {
+        Level::Systems => systems
+            .into_iter()
+            .map(|system| {
+                Record::new(system.location)
+                    .integer("id", i64::from(system.id.0))
+                    .text("name", &system.name)
+                    .number("x", system.location.x)
+                    .number("y", system.location.y)
+                    .integer("sector", sector(system.id))
+                    .text("sector_name", sector_name(system.id))
+                    .text("faction", &system.faction)
+                    .text("security", format!("{:?}", system.security))
+                    .text("state", format!("{:?}", system.state))
+                    .text("star_type", format!("{:?}", system.star.startype))
+                    .text("star_class", format!("{:?}", system.star.class))
+                    .number("star_temperature", system.star.temperature)
+                    .number("star_radius", system.star.radius)
+                    .number("star_mass", system.star.mass)
+                    .number("star_luminosity", system.star.luminosity)
+                    .text(
+                        "companion_class",
+                        system
+                            .star
+                            .companion
+                            .as_ref()
+                            .map_or(String::new(), |star| format!("{:?}", star.class)),
+                    )
+                    .integer("planets", system.satelites.len() as i64)
+                    .integer("stations", system.stations.len() as i64)
+                    .integer("belts", system.belts.len() as i64)
+            })
+            .collect(),
+        Level::Planets => systems
+            .into_iter()
+            .flat_map(|system| {
+                system.satelites.iter().map(move |planet| {
+                    Record::new(system.location)
+                        .integer("system_id", i64::from(system.id.0))
+                        .text("system", &system.name)
+                        .number("x", system.location.x)
+                        .number("y", system.location.y)
+                        .integer("sector", sector(system.id))
+                        .text("faction", &system.faction)
+                        .text("name", &planet.name)
+                        .number("mass", planet.mass)
+                        .number("radius", planet.radius)
+                        .number("gravity", planet.gravity)
+                        .number("orbit_distance", planet.orbit_distance)
+                        .number("orbital_period", planet.orbital_period)
+                        .number("surface_temperature", planet.surface_temperature)
+                        .text("planet_type", format!("{:?}", planet.planet_type))
+                        .text("economy", format!("{:?}", planet.economic_type))
+                        .integer("moons", planet.moons.len() as i64)
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Writes all systems or planets of the galaxy matching the filter, if any, to
+/// the given output in the given format.
+pub fn export_data<W: Write>(
+    galaxy: &Galaxy,
+    level: Level,
+    filter: Option<&Filter>,
+    format: DataFormat,
+    output: &mut W,
+) -> io::Result<()> {
+    let names = level.fields();
+
+    // Catch misspelled fields rather than silently exporting nothing.
+    if let Some(filter) = filter {
+        if let Some(field) = filter
+            .fields()
+            .into_iter()
+            .find(|field| !names.contains(field))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unknown field in filter: {}", field),
+            ));
+        }
+    }
+    let records = records(galaxy, level)
+        .into_iter()
+        .filter(|record| filter.map_or(true, |filter| filter.matches(record)))
+        .collect::<Vec<_>>();
+
+    match format {
+        DataFormat::Csv => write_csv(names, &records, output)?,
+        DataFormat::Json => {
+            serde_json::to_writer_pretty(&mut *output, &records)?;
+            writeln!(output)?;
+        }
+        DataFormat::GeoJson => {
+            let collection = FeatureCollection {
+                kind: "FeatureCollection",
+                features: records
+                    .iter()
+                    .map(|record| Feature {
+                        kind: "Feature",
+                        geometry: Geometry {
+                            kind: "Point",
+                            coordinates: [record.location.x, record.location.y],
+                        },
+                        properties: record,
+                    })
+                    .collect(),
+            };
+            serde_json::to_writer_pretty(&mut *output, &collection)?;
+            writeln!(output)?;
+        }
+    }
+    output.flush()
+}
+
+/// Writes the records as CSV with a header row of the given field names.
+fn write_csv<W: Write>(names: &[&str], records: &[Record], output: &mut W) -> io::Result<()> {
+    writeln!(output, "{}", names.join(","))?;
+    for record in records {
+        let row = record
+            .fields
+            .iter()
+            .map(|&(_, ref value)| quote(&value.to_string()))
+            .collect::<Vec<_>>();
+        writeln!(output, "{}", row.join(","))?;
+    }
+    Ok(())
+}
+
+/// Quotes the CSV value if needed.
+fn quote(value: &str) -> String {
+    if value.contains(|c| c == ',' || c == '"' || c == '\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        planet::{PlanetBuilder, PlanetEconomy, PlanetType},
+        sector::Sector,
+        star::{Star, StarType},
+        system::{System, SystemBuilder, SystemSecurity, SystemState},
+    };
+    use entities::Faction;
+    use serde_json::Value;
+
+    fn system(id: u32, name: &str, planets: usize) -> System {
+        let planet = PlanetBuilder::default()
+            .name(String::from("Nova, Prime"))
+            .mass(1.)
+            .gravity(1.)
+            .orbit_distance(500.)
+            .surface_temperature(288.)
+            .planet_type(PlanetType::Earth)
+            .economic_type(PlanetEconomy::Agriculture)
+            .build()
+            .unwrap();
+        SystemBuilder::default()
+            .id(SystemId(id))
+            .location(Point::new(f64::from(id), 2.))
+            .name(String::from(name))
+            .faction(Faction::new("Empire"))
+            .security(SystemSecurity::Medium)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![planet; planets])
+            .build()
+            .unwrap()
+    }
+
+    fn galaxy() -> Galaxy {
+        let sector = Sector {
+            name: String::from("Core"),
+            faction: Faction::new("Empire"),
+            system_ids: vec![SystemId(7)],
+            border: vec![],
+            neighbors: vec![],
+            contested_for: 0,
+        };
+        Galaxy::new(
+            vec![sector],
+            vec![system(7, "Sol", 2), system(3, "Vega", 0)],
+        )
+    }
+
+    fn names(record: &Record) -> Vec<&str> {
+        record.fields.iter().map(|&(name, _)| name).collect()
+    }
+
+    fn export(level: Level, filter: &str, format: DataFormat) -> io::Result<String> {
+        let filter = Filter::parse(filter).unwrap();
+        let mut output = vec![];
+        export_data(&galaxy(), level, Some(&filter), format, &mut output)?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_records() {
+        let systems = records(&galaxy(), Level::Systems);
+        assert_eq!(systems.len(), 2);
+        assert_eq!(
+            systems[0].get("name"),
+            Some(&Field::Text(String::from("Vega")))
+        );
+        assert_eq!(systems[0].get("sector"), Some(&Field::Integer(-1)));
+        assert_eq!(
+            systems[1].get("sector_name"),
+            Some(&Field::Text(String::from("Core")))
+        );
+        assert_eq!(systems[1].get("planets"), Some(&Field::Integer(2)));
+        assert_eq!(names(&systems[1]), Level::Systems.fields());
+
+        let planets = records(&galaxy(), Level::Planets);
+        assert_eq!(planets.len(), 2);
+        assert_eq!(planets[0].get("system_id"), Some(&Field::Integer(7)));
+        assert_eq!(names(&planets[0]), Level::Planets.fields());
+    }
+
+    #[test]
+    fn test_export_data() {
+        let csv = export(Level::Planets, "system == Sol", DataFormat::Csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], Level::Planets.fields().join(","));
+        assert!(lines[1].contains(",\"Nova, Prime\","));
+
+        // The header is written even when nothing matches.
+        let csv = export(Level::Systems, "name == Rigel", DataFormat::Csv).unwrap();
+        assert_eq!(csv, format!("{}\n", Level::Systems.fields().join(",")));
+
+        let json = export(Level::Systems, "id < 5", DataFormat::Json).unwrap();
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["name"], "Vega");
+
+        let geojson = export(Level::Systems, "planets > 0", DataFormat::GeoJson).unwrap();
+        let geojson: Value = serde_json::from_str(&geojson).unwrap();
+        assert_eq!(geojson["features"][0]["geometry"]["coordinates"][0], 7.);
+        assert_eq!(geojson["features"][0]["properties"]["name"], "Sol");
+
+        let error = export(Level::Systems, "mass > 1", DataFormat::Csv).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,273 @@
+use std::{cmp::Ordering, iter::Peekable, str::Chars};
+
+use super::data::Record;
+
+/// Comparison between a field and a value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Case insensitive substring match.
+    Contains,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Compare(String, Op, String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// Filter expression selecting exported records, such as
+/// `faction == Empire && (star_mass > 1.5 || name ~ "prime")`.
+///
+/// Values are compared as numbers when both sides are numeric, otherwise as
+/// case insensitive text. Values containing spaces or operators are quoted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter(Expr);
+
+impl Filter {
+    /// Parses the given filter expression.
+    pub fn parse(expression: &str) -> Result<Filter, String> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser { tokens, index: 0 };
+        let expr = parser.or()?;
+        match parser.tokens.get(parser.index) {
+            Some(token) => Err(format!("Unexpected {:?} in filter", token)),
+            None => Ok(Filter(expr)),
+        }
+    }
+
+    /// Returns true if the record matches the filter.
+    pub fn matches(&self, record: &Record) -> bool {
+        evaluate(&self.0, record)
+    }
+
+    /// Returns the names of all fields used by the filter.
+    pub fn fields(&self) -> Vec<&str> {
+        let mut fields = vec![];
+        collect_fields(&self.0, &mut fields);
+        fields
+    }
+}
+
+fn collect_fields<'a>(expr: &'a Expr, fields: &mut Vec<&'a str>) {
+    match *expr {
+        Expr::Compare(ref field, _, _) => fields.push(field),
+        Expr::Not(ref inner) => collect_fields(inner, fields),
+        Expr::And(ref left, ref right) | Expr::Or(ref left, ref right) => {
+            collect_fields(left, fields);
+            collect_fields(right, fields);
+        }
+    }
+}
+
+fn evaluate(expr: &Expr, record: &Record) -> bool {
+    match *expr {
+        Expr::Compare(ref field, op, ref value) => match record.get(field) {
+            Some(field) => compare(field.as_number(), &field.to_string(), op, value),
+            None => false,
+        },
+        Expr::Not(ref inner) => !evaluate(inner, record),
+        Expr::And(ref left, ref right) => evaluate(left, record) && evaluate(right, record),
+        Expr::Or(ref left, ref right) => evaluate(left, record) || evaluate(right, record),
+    }
+}
+
+fn compare(number: Option<f64>, text: &str, op: Op, value: &str) -> bool {
+    if op == Op::Contains {
+        return text.to_lowercase().contains(&value.to_lowercase());
+    }
+    let ordering = match (number, value.parse::<f64>()) {
+        (Some(number), Ok(value)) => number.partial_cmp(&value),
+        _ => Some(text.to_lowercase().cmp(&value.to_lowercase())),
+    };
+    match ordering {
+        Some(ordering) => match op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Ne => ordering != Ordering::Equal,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Contains => false,
+        },
+        // NaN compares unequal to everything.
+        None => op == Op::Ne,
+    }
+}
+
+/// Characters which end a bare word.
+const SPECIAL: &str = "()!=<>~&|\"'";
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let token = match c {
+            '(' => single(&mut chars, Token::Open),
+            ')' => single(&mut chars, Token::Close),
+            '~' => single(&mut chars, Token::Op(Op::Contains)),
+            '&' | '|' => {
+                chars.next();
+                match chars.next() {
+                    Some(next) if next == c && c == '&' => Token::And,
+                    Some(next) if next == c => Token::Or,
+                    _ => return Err(format!("Expected {0}{0} in filter", c)),
+                }
+            }
+            '!' | '=' | '<' | '>' => {
+                chars.next();
+                let equals = chars.peek() == Some(&'=');
+                if equals {
+                    chars.next();
+                }
+                match (c, equals) {
+                    ('!', true) => Token::Op(Op::Ne),
+                    ('!', false) => Token::Not,
+                    ('=', true) => Token::Op(Op::Eq),
+                    ('<', true) => Token::Op(Op::Le),
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('>', true) => Token::Op(Op::Ge),
+                    ('>', false) => Token::Op(Op::Gt),
+                    _ => return Err(String::from("Expected == in filter")),
+                }
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some(next) if next == c => break,
+                        Some(next) => word.push(next),
+                        None => return Err(format!("Unterminated {} in filter", c)),
+                    }
+                }
+                Token::Word(word)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || SPECIAL.contains(next) {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn single(chars: &mut Peekable<Chars>, token: Token) -> Token {
+    chars.next();
+    token
+}
+
+/// Recursive descent parser, where `!` binds tighter than `&&`, which binds
+/// tighter than `||`.
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err(String::from("Expected ) in filter")),
+                }
+            }
+            Some(Token::Word(field)) => match (self.next(), self.next()) {
+                (Some(Token::Op(op)), Some(Token::Word(value))) => {
+                    Ok(Expr::Compare(field, op, value))
+                }
+                _ => Err(format!("Expected comparison after {} in filter", field)),
+            },
+            Some(token) => Err(format!("Unexpected {:?} in filter", token)),
+            None => Err(String::from("Unexpected end of filter")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let filter = Filter::parse("!(a == 1 || b ~ 'x y') && c >= 2").unwrap();
+        assert_eq!(filter.fields(), vec!["a", "b", "c"]);
+        assert!(Filter::parse("a = 1").is_err());
+        assert!(Filter::parse("a == 1 &&").is_err());
+        assert!(Filter::parse("(a == 1").is_err());
+        assert!(Filter::parse("a == 1 b").is_err());
+        assert!(Filter::parse("name ~ 'abc").is_err());
+        assert!(Filter::parse("name == \"").is_err());
+        assert_eq!(Filter::parse("name == ''").unwrap().fields(), vec!["name"]);
+    }
+
+    #[test]
+    fn test_compare() {
+        assert!(!compare(Some(2.), "2", Op::Gt, "10"));
+        assert!(compare(None, "Empire", Op::Eq, "empire"));
+        assert!(compare(None, "Nova Prime", Op::Contains, "PRIME"));
+        assert!(compare(Some(1.5), "1.5", Op::Le, "1.5"));
+        assert!(compare(None, "Low", Op::Ne, "High"));
+    }
+}
@@ -0,0 +1,511 @@
+use png::{self, HasParameters};
+use std::{
+    collections::HashMap,
+    f64,
+    fmt::Write as FmtWrite,
+    io::{self, Write},
+};
+use tui::style::Color;
+
+use astronomicals::Galaxy;
+use economy::{Commodity, Economy};
+use entities::Faction;
+use resources::{fetch_resource, FactionResource};
+use utils::{parse_color, Point};
+
+/// Red, green and blue channels of a color.
+type Rgb = (u8, u8, u8);
+
+const BACKGROUND: Rgb = (0, 0, 0);
+const ROUTE_COLOR: Rgb = (255, 255, 255);
+/// Color of systems without a price when drawing a heatmap.
+const NO_PRICE_COLOR: Rgb = (90, 90, 90);
+/// Opacity of the halo drawn around each system to show sector shapes.
+const HALO_OPACITY: f64 = 0.12;
+
+lazy_static! {
+    /// Color of each faction, as configured for the galaxy map.
+    static ref FACTION_COLORS: HashMap<Faction, Rgb> = {
+        fetch_resource::<FactionResource>()
+            .unwrap()
+            .factions
+            .iter()
+            .map(|definition| (definition.id.clone(), rgb(parse_color(&definition.color))))
+            .collect()
+    };
+}
+
+/// Image formats supported by the map export.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    /// Guesses the format from the extension of the given file name.
+    pub fn from_path(path: &str) -> Option<ImageFormat> {
+        let path = path.to_lowercase();
+        if path.ends_with(".svg") {
+            Some(ImageFormat::Svg)
+        } else if path.ends_with(".png") {
+            Some(ImageFormat::Png)
+        } else {
+            None
+        }
+    }
+}
+
+/// Options controlling what is drawn on an exported map.
+pub struct MapOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Routes drawn on top of the systems, as the locations along each route.
+    pub routes: Vec<Vec<Point>>,
+    /// Commodity whose price colors the systems instead of their faction.
+    pub heatmap: Option<Commodity>,
+    /// Whether systems are colored by the class of their star instead of
+    /// their faction.
+    pub star_colors: bool,
+}
+
+impl Default for MapOptions {
+    fn default() -> MapOptions {
+        MapOptions {
+            width: 1024,
+            height: 1024,
+            routes: vec![],
+            heatmap: None,
+            star_colors: false,
+        }
+    }
+}
+
+/// Renders the galaxy as an image in the given format to the given output.
+/// The economy is only needed for price heatmaps.
+pub fn export_map<W: Write>(
+    galaxy: &Galaxy,
+    economy: Option<&Economy>,
+    options: &MapOptions,
+    format: ImageFormat,
+    output: &mut W,
+) -> io::Result<()> {
+    match format {
+        ImageFormat::Svg => {
+            let mut svg = Svg::new(options.width, options.height);
+            render(galaxy, economy, options, &mut svg);
+            output.write_all(svg.finish().as_bytes())?;
+        }
+        ImageFormat::Png => {
+            let mut raster = Raster::new(options.width, options.height);
+            render(galaxy, economy, options, &mut raster);
+            raster.write_png(&mut *output)?;
+        }
+    }
+    output.flush()
+}
+
+/// Draws systems grouped by sector, followed by all routes.
+fn render<C: Canvas>(
+    galaxy: &Galaxy,
+    economy: Option<&Economy>,
+    options: &MapOptions,
+    canvas: &mut C,
+) {
+    let locations = galaxy
+        .systems()
+        .map(|system| system.location)
+        .collect::<Vec<_>>();
+    let projection = Projection::new(&locations, options.width, options.height);
+
+    // Scale systems with the area available to each.
+    let area = f64::from(options.width) * f64::from(options.height);
+    let radius = (0.15 * (area / locations.len().max(1) as f64).sqrt()).clamp(1., 6.);
+
+    let prices = match (economy, options.heatmap.as_ref()) {
+        (Some(economy), Some(commodity)) => galaxy
+            .systems()
+            .filter_map(|system| {
+                economy
+                    .price(system, commodity)
+                    .map(|price| (system.id, price))
+            })
+            .collect::<HashMap<_, _>>(),
+        _ => HashMap::new(),
+    };
+    let min_price = prices.values().cloned().min().unwrap_or(0);
+    let max_price = prices.values().cloned().max().unwrap_or(0);
+
+    for (index, sector) in galaxy.sectors.iter().enumerate() {
+        canvas.begin_group(
+            &format!("sector-{}", index),
+            &if sector.name.is_empty() {
+                format!("Sector {} ({})", index, sector.faction)
+            } else {
+                format!("{} ({})", sector.name, sector.faction)
+            },
+        );
+        let systems = sector
+            .system_ids
+            .iter()
+            .filter_map(|&id| galaxy.system(id))
+            .collect::<Vec<_>>();
+
+        // Overlapping halos show the shape and density of the sector.
+        for system in &systems {
+            canvas.circle(
+                projection.apply(&system.location),
+                radius * 3.,
+                faction_color(&system.faction),
+                HALO_OPACITY,
+            );
+        }
+        for (index, from) in sector.border.iter().enumerate() {
+            let to = &sector.border[(index + 1) % sector.border.len()];
+            canvas.line(
+                projection.apply(from),
+                projection.apply(to),
+                (radius / 4.).max(1.),
+                faction_color(&sector.faction),
+            );
+        }
+        for system in &systems {
+            let color = if options.heatmap.is_some() {
+                prices.get(&system.id).map_or(NO_PRICE_COLOR, |&price| {
+                    heat_color(price, min_price, max_price)
+                })
+            } else if options.star_colors {
+                rgb(system.star.class.color())
+            } else {
+                faction_color(&system.faction)
+            };
+            canvas.circle(projection.apply(&system.location), radius, color, 1.);
+        }
+        canvas.end_group();
+    }
+
+    for route in &options.routes {
+        for pair in route.windows(2) {
+            canvas.line(
+                projection.apply(&pair[0]),
+                projection.apply(&pair[1]),
+                radius.max(2.) / 2.,
+                ROUTE_COLOR,
+            );
+        }
+        for location in route.first().iter().chain(route.last().iter()) {
+            canvas.circle(projection.apply(location), radius * 2., ROUTE_COLOR, 1.);
+        }
+    }
+}
+
+/// Returns the color of the given faction, white if unknown.
+fn faction_color(faction: &Faction) -> Rgb {
+    *FACTION_COLORS.get(faction).unwrap_or(&(255, 255, 255))
+}
+
+/// Returns a color from blue for the cheapest through yellow to red for the
+/// most expensive price.
+fn heat_color(price: i64, min: i64, max: i64) -> Rgb {
+    let cheap = (40., 80., 255.);
+    let middle = (255., 230., 60.);
+    let expensive = (230., 30., 30.);
+    let t = if max > min {
+        (price - min) as f64 / (max - min) as f64
+    } else {
+        0.5
+    };
+    let lerp = |from: (f64, f64, f64), to: (f64, f64, f64), t: f64| {
+        (
+            (from.0 + (to.0 - from.0) * t) as u8,
+            (from.1 + (to.1 - from.1) * t) as u8,
+            (from.2 + (to.2 - from.2) * t) as u8,
+        )
+    };
+    if t < 0.5 {
+        lerp(cheap, middle, t * 2.)
+    } else {
+        lerp(middle, expensive, (t - 0.5) * 2.)
+    }
+}
+
+/// Converts a terminal color to its usual appearance.
+fn rgb(color: Color) -> Rgb {
+    match color {
+        Color::Black => (0, 0, 0),
+        Color::Red => (205, 49, 49),
+        Color::Green => (13, 188, 121),
+        Color::Yellow => (229, 229, 16),
+        Color::Blue => (36, 114, 200),
+        Color::Magenta => (188, 63, 188),
+        Color::Cyan => (17, 168, 205),
+        Color::Gray => (160, 160, 160),
+        Color::DarkGray => (102, 102, 102),
+        Color::LightRed => (241, 76, 76),
+        Color::LightGreen => (35, 209, 139),
+        Color::LightYellow => (245, 245, 67),
+        Color::LightBlue => (59, 142, 234),
+        Color::LightMagenta => (214, 112, 214),
+        Color::LightCyan => (41, 184, 219),
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => (229, 229, 229),
+    }
+}
+
+/// Maps galaxy coordinates onto image coordinates, keeping the aspect ratio
+/// and flipping the y axis so north is up.
+struct Projection {
+    min_x: f64,
+    max_y: f64,
+    scale: f64,
+    offset: Point,
+}
+
+impl Projection {
+    /// Fraction of the image left empty around the galaxy.
+    const MARGIN: f64 = 0.05;
+
+    /// Creates a projection fitting all the given locations in the image.
+    fn new(locations: &[Point], width: u32, height: u32) -> Projection {
+        let (width, height) = (f64::from(width), f64::from(height));
+        let (min_x, max_x, min_y, max_y) = if locations.is_empty() {
+            (0., 1., 0., 1.)
+        } else {
+            locations.iter().fold(
+                (
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                ),
+                |(min_x, max_x, min_y, max_y), p| {
+                    (
+                        min_x.min(p.x),
+                        max_x.max(p.x),
+                        min_y.min(p.y),
+                        max_y.max(p.y),
+                    )
+                },
+            )
+        };
+
+        let margin = Projection::MARGIN * width.min(height);
+        let scale = ((width - 2. * margin) / (max_x - min_x).max(1.))
+            .min((height - 2. * margin) / (max_y - min_y).max(1.));
+        Projection {
+            min_x,
+            max_y,
+            scale,
+            offset: Point::new(
+                (width - (max_x - min_x) * scale) / 2.,
+                (height - (max_y - min_y) * scale) / 2.,
+            ),
+        }
+    }
+
+    /// Returns the image coordinates of the given location.
+    fn apply(&self, location: &Point) -> Point {
+        Point::new(
+            self.offset.x + (location.x - self.min_x) * self.scale,
+            self.offset.y + (self.max_y - location.y) * self.scale,
+        )
+    }
+}
+
+/// Surface which a map is drawn onto, in image coordinates.
+trait Canvas {
+    /// Draws a filled circle.
+    fn circle(&mut self, center: Point, radius: f64, color: Rgb, opacity: f64);
+
+    /// Draws a line of the given width.
+    fn line(&mut self, from: Point, to: Point, width: f64, color: Rgb);
+
+    /// Starts a named group of shapes, if supported.
+    fn begin_group(&mut self, _id: &str, _title: &str) {}
+
+    /// Ends the current group of shapes, if supported.
+    fn end_group(&mut self) {}
+}
+
+/// Vector image built as SVG markup.
+struct Svg {
+    width: u32,
+    height: u32,
+    body: String,
+}
+
+impl Svg {
+    fn new(width: u32, height: u32) -> Svg {
+        Svg {
+            width,
+            height,
+            body: String::new(),
+        }
+    }
+
+    /// Returns the complete SVG document.
+    fn finish(self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\">\n<rect width=\"100%\" height=\"100%\" fill=\"{2}\"/>\n{3}</svg>\n",
+            self.width,
+            self.height,
+            hex(BACKGROUND),
+            self.body
+        )
+    }
+}
+
+impl Canvas for Svg {
+    fn circle(&mut self, center: Point, radius: f64, color: Rgb, opacity: f64) {
+        write!(
+            self.body,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{:.2}\" fill=\"{}\"",
+            center.x,
+            center.y,
+            radius,
+            hex(color)
+        )
+        .unwrap();
+        if opacity < 1. {
+            write!(self.body, " fill-opacity=\"{:.2}\"", opacity).unwrap();
+        }
+        self.body.push_str("/>\n");
+    }
+
+    fn line(&mut self, from: Point, to: Point, width: f64, color: Rgb) {
+        writeln!(
+            self.body,
+            "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" \
+             stroke-width=\"{:.2}\" stroke-linecap=\"round\"/>",
+            from.x,
+            from.y,
+            to.x,
+            to.y,
+            hex(color),
+            width
+        )
+        .unwrap();
+    }
+
+    fn begin_group(&mut self, id: &str, title: &str) {
+        writeln!(
+            self.body,
+            "<g id=\"{}\"><title>{}</title>",
+            escape(id),
+            escape(title)
+        )
+        .unwrap();
+    }
+
+    fn end_group(&mut self) {
+        self.body.push_str("</g>\n");
+    }
+}
+
+/// Formats the color as a "#rrggbb" hex string.
+fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.0, color.1, color.2)
+}
+
+/// Escapes the characters which are special in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Raster image with 8 bit RGB pixels.
+struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Raster {
+    fn new(width: u32, height: u32) -> Raster {
+        let pixels = (0..width * height)
+            .flat_map(|_| vec![BACKGROUND.0, BACKGROUND.1, BACKGROUND.2])
+            .collect();
+        Raster {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// Blends the given color into the pixel, ignoring pixels outside.
+    fn blend(&mut self, x: i64, y: i64, color: Rgb, opacity: f64) {
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return;
+        }
+        let index = 3 * (y as usize * self.width as usize + x as usize);
+        for (channel, value) in self.pixels[index..index + 3]
+            .iter_mut()
+            .zip(&[color.0, color.1, color.2])
+        {
+            *channel = (f64::from(*channel) * (1. - opacity) + f64::from(*value) * opacity) as u8;
+        }
+    }
+
+    /// Encodes the image as PNG to the given output.
+    fn write_png<W: Write>(&self, output: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(output, self.width, self.height);
+        encoder.set(png::ColorType::RGB).set(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+}
+
+impl Canvas for Raster {
+    fn circle(&mut self, center: Point, radius: f64, color: Rgb, opacity: f64) {
+        // Small circles cover at least the pixel of their center.
+        let radius = radius.max(0.71);
+        for y in (center.y - radius).floor() as i64..=(center.y + radius).ceil() as i64 {
+            for x in (center.x - radius).floor() as i64..=(center.x + radius).ceil() as i64 {
+                let (dx, dy) = (x as f64 + 0.5 - center.x, y as f64 + 0.5 - center.y);
+                if dx * dx + dy * dy <= radius * radius {
+                    self.blend(x, y, color, opacity);
+                }
+            }
+        }
+    }
+
+    fn line(&mut self, from: Point, to: Point, width: f64, color: Rgb) {
+        // Stamp circles along the line, at most half a pixel apart.
+        let steps = (from.distance(&to) * 2.).ceil().max(1.) as u32;
+        for step in 0..=steps {
+            let t = f64::from(step) / f64::from(steps);
+            let point = Point::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t);
+            self.circle(point, width / 2., color, 1.);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_keeps_aspect_ratio() {
+        let locations = [Point::new(-10., -5.), Point::new(10., 5.)];
+        let projection = Projection::new(&locations, 200, 200);
+
+        let top_left = projection.apply(&Point::new(-10., 5.));
+        let bottom_right = projection.apply(&Point::new(10., -5.));
+        assert!((top_left.x - 10.).abs() < 1e-9);
+        assert!((bottom_right.x - 190.).abs() < 1e-9);
+        assert!((top_left.y - 55.).abs() < 1e-9);
+        assert!((bottom_right.y - 145.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_raster_circle() {
+        let mut raster = Raster::new(5, 5);
+        raster.circle(Point::new(2.5, 2.5), 1., (255, 0, 0), 1.);
+        assert_eq!(&raster.pixels[3 * 12..3 * 13], &[255, 0, 0]);
+        assert_eq!(&raster.pixels[0..3], &[0, 0, 0]);
+    }
+}
@@ -1,20 +1,33 @@
-use app_dirs::{get_data_root, AppDataType};
 use bincode::{deserialize_from, serialize_into};
 use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::Serialize;
 use std::{
     fs::{create_dir_all, File},
-    io::{BufReader, BufWriter},
-    sync::{Arc, Mutex},
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
     time::Instant,
 };
 
 use astronomicals::Galaxy;
+use clock::{Clock, ClockState, SystemTime};
 use economy::Economy;
+use game_config::data_root;
 use player::Player;
 use resources::{fetch_resource, ShipResource};
 use ship::Shipyard;
 
-const SAVE_PATH: &str = "gemini/saves/";
+const SAVE_PATH: &str = "saves/";
+
+/// Version of the save format, to be increased whenever any saved type
+/// changes since saves can not be read across versions.
+const SAVE_VERSION: u32 = 1;
+
+/// Save slot used unless another one is given.
+pub const DEFAULT_SLOT: &str = "quicksave";
 
 /// Main game state object, shared and syncronized by use of Arc and Mutex.
 pub struct Game {
@@ -22,56 +35,131 @@ pub struct Game {
     pub shipyard: Mutex<Shipyard>,
     pub player: Mutex<Player>,
     pub economy: Mutex<Economy>,
+    pub clock: Mutex<Clock>,
     updated: Mutex<DateTime<Utc>>,
+    catching_up: AtomicBool,
+    slot: String,
 }
 
 impl Game {
-    /// Creates a new game.
-    pub fn new() -> Arc<Self> {
+    /// Maximum number of steps simulated when advancing time.
+    const MAX_CATCH_UP_STEPS: i64 = 90;
+
+    /// Creates a new game, saved in the given slot.
+    pub fn new(slot: &str) -> Arc<Self> {
         Arc::new(Game {
             galaxy: Mutex::new(Galaxy::default()),
             shipyard: Mutex::new(Shipyard::new()),
             player: Mutex::new(Player::default()),
             economy: Mutex::new(Economy::default()),
+            clock: Mutex::new(Clock::realtime()),
             updated: Mutex::new(Utc.ymd(2018, 1, 1).and_hms(0, 0, 0)), // Start time
+            catching_up: AtomicBool::new(false),
+            slot: String::from(slot),
         })
     }
 
+    /// Returns the directory of the given save slot.
+    pub fn save_path(slot: &str) -> PathBuf {
+        data_root().join(SAVE_PATH).join(slot)
+    }
+
     /// Update Game information, may advance time.
     pub fn update(&self) {
+        self.update_with_progress(&|_, _| {});
+    }
+
+    /// Update Game information, may advance time.
+    /// Progress of any time advancement is reported as the number of steps
+    /// done out of the total number of steps.
+    pub fn update_with_progress(&self, progress: &dyn Fn(u32, u32)) {
         // If we have advanced time some steps.
-        if self.attempt_advance_time().is_some() {
+        if self.attempt_advance_time(progress).is_some() {
             self.save_all();
         }
 
         // Update player location etc.
-        self.player.lock().unwrap().update_state();
+        let now = self.clock.lock().unwrap().now();
+        self.player.lock().unwrap().update_state(now);
+    }
+
+    /// Returns true while time is being advanced, during which the game state
+    /// should only be viewed and not modified by the player.
+    pub fn is_catching_up(&self) -> bool {
+        self.catching_up.load(Ordering::SeqCst)
+    }
+
+    /// Manually moves game time forward the given number of days and updates
+    /// the game accordingly.
+    pub fn advance_days(&self, days: i64) {
+        self.clock
+            .lock()
+            .unwrap()
+            .advance(Duration::days(days.max(0)));
+        self.update();
+    }
+
+    /// Pauses game time if running, resumes it if paused.
+    pub fn toggle_pause(&self) {
+        let mut clock = self.clock.lock().unwrap();
+        if clock.is_paused() {
+            clock.resume();
+        } else {
+            clock.pause();
+        }
     }
 
     /// Attemps to advance time returning the number of days advanced if any.
-    fn attempt_advance_time(&self) -> Option<i64> {
+    /// The update time stays locked throughout so that only one catch up runs
+    /// at the time, while the other locks are only held for a single step so
+    /// that the game remains viewable while catching up.
+    fn attempt_advance_time(&self, progress: &dyn Fn(u32, u32)) -> Option<i64> {
         let updated: &mut DateTime<Utc> = &mut self.updated.lock().unwrap();
         // Check if we need to advance time.
-        let days_passed = Utc::now().signed_duration_since(*updated).num_days();
+        let now = self.clock.lock().unwrap().now();
+        let days_passed = now.signed_duration_since(*updated).num_days();
         if days_passed > 0 {
             // Measure time for generation.
-            let now = Instant::now();
-            debug!("Advancing time: {} steps", days_passed);
+            let timer = Instant::now();
+            self.catching_up.store(true, Ordering::SeqCst);
+
+            // Very long absences are coarsened into fewer steps covering
+            // several days each, the economy converges anyway.
+            let steps = days_passed.min(Game::MAX_CATCH_UP_STEPS);
+            debug!("Advancing time: {} days in {} steps", days_passed, steps);
 
             // Update state iterativly.
-            for _ in 0..days_passed {
-                self.galaxy.lock().unwrap().update();
-                self.economy.lock().unwrap().update();
+            for step in 0..steps {
+                let days_in_step = days_passed * (step + 1) / steps - days_passed * step / steps;
+                {
+                    let mut galaxy = self.galaxy.lock().unwrap();
+                    for _ in 0..days_in_step {
+                        galaxy.update();
+                    }
+                }
+                let strengths = {
+                    let mut economy = self.economy.lock().unwrap();
+                    economy.update();
+                    economy.strengths()
+                };
+                self.galaxy.lock().unwrap().update_territory(&strengths);
+                {
+                    let mut player = self.player.lock().unwrap();
+                    for _ in 0..days_in_step {
+                        player.standing_mut().update();
+                    }
+                }
+                progress((step + 1) as u32, steps as u32);
             }
 
             // Update last update timer.
             *updated = updated
                 .checked_add_signed(Duration::days(days_passed))
                 .unwrap();
-            //self.save_all();
+            self.catching_up.store(false, Ordering::SeqCst);
             debug!(
                 "Time advancement finished, took {} ms",
-                ((now.elapsed().as_secs() * 1_000) + u64::from(now.elapsed().subsec_millis()))
+                ((timer.elapsed().as_secs() * 1_000) + u64::from(timer.elapsed().subsec_millis()))
             );
             Some(days_passed)
         } else {
@@ -81,44 +169,80 @@ impl Game {
 
     /// Creates and stores a quicksave of the current game.
     pub fn save_all(&self) {
-        let base_path = get_data_root(AppDataType::UserConfig)
-            .unwrap()
-            .join(SAVE_PATH);
+        let base_path = Game::save_path(&self.slot);
 
         if create_dir_all(base_path.as_path()).is_ok() {
-            let mut galaxy_file =
-                BufWriter::new(File::create(base_path.join("galaxy.cbor").as_path()).unwrap());
-            serialize_into(&mut galaxy_file, &(*self.galaxy.lock().unwrap()));
-            let mut player_file =
-                BufWriter::new(File::create(base_path.join("player.cbor").as_path()).unwrap());
-            serialize_into(&mut player_file, &(*self.player.lock().unwrap()));
-            let mut economy_file =
-                BufWriter::new(File::create(base_path.join("economy.cbor").as_path()).unwrap());
-            serialize_into(&mut economy_file, &(*self.economy.lock().unwrap()));
-            let mut update_file =
-                BufWriter::new(File::create(base_path.join("updated.cbor").as_path()).unwrap());
-            serialize_into(&mut update_file, &(*self.updated.lock().unwrap()));
+            Game::write_save(&base_path.join("version.cbor"), &SAVE_VERSION);
+            Game::write_save(
+                &base_path.join("galaxy.cbor"),
+                &*self.galaxy.lock().unwrap(),
+            );
+            Game::write_save(
+                &base_path.join("player.cbor"),
+                &*self.player.lock().unwrap(),
+            );
+            Game::write_save(
+                &base_path.join("economy.cbor"),
+                &*self.economy.lock().unwrap(),
+            );
+            Game::write_save(
+                &base_path.join("updated.cbor"),
+                &*self.updated.lock().unwrap(),
+            );
+            Game::write_save(
+                &base_path.join("clock.cbor"),
+                &self.clock.lock().unwrap().state(),
+            );
         }
     }
 
     /// Creates and stores a quicksave of the player data.
     pub fn save_player(&self) {
-        let base_path = get_data_root(AppDataType::UserConfig)
-            .unwrap()
-            .join(SAVE_PATH);
+        let base_path = Game::save_path(&self.slot);
 
         if create_dir_all(base_path.as_path()).is_ok() {
-            let mut player_file =
-                BufWriter::new(File::create(base_path.join("player.cbor").as_path()).unwrap());
-            serialize_into(&mut player_file, &(*self.player.lock().unwrap()));
+            Game::write_save(
+                &base_path.join("player.cbor"),
+                &*self.player.lock().unwrap(),
+            );
         }
     }
 
-    /// Attempts to load a quicksave of a game state.
-    pub fn load() -> Option<Arc<Self>> {
-        let base_path = get_data_root(AppDataType::UserConfig)
-            .unwrap()
-            .join(SAVE_PATH);
+    /// Writes a single part of a save, logging any failure since a partial
+    /// save can not be loaded again.
+    fn write_save<T: Serialize>(path: &Path, value: &T) {
+        let result = File::create(path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                let mut writer = BufWriter::new(file);
+                serialize_into(&mut writer, value).map_err(|e| e.to_string())?;
+                writer.flush().map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            error!("Could not write save file {}: {}", path.display(), e);
+        }
+    }
+
+    /// Attempts to load the game saved in the given slot.
+    pub fn load(slot: &str) -> Option<Arc<Self>> {
+        Game::load_from(&Game::save_path(slot), slot)
+    }
+
+    /// Attempts to load a game state saved in the given directory, further
+    /// saves go to the given slot.
+    pub fn load_from(base_path: &Path, slot: &str) -> Option<Arc<Self>> {
+        let version: Option<u32> = File::open(base_path.join("version.cbor").as_path())
+            .ok()
+            .and_then(|version_file| deserialize_from(BufReader::new(version_file)).ok());
+        if version != Some(SAVE_VERSION) {
+            if base_path.join("galaxy.cbor").exists() {
+                warn!(
+                    "Save in {} is from another version of the game",
+                    base_path.display()
+                );
+            }
+            return None;
+        }
 
         let galaxy: Option<Galaxy> = File::open(base_path.join("galaxy.cbor").as_path())
             .ok()
@@ -134,16 +258,24 @@ impl Game {
             .ok()
             .and_then(|updated_file| deserialize_from(BufReader::new(updated_file)).ok());
 
+        let clock = File::open(base_path.join("clock.cbor").as_path())
+            .ok()
+            .and_then(|clock_file| deserialize_from(BufReader::new(clock_file)).ok())
+            .map(|state: ClockState| Clock::restore(Arc::new(SystemTime), state));
+
         let mut shipyard = Shipyard::new();
-        shipyard.add_ships(fetch_resource::<ShipResource>().unwrap());
+        shipyard.add_ships(&fetch_resource::<ShipResource>().unwrap());
 
-        match (galaxy, player, economy, updated) {
-            (Some(g), Some(p), Some(e), Some(u)) => Some(Arc::new(Game {
+        match (galaxy, player, economy, updated, clock) {
+            (Some(g), Some(p), Some(e), Some(u), Some(c)) => Some(Arc::new(Game {
                 galaxy: Mutex::new(g),
                 shipyard: Mutex::new(shipyard),
                 player: Mutex::new(p),
                 economy: Mutex::new(e),
+                clock: Mutex::new(c),
                 updated: Mutex::new(u),
+                catching_up: AtomicBool::new(false),
+                slot: String::from(slot),
             })),
             _ => None,
         }
@@ -155,3 +287,47 @@ pub trait Updatable {
     /// Performs an update for one time step.
     fn update(&mut self);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::ManualTime;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_catch_up() {
+        let start = Utc.ymd(2018, 1, 1).and_hms(0, 0, 0);
+        let source = Arc::new(ManualTime::new(start));
+        let game = Game::new("test");
+        *game.clock.lock().unwrap() = Clock::new(source.clone(), start, 1.);
+        // Steps done so far, each reported once and in order.
+        let steps = Cell::new(0);
+        let progress = |done, total| {
+            assert_eq!(done, steps.get() + 1);
+            assert!(done <= total);
+            steps.set(done);
+        };
+
+        // Short absences are simulated a day at the time.
+        source.advance(Duration::days(5));
+        assert_eq!(game.attempt_advance_time(&progress), Some(5));
+        assert_eq!(steps.get(), 5);
+
+        // Long ones are coarsened into fewer steps.
+        steps.set(0);
+        source.advance(Duration::days(400));
+        assert_eq!(game.attempt_advance_time(&progress), Some(400));
+        assert_eq!(steps.get(), Game::MAX_CATCH_UP_STEPS as u32);
+        assert_eq!(*game.updated.lock().unwrap(), start + Duration::days(405));
+        assert_eq!(game.attempt_advance_time(&progress), None);
+    }
+
+    #[test]
+    fn test_reject_other_versions() {
+        let path = ::std::env::temp_dir().join("gemini-test-save-version");
+        create_dir_all(&path).unwrap();
+        let mut version_file = File::create(path.join("version.cbor")).unwrap();
+        serialize_into(&mut version_file, &(SAVE_VERSION + 1)).unwrap();
+        assert!(Game::load_from(&path, "test").is_none());
+    }
+}
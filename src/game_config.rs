@@ -1,11 +1,107 @@
 use app_dirs::{get_data_root, AppDataType};
 use std::{
+    fmt,
     fs::{create_dir_all, File},
     io::{Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::RwLock,
 };
 use toml::{de::from_str, ser::to_string_pretty};
 
-const PREFS_PATH: &str = "gemini/conf/";
+use astronomicals::system::SystemSecurity;
+use generators::shapes::GalaxyShape;
+use resources::{fetch_resource, FactionResource, ScenarioResource};
+use scenario::StartLocation;
+
+const PREFS_PATH: &str = "conf/";
+
+lazy_static! {
+    /// Data directory set on the command line, if any.
+    static ref DATA_ROOT: RwLock<Option<PathBuf>> = RwLock::new(None);
+}
+
+/// Overrides the directory used for config, saves, mods and logs.
+pub fn set_data_root(path: PathBuf) {
+    *DATA_ROOT.write().unwrap() = Some(path);
+}
+
+/// Returns the directory used for config, saves, mods and logs, by default
+/// `gemini` in the user config directory.
+pub fn data_root() -> PathBuf {
+    DATA_ROOT.read().unwrap().clone().unwrap_or_else(|| {
+        get_data_root(AppDataType::UserConfig)
+            .unwrap()
+            .join("gemini")
+    })
+}
+
+/// Named sets of parameters making the game more or less forgiving.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    /// Returns all difficulties, from the easiest to the hardest.
+    pub fn values() -> &'static [Difficulty] {
+        &[Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Difficulty, String> {
+        Difficulty::values()
+            .iter()
+            .find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(name))
+            .cloned()
+            .ok_or_else(|| format!("Unknown difficulty: {}", name))
+    }
+}
+
+/// Multipliers applied to the security distributions of the factions when
+/// generating systems.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SecurityWeights {
+    pub anarchy: f64,
+    pub low: f64,
+    pub medium: f64,
+    pub high: f64,
+}
+
+impl SecurityWeights {
+    /// Returns the multiplier of the given security level.
+    pub fn get(&self, security: &SystemSecurity) -> f64 {
+        match *security {
+            SystemSecurity::Anarchy => self.anarchy,
+            SystemSecurity::Low => self.low,
+            SystemSecurity::Medium => self.medium,
+            SystemSecurity::High => self.high,
+        }
+    }
+}
+
+impl Default for SecurityWeights {
+    fn default() -> SecurityWeights {
+        SecurityWeights {
+            anarchy: 1.,
+            low: 1.,
+            medium: 1.,
+            high: 1.,
+        }
+    }
+}
 
 // Deriving `Serialize` and `Deserialize` on a struct/enum automatically
 // implements the `Preferences` trait.
@@ -18,30 +114,179 @@ pub struct GameConfig {
     pub number_of_systems: u64,
     pub system_spread: f64,
     pub number_of_sectors: usize,
+    /// Maximal number of k-means iterations when clustering sectors.
+    #[serde(default = "GameConfig::default_sector_iterations")]
+    pub sector_iterations: u32,
+    /// Whether sectors are balanced to contain similar numbers of systems.
+    #[serde(default)]
+    pub balance_sectors: bool,
+    /// Number of game seconds passing per real second in new games, saved
+    /// games keep their own.
+    #[serde(default = "GameConfig::default_time_scale")]
+    pub time_scale: f64,
+    /// Difficulty the remaining parameters were last set from.
+    #[serde(default)]
+    pub difficulty: Difficulty,
+    /// Average number of planets per system.
+    #[serde(default = "GameConfig::default_planets_per_system")]
+    pub planets_per_system: f64,
+    /// Average number of stations and outposts per system.
+    #[serde(default = "GameConfig::default_stations_per_system")]
+    pub stations_per_system: f64,
+    /// Minimal distance from a planet to its star.
+    #[serde(default = "GameConfig::default_min_orbit_distance")]
+    pub min_orbit_distance: f64,
+    /// Credits each economic agent starts with.
+    #[serde(default = "GameConfig::default_agent_credits")]
+    pub agent_credits: u64,
+    /// Name of the scenario new games start from, if any.
+    #[serde(default)]
+    pub scenario: Option<String>,
+    /// Starting system of new games without a scenario.
+    #[serde(default)]
+    pub start: StartLocation,
+    /// Multipliers of the faction security distributions.
+    #[serde(default)]
+    pub security: SecurityWeights,
+    /// Shape of the generated galaxy, kept last since it is stored as a table.
+    #[serde(default)]
+    pub shape: GalaxyShape,
 }
 
 impl GameConfig {
+    /// Game time follows real time unless configured otherwise.
+    fn default_time_scale() -> f64 {
+        1.
+    }
+
+    fn default_sector_iterations() -> u32 {
+        100
+    }
+
+    fn default_planets_per_system() -> f64 {
+        3.
+    }
+
+    fn default_stations_per_system() -> f64 {
+        0.5
+    }
+
+    fn default_min_orbit_distance() -> f64 {
+        500.
+    }
+
+    fn default_agent_credits() -> u64 {
+        100_000
+    }
+
+    /// Sets the credits and security of the given difficulty, keeping the
+    /// parameters of the galaxy itself.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+        match difficulty {
+            Difficulty::Easy => {
+                self.starting_credits = 5000;
+                self.agent_credits = 200_000;
+                self.security = SecurityWeights {
+                    anarchy: 0.5,
+                    low: 1.,
+                    medium: 1.,
+                    high: 1.5,
+                };
+            }
+            Difficulty::Normal => {
+                self.starting_credits = 1000;
+                self.agent_credits = GameConfig::default_agent_credits();
+                self.security = SecurityWeights::default();
+            }
+            Difficulty::Hard => {
+                self.starting_credits = 250;
+                self.agent_credits = 50_000;
+                self.security = SecurityWeights {
+                    anarchy: 2.,
+                    low: 1.5,
+                    medium: 1.,
+                    high: 0.5,
+                };
+            }
+        }
+    }
+
+    /// Checks that the parameters can be used to generate a galaxy, returning
+    /// a description of the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        let weights = [
+            self.security.anarchy,
+            self.security.low,
+            self.security.medium,
+            self.security.high,
+        ];
+        if self.number_of_systems == 0 {
+            Err(String::from("number_of_systems must be positive"))
+        } else if self.number_of_sectors == 0
+            || self.number_of_sectors as u64 > self.number_of_systems
+        {
+            Err(String::from(
+                "number_of_sectors must be between 1 and number_of_systems",
+            ))
+        } else if self.sector_iterations == 0 {
+            Err(String::from("sector_iterations must be positive"))
+        } else if self.system_spread.is_nan() || self.system_spread <= 0. {
+            Err(String::from("system_spread must be positive"))
+        } else if self.time_scale.is_nan() || self.time_scale < 0. {
+            Err(String::from("time_scale must not be negative"))
+        } else if self.planets_per_system.is_nan() || self.planets_per_system <= 0. {
+            Err(String::from("planets_per_system must be positive"))
+        } else if self.stations_per_system.is_nan() || self.stations_per_system < 0. {
+            Err(String::from("stations_per_system must not be negative"))
+        } else if self.min_orbit_distance.is_nan() || self.min_orbit_distance <= 0. {
+            Err(String::from("min_orbit_distance must be positive"))
+        } else if weights.iter().any(|&weight| weight.is_nan() || weight < 0.) {
+            Err(String::from("security weights must not be negative"))
+        } else if weights.iter().all(|&weight| weight == 0.) {
+            Err(String::from("security weights must not all be zero"))
+        } else {
+            self.shape.validate()?;
+            self.validate_start()
+        }
+    }
+
+    /// Checks that the scenario and starting faction, if any, exist.
+    fn validate_start(&self) -> Result<(), String> {
+        if let Some(ref name) = self.scenario {
+            let scenarios = fetch_resource::<ScenarioResource>().map_err(|err| err.to_string())?;
+            if scenarios.get(name).is_none() {
+                let names = scenarios
+                    .scenarios
+                    .iter()
+                    .map(|scenario| scenario.name.as_str())
+                    .collect::<Vec<_>>();
+                return Err(format!(
+                    "unknown scenario {}, expected one of: {}",
+                    name,
+                    names.join(", ")
+                ));
+            }
+        }
+        if let StartLocation::Random {
+            faction: Some(ref faction),
+            ..
+        } = self.start
+        {
+            let factions = fetch_resource::<FactionResource>().map_err(|err| err.to_string())?;
+            if factions.get(faction).is_none() {
+                return Err(format!("unknown starting faction {}", faction));
+            }
+        }
+        Ok(())
+    }
+
     /// Attempts to load a GameConfig from disk at the default preference
     /// location.
     /// If the loading fails for any reason, for example, the file does not
     /// exist, a new default GameConfig object is created, stored and returned.
     pub fn retrieve() -> GameConfig {
-        let config: Option<GameConfig> = File::open(
-            get_data_root(AppDataType::UserConfig)
-                .unwrap()
-                .join(PREFS_PATH)
-                .join("general.toml")
-                .as_path(),
-        ).ok()
-            .and_then(|mut config_file| {
-                let mut config_str = String::new();
-                match config_file.read_to_string(&mut config_str) {
-                    Ok(_) => Some(config_str),
-                    Err(_) => None,
-                }
-            })
-            .and_then(|config_str| from_str(&config_str).ok())
-            .or_else(|| None);
+        let config = GameConfig::load(data_root().join(PREFS_PATH).join("general.toml").as_path());
 
         match config {
             Some(config) => config,
@@ -54,12 +299,24 @@ impl GameConfig {
         }
     }
 
+    /// Attempts to load a GameConfig from the given path.
+    pub fn load(path: &Path) -> Option<GameConfig> {
+        File::open(path)
+            .ok()
+            .and_then(|mut config_file| {
+                let mut config_str = String::new();
+                match config_file.read_to_string(&mut config_str) {
+                    Ok(_) => Some(config_str),
+                    Err(_) => None,
+                }
+            })
+            .and_then(|config_str| from_str(&config_str).ok())
+    }
+
     /// Attempts to store the GameConfig on disk at the default preference
     /// location.
     pub fn store(&self) {
-        let base_path = get_data_root(AppDataType::UserConfig)
-            .unwrap()
-            .join(PREFS_PATH);
+        let base_path = data_root().join(PREFS_PATH);
 
         create_dir_all(base_path.as_path())
             .ok()
@@ -79,6 +336,54 @@ impl Default for GameConfig {
             number_of_systems: 10_000,
             system_spread: 150.,
             number_of_sectors: 150,
+            sector_iterations: GameConfig::default_sector_iterations(),
+            balance_sectors: false,
+            time_scale: GameConfig::default_time_scale(),
+            difficulty: Difficulty::default(),
+            planets_per_system: GameConfig::default_planets_per_system(),
+            stations_per_system: GameConfig::default_stations_per_system(),
+            min_orbit_distance: GameConfig::default_min_orbit_distance(),
+            agent_credits: GameConfig::default_agent_credits(),
+            scenario: None,
+            start: StartLocation::default(),
+            security: SecurityWeights::default(),
+            shape: GalaxyShape::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        for difficulty in Difficulty::values() {
+            let mut config = GameConfig::default();
+            config.set_difficulty(*difficulty);
+            assert_eq!(config.validate(), Ok(()));
+            assert_eq!(
+                difficulty.to_string().to_lowercase().parse(),
+                Ok(*difficulty)
+            );
         }
+
+        let mut config = GameConfig {
+            number_of_systems: 100,
+            ..GameConfig::default()
+        };
+        assert!(config.validate().is_err());
+        config.number_of_sectors = 10;
+        config.planets_per_system = 0.;
+        assert!(config.validate().is_err());
+        config.planets_per_system = 3.;
+        config.security.high = -1.;
+        assert!(config.validate().is_err());
+        config.security.high = 1.;
+        config.shape = GalaxyShape::Ring {
+            radius: 2.,
+            width: 0.,
+        };
+        assert!(config.validate().is_err());
     }
 }
@@ -0,0 +1,137 @@
+use rand::Rng;
+use std::collections::{BTreeSet, HashMap};
+
+/// Symbol marking the start and end of a sequence.
+const BOUNDARY: char = '\0';
+
+/// Character level Markov model of the given order, backing off to shorter
+/// contexts for contexts which have not been observed.
+pub struct MarkovModel {
+    order: usize,
+    /// All observed symbols, sorted to make sampling deterministic.
+    support: Vec<char>,
+    /// Cumulative weights of each symbol in the support, per context.
+    distributions: HashMap<Vec<char>, Vec<f64>>,
+}
+
+impl MarkovModel {
+    /// Creates a model trained on the given sequences.
+    /// Every symbol is given the prior weight in addition to its count.
+    pub fn new<'a, I: Iterator<Item = &'a str>>(sequences: I, order: usize, prior: f64) -> Self {
+        let mut support = BTreeSet::new();
+        support.insert(BOUNDARY);
+        let mut counts = HashMap::new();
+        for sequence in sequences {
+            support.extend(sequence.chars());
+            MarkovModel::observe(&mut counts, order, sequence);
+        }
+        let support = support.into_iter().collect::<Vec<_>>();
+
+        let distributions = counts
+            .into_iter()
+            .map(|(context, counts)| {
+                let mut total = 0.;
+                let cumulative = support
+                    .iter()
+                    .map(|symbol| {
+                        total += prior + counts.get(symbol).unwrap_or(&0.);
+                        total
+                    })
+                    .collect();
+                (context, cumulative)
+            })
+            .collect();
+
+        MarkovModel {
+            order,
+            support,
+            distributions,
+        }
+    }
+
+    /// Counts each symbol of the sequence for all contexts up to the order.
+    fn observe(counts: &mut HashMap<Vec<char>, HashMap<char, f64>>, order: usize, sequence: &str) {
+        let symbols = (0..order)
+            .map(|_| BOUNDARY)
+            .chain(sequence.chars())
+            .chain(Some(BOUNDARY))
+            .collect::<Vec<_>>();
+        for index in order..symbols.len() {
+            let context = &symbols[index - order..index];
+            for start in 0..=context.len() {
+                *counts
+                    .entry(context[start..].to_vec())
+                    .or_default()
+                    .entry(symbols[index])
+                    .or_insert(0.) += 1.;
+            }
+        }
+    }
+
+    /// Returns the longest observed suffix of the given context.
+    fn backoff(&self, context: &[char]) -> Vec<char> {
+        let mut context = context[context.len().saturating_sub(self.order)..].to_vec();
+        while context.len() < self.order {
+            context.insert(0, BOUNDARY);
+        }
+        while !context.is_empty() && !self.distributions.contains_key(&context) {
+            context.remove(0);
+        }
+        context
+    }
+
+    /// Samples the next symbol given the preceding symbols.
+    fn sample<R: Rng>(&self, gen: &mut R, context: &[char]) -> char {
+        let cumulative = match self.distributions.get(&self.backoff(context)) {
+            Some(cumulative) => cumulative,
+            None => return BOUNDARY,
+        };
+        let sample = gen.gen::<f64>() * cumulative.last().unwrap_or(&0.);
+        cumulative
+            .iter()
+            .position(|&weight| sample <= weight)
+            .map_or(BOUNDARY, |index| self.support[index])
+    }
+
+    /// Generates a new sequence from the model.
+    pub fn generate<R: Rng>(&self, gen: &mut R) -> String {
+        let mut sequence = vec![];
+        loop {
+            match self.sample(gen, &sequence) {
+                BOUNDARY => break,
+                symbol => sequence.push(symbol),
+            }
+        }
+        sequence.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{ChaChaRng, SeedableRng};
+
+    #[test]
+    fn test_deterministic_generation() {
+        let names = ["anna", "annika", "hanna", "johanna", "nina"];
+        let model = MarkovModel::new(names.iter().cloned(), 3, 0.001);
+
+        let seed: &[_] = &[7];
+        let mut first = ChaChaRng::from_seed(seed);
+        let mut second = ChaChaRng::from_seed(seed);
+        for _ in 0..20 {
+            let name = model.generate(&mut first);
+            assert_eq!(name, model.generate(&mut second));
+            assert!(name.chars().all(|symbol| "anikhjo".contains(symbol)));
+        }
+    }
+
+    #[test]
+    fn test_backoff() {
+        let model = MarkovModel::new(["abc"].iter().cloned(), 2, 0.);
+        assert_eq!(model.backoff(&[]), vec![BOUNDARY, BOUNDARY]);
+        assert_eq!(model.backoff(&['x', 'a', 'b']), vec!['a', 'b']);
+        assert_eq!(model.backoff(&['x', 'b']), vec!['b']);
+        assert_eq!(model.backoff(&['x', 'y']), vec![]);
+    }
+}
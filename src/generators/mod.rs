@@ -1,6 +1,5 @@
 use rand::{ChaChaRng, SeedableRng};
 use rayon::prelude::*;
-use statrs::distribution::{Distribution, Normal};
 use std::time::Instant;
 
 use astronomicals::{
@@ -9,13 +8,16 @@ use astronomicals::{
     Galaxy,
 };
 use game_config::GameConfig;
-use resources::{fetch_resource, AstronomicalNamesResource};
+use resources::{fetch_resource, AstronomicalNamesResource, NameStyleResource};
 use utils::Point;
 
+mod markov;
 pub mod names;
 pub mod planets;
 pub mod sectors;
+pub mod shapes;
 pub mod stars;
+pub mod stations;
 pub mod systems;
 
 /// Generate a galaxy with systems etc, will use the provided config to guide
@@ -27,28 +29,30 @@ pub fn generate_galaxy(config: &GameConfig) -> Galaxy {
     // Measure time for generation.
     let now = Instant::now();
 
-    // Clusters are spaced uniformly, systems gaussian.
-    let loc_x = Normal::new(0., config.system_spread).unwrap();
-    let loc_y = Normal::new(0., config.system_spread).unwrap();
-
-    // Generate system locations.
-    let mut locations = vec![];
-    for _ in 0..config.number_of_systems {
-        locations.push(Point::new(
-            loc_x.sample::<ChaChaRng>(&mut rng),
-            loc_y.sample::<ChaChaRng>(&mut rng),
-        ))
-    }
+    // Generate system locations according to the shape of the galaxy.
+    let placements = shapes::place_systems(
+        &config.shape,
+        config.number_of_systems as usize,
+        config.system_spread,
+        &mut rng,
+    );
+    let locations = placements
+        .iter()
+        .map(|placement| placement.location)
+        .collect::<Vec<Point>>();
 
     // Create name generator to be shared mutably.
     let mut name_gen = names::NameGen::from_seed(config.map_seed);
-    name_gen.train(fetch_resource::<AstronomicalNamesResource>().unwrap());
+    name_gen.train(
+        &fetch_resource::<AstronomicalNamesResource>().unwrap(),
+        &fetch_resource::<NameStyleResource>().unwrap(),
+    );
 
-    // Generate sectors
+    // Generate sectors, systems are identified by their generation order.
     let sector_gen = sectors::SectorGen::new();
-    let sectors = sector_gen.generate(config, locations);
+    let mut sectors = sector_gen.generate(config, &locations);
     // Create System generator.
-    let system_gen = systems::SystemGen::new();
+    let system_gen = systems::SystemGen::new(config);
 
     // Generate systems for each cluster in parallel.
     // Fold will generate one vector per thread (per cluster), reduce will
@@ -58,9 +62,15 @@ pub fn generate_galaxy(config: &GameConfig) -> Galaxy {
         .fold(
             Vec::<(SystemBuilder, Vec<PlanetBuilder>)>::new,
             |mut systems: Vec<(SystemBuilder, Vec<PlanetBuilder>)>, sector| {
-                for location in &sector.system_locations {
+                for id in &sector.system_ids {
                     // Generate system
-                    systems.push(system_gen.generate(location.clone(), sector.faction.clone()));
+                    let placement = &placements[id.0 as usize];
+                    systems.push(system_gen.generate(
+                        *id,
+                        placement.location,
+                        placement.age,
+                        sector.faction.clone(),
+                    ));
                 }
                 systems
             },
@@ -74,30 +84,68 @@ pub fn generate_galaxy(config: &GameConfig) -> Galaxy {
         );
 
     // Sort to ensure that naming etc, will be deterministic.
-    builders.sort_by_key(|&(ref system_builder, _)| system_builder.location.unwrap().hash());
+    builders.sort_by_key(|&(ref system_builder, _)| system_builder.id.unwrap());
 
     let systems = builders
         .into_iter()
         .map(|(mut system_builder, planet_builders)| {
-            let hash = system_builder.location.unwrap().hash();
-            name_gen.reseed(hash as u32);
+            name_gen.reseed(system_builder.location.unwrap().seed());
 
-            let (system_name, planet_names) = name_gen.generate(planet_builders.len());
+            let faction = system_builder.faction.clone().unwrap();
+            let (system_name, planet_names) = name_gen.generate(planet_builders.len(), &faction);
 
             let planets: Vec<Planet> = planet_builders
                 .into_iter()
                 .zip(planet_names.into_iter())
-                .map(|(mut builder, name)| builder.name(name).build().unwrap())
+                .map(|(mut builder, name)| {
+                    let mut planet = builder.name(name).build().unwrap();
+                    // Moons are numbered after their planet.
+                    for (index, moon) in planet.moons.iter_mut().enumerate() {
+                        moon.name = format!("{} {}", planet.name, roman_numeral(index + 1));
+                    }
+                    planet
+                })
                 .collect();
 
+            let mut belts = system_builder.belts.take().unwrap_or_default();
+            let numbered = belts.len() > 1;
+            for (index, belt) in belts.iter_mut().enumerate() {
+                belt.name = if numbered {
+                    format!("{} Belt {}", system_name, roman_numeral(index + 1))
+                } else {
+                    format!("{} Belt", system_name)
+                };
+            }
+
+            // Stations are named after the planet they orbit.
+            let stations = system_builder
+                .stations
+                .take()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|mut station| {
+                    station.name = format!("{} {}", planets[station.planet].name, station.kind);
+                    station
+                })
+                .collect::<Vec<_>>();
+
             system_builder
                 .name(system_name)
                 .satelites(planets)
+                .stations(stations)
+                .belts(belts)
                 .build()
                 .unwrap()
         })
         .collect::<Vec<_>>();
 
+    // Name sectors after the systems so that system names do not depend on
+    // the number of sectors.
+    for (index, sector) in sectors.iter_mut().enumerate() {
+        name_gen.reseed(config.map_seed.wrapping_add(index as u32));
+        sector.name = name_gen.generate(0, &sector.faction).0;
+    }
+
     info!(
         "Generated new galaxy containing: {} systems and {} planets taking {} ms",
         systems.len(),
@@ -109,3 +157,34 @@ pub fn generate_galaxy(config: &GameConfig) -> Galaxy {
 
     Galaxy::new(sectors, systems)
 }
+
+/// Returns the given positive number below forty as a roman numeral.
+fn roman_numeral(mut number: usize) -> String {
+    let numerals = [(10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I")];
+    let mut result = String::new();
+    for &(value, numeral) in &numerals {
+        while number >= value {
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roman_numeral() {
+        let numerals = (1..16).map(roman_numeral).collect::<Vec<_>>();
+        assert_eq!(
+            numerals,
+            vec![
+                "I", "II", "III", "IV", "V", "VI", "VII", "VIII", "IX", "X", "XI", "XII", "XIII",
+                "XIV", "XV",
+            ]
+        );
+        assert_eq!(roman_numeral(39), "XXXIX");
+    }
+}
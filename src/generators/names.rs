@@ -1,20 +1,38 @@
 use inflector::Inflector;
 use rand::{ChaChaRng, Rng, SeedableRng};
-use resources::AstronomicalNamesResource;
 use statrs::distribution::{Categorical, Distribution};
+use std::collections::{HashMap, HashSet};
 
-/// Name generator which generates based on names given in training data.
+use entities::Faction;
+use generators::markov::MarkovModel;
+use resources::{AstronomicalNamesResource, NameStyleResource};
+
+/// Name generator which generates names using Markov models trained on the
+/// name corpora of each faction style.
 pub struct NameGen {
     rng: ChaChaRng,
-    base_names: Vec<String>,
-    cache: Vec<String>,
+    models: Vec<MarkovModel>,
+    /// Index of the model used for each faction.
+    styles: HashMap<Faction, usize>,
+    default_style: usize,
+    /// Training names and names already generated, which are not reused.
+    used: HashSet<String>,
     greek_suffix: Vec<String>,
     roman_suffix: Vec<String>,
     decorator_suffix: Vec<String>,
     scientific_names: Vec<String>,
+    scientific_count: usize,
 }
 
 impl NameGen {
+    const ORDER: usize = 3;
+    const PRIOR: f64 = 0.001;
+    const MIN_LENGTH: usize = 4;
+    const MAX_LENGTH: usize = 8;
+    /// Attempts at generating a new name before falling back to scientific
+    /// names.
+    const MAX_ATTEMPTS: usize = 100;
+
     /// Creates a new NameGen with the given seed.
     pub fn from_seed(seed: u32) -> NameGen {
         // Create and initialize random generator using seed.
@@ -23,12 +41,15 @@ impl NameGen {
 
         NameGen {
             rng,
-            cache: vec![],
-            base_names: vec![],
+            models: vec![],
+            styles: HashMap::new(),
+            default_style: 0,
+            used: HashSet::new(),
             greek_suffix: vec![],
             roman_suffix: vec![],
             decorator_suffix: vec![],
             scientific_names: vec![],
+            scientific_count: 0,
         }
     }
 
@@ -39,51 +60,85 @@ impl NameGen {
         self.rng.reseed(new_seed);
     }
 
-    /// Trains the underlying model using the given resouce.
-    pub fn train(&mut self, data: AstronomicalNamesResource) {
-        self.base_names = data.names;
-        self.scientific_names = data.scientific_names;
-
-        self.rng.shuffle(&mut self.base_names);
+    /// Trains the underlying models using the given resources, one model per
+    /// style.
+    pub fn train(&mut self, data: &AstronomicalNamesResource, styles: &NameStyleResource) {
+        self.scientific_names = data.scientific_names.clone();
         self.rng.shuffle(&mut self.scientific_names);
 
         // Load suffixes.
-        self.greek_suffix = data.greek;
-        self.roman_suffix = data.roman;
-        self.decorator_suffix = data.decorators;
+        self.greek_suffix = data.greek.clone();
+        self.roman_suffix = data.roman.clone();
+        self.decorator_suffix = data.decorators.clone();
+
+        for names in styles.corpora.values() {
+            self.used.extend(names.iter().cloned());
+        }
+
+        let model = |corpora: &[String]| {
+            let names = corpora
+                .iter()
+                .filter_map(|corpus| styles.corpora.get(corpus))
+                .flat_map(|names| names.iter().map(|name| name.as_str()));
+            MarkovModel::new(names, NameGen::ORDER, NameGen::PRIOR)
+        };
+        self.models = vec![model(&styles.default_style)];
+        self.default_style = 0;
+
+        // Sort to ensure that the model order is deterministic.
+        let mut factions = styles.styles.iter().collect::<Vec<_>>();
+        factions.sort_by_key(|&(faction, _)| faction);
+        for (faction, corpora) in factions {
+            self.styles.insert(faction.clone(), self.models.len());
+            self.models.push(model(corpora));
+        }
     }
 
-    /// Generates a new main name using base names and suffixes.
-    fn generate_name(&mut self) -> Option<String> {
-        if self.cache.is_empty() {
-            if let Some(base_name) = self.base_names.pop() {
-                let suffix_probs = Categorical::new(&[80., 8., 4., 8.]).unwrap();
-
-                let suffixes = match suffix_probs.sample(&mut self.rng) as usize {
-                    1 => self.greek_suffix.clone(),
-                    2 => self.roman_suffix.clone(),
-                    3 => self.decorator_suffix.clone(),
-                    _ => vec![],
-                };
-
-                self.cache.push(base_name.to_title_case());
-                for suffix in suffixes {
-                    self.cache
-                        .push(format!("{} {}", base_name.to_title_case(), suffix));
-                }
-            } else if !self.scientific_names.is_empty() {
-                self.cache.append(&mut self.scientific_names);
+    /// Generates a new unique name in the style of the given faction, falling
+    /// back to scientific names.
+    fn generate_name(&mut self, faction: &Faction) -> String {
+        let model = &self.models[*self.styles.get(faction).unwrap_or(&self.default_style)];
+        for _ in 0..NameGen::MAX_ATTEMPTS {
+            let name = model.generate(&mut self.rng);
+            let length = name.chars().count();
+            if (NameGen::MIN_LENGTH..=NameGen::MAX_LENGTH).contains(&length)
+                && !self.used.contains(&name)
+            {
+                self.used.insert(name.clone());
+                return name.to_title_case();
             }
         }
-        self.cache.pop()
+
+        self.scientific_names.pop().unwrap_or_else(|| {
+            self.scientific_count += 1;
+            format!("GX-{}", self.scientific_count)
+        })
+    }
+
+    /// Generates a new main name, possibly with a suffix.
+    fn generate_main_name(&mut self, faction: &Faction) -> String {
+        let base_name = self.generate_name(faction);
+        let suffix_probs = Categorical::new(&[80., 8., 4., 8.]).unwrap();
+
+        let suffix = {
+            let suffixes = match suffix_probs.sample(&mut self.rng) as usize {
+                1 => &self.greek_suffix,
+                2 => &self.roman_suffix,
+                3 => &self.decorator_suffix,
+                _ => return base_name,
+            };
+            self.rng.choose(suffixes).cloned()
+        };
+        match suffix {
+            Some(suffix) => format!("{} {}", base_name, suffix),
+            None => base_name,
+        }
     }
 
-    /// Attempts to generate a new name main name and several secondary names from the model.
-    pub fn generate(&mut self, subcount: usize) -> (String, Vec<String>) {
-        let main_name = self.generate_name().unwrap_or_else(|| {
-            error!("Failed to generate main system name");
-            panic!()
-        });
+    /// Generates a new main name and several secondary names in the style of
+    /// the given faction.
+    pub fn generate(&mut self, subcount: usize, faction: &Faction) -> (String, Vec<String>) {
+        let main_name = self.generate_main_name(faction);
 
         let subname_type = Categorical::new(&[10., 40., 40.]).unwrap();
 
@@ -93,24 +148,84 @@ impl NameGen {
         ];
 
         match subname_type.sample(&mut self.rng) as usize {
-            0 => {
+            0 if subcount > 0 => {
                 for _ in 1..subcount {
-                    let sub_name = self
-                        .generate_name()
-                        .unwrap_or_else(|| String::from("Unnamed"));
+                    let sub_name = self.generate_name(faction);
                     sub_names.push(sub_name);
                 }
                 sub_names.push(main_name.clone());
             }
-            1 => for index in 1..subcount {
-                sub_names.push(format!("{} A-{}", main_name, index));
-            },
-            _ => for character in alphabet.iter().take(subcount) {
-                sub_names.push(format!("{} {}", main_name, character));
-            },
+            2 if subcount <= alphabet.len() => {
+                for character in alphabet.iter().take(subcount) {
+                    sub_names.push(format!("{} {}", main_name, character));
+                }
+            }
+            _ => {
+                for index in 1..=subcount {
+                    sub_names.push(format!("{} A-{}", main_name, index));
+                }
+            }
         };
         self.rng.shuffle(&mut sub_names);
 
         (main_name, sub_names)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use resources::fetch_resource;
+
+    #[test]
+    fn test_unique_names() {
+        let names = AstronomicalNamesResource {
+            scientific_names: vec![String::from("CZ-1")],
+            greek: vec![String::from("Alpha")],
+            roman: vec![String::from("II")],
+            decorators: vec![String::from("Prime")],
+        };
+        let mut corpora = HashMap::new();
+        corpora.insert(
+            String::from("test"),
+            vec![
+                String::from("anna"),
+                String::from("hanna"),
+                String::from("nina"),
+            ],
+        );
+        let styles = NameStyleResource {
+            styles: HashMap::new(),
+            default_style: vec![String::from("test")],
+            corpora,
+        };
+        let mut gen = NameGen::from_seed(1);
+        gen.train(&names, &styles);
+
+        // Names are never reused, even once the model is exhausted.
+        let generated = (0..200)
+            .map(|_| gen.generate_name(&Faction::new("Empire")))
+            .collect::<HashSet<_>>();
+        assert_eq!(generated.len(), 200);
+        assert!(!generated.contains("Anna"));
+        assert!(generated.contains("CZ-1"));
+    }
+
+    #[test]
+    fn test_name_count() {
+        let mut gen = NameGen::from_seed(1);
+        gen.train(
+            &fetch_resource::<AstronomicalNamesResource>().unwrap(),
+            &fetch_resource::<NameStyleResource>().unwrap(),
+        );
+
+        // Every planet gets a name, whichever naming scheme is picked.
+        for seed in 0..20 {
+            gen.reseed(seed);
+            for subcount in 0..20 {
+                let (_, names) = gen.generate(subcount, &Faction::new("Empire"));
+                assert_eq!(names.len(), subcount);
+            }
+        }
+    }
+}
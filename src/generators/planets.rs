@@ -1,40 +1,79 @@
 use rand::Rng;
-use statrs::distribution::{Distribution, Exponential, Gamma};
-use std::f64::consts::PI;
+use statrs::distribution::{Distribution, Exponential, Gamma, Poisson};
+use std::{cmp::Ordering, f64::consts::PI};
 
 use astronomicals::{
-    planet::{PlanetBuilder, PlanetEconomy, PlanetType},
+    belt::AsteroidBelt,
+    planet::{Planet, PlanetBuilder, PlanetEconomy, PlanetType},
     Star,
 };
 
+/// Mass of the Earth in kilograms.
+pub const EARTH_MASS: f64 = 5.972e24;
+/// Mass of the Sun in kilograms.
+pub const SOLAR_MASS: f64 = 1.989e30;
+/// Radius of the Earth in light seconds.
+pub const EARTH_RADIUS: f64 = 6.371e6 / 299_792_458.;
+
 /// Basic non deterministic name generator for generating new Planets which
 /// are similar to the trained data provided.
 pub struct PlanetGen {
     mass_gen: Exponential,
     orbit_dist_gen: Gamma,
+    /// Minimal distance to star.
+    min_dist: f64,
 }
 
 impl PlanetGen {
-    /// Minimal distance to star.
-    // TODO: Move to config.
-    const MIN_DIST: f64 = 500.;
-
-    /// Create a new Planet generator which loads the star resources needed.
-    pub fn new() -> Self {
+    /// Create a new Planet generator placing planets at least the given
+    /// distance from their star.
+    pub fn new(min_dist: f64) -> Self {
         let mass_gen = Exponential::new(1. / 3.).unwrap();
         let orbit_dist_gen = Gamma::new(0.28, 0.17).unwrap();
         PlanetGen {
             mass_gen,
             orbit_dist_gen,
+            min_dist,
         }
     }
 
-    /// Calculate planet surface temperature from star luminosity and distance
-    /// to it. Uses the Bond albedo for the Earth.
+    /// Calculate planet surface temperature from the luminosity of the stars
+    /// and distance to them. Uses the Bond albedo for the Earth, and planets in
+    /// the habitable zone are warmed by the greenhouse effect of the Earth,
+    /// which fades out over a tenth of the zone width past either edge.
     pub fn calculate_surface_temperature(orbit_distance: f64, star: &Star) -> f64 {
-        (star.luminosity * 3.846 * 10f64.powi(26) * (1. - 0.29)
+        let temperature = (star.total_luminosity() * 3.846 * 10f64.powi(26) * (1. - 0.29)
             / (16. * PI * (299_692_458. * orbit_distance).powi(2) * 5.670_373 * 10f64.powi(-8)))
-            .powf(0.25)
+        .powf(0.25);
+        let (inner, outer) = star.habitable_zone();
+        let fade = 0.1 * (outer - inner);
+        let greenhouse = ((orbit_distance - inner + fade) / fade)
+            .min((outer + fade - orbit_distance) / fade)
+            .clamp(0., 1.);
+        temperature + 33. * greenhouse
+    }
+
+    /// Calculate the radius relative to the Earth from the mass, using the
+    /// mass-radius relations of rocky and Neptune-like planets.
+    pub fn calculate_radius(mass: f64) -> f64 {
+        if mass < 2.04 {
+            mass.powf(0.279)
+        } else {
+            0.808 * mass.powf(0.589)
+        }
+    }
+
+    /// Calculate the surface gravity relative to the Earth from the mass and
+    /// radius.
+    pub fn calculate_gravity(mass: f64, radius: f64) -> f64 {
+        mass / radius.powi(2)
+    }
+
+    /// Calculate the orbital period in days using Kepler's third law, from
+    /// the orbit distance in light seconds and the orbited mass in kilograms.
+    pub fn calculate_orbital_period(orbit_distance: f64, mass: f64) -> f64 {
+        let distance = orbit_distance * 299_792_458.;
+        2. * PI * (distance.powi(3) / (6.674e-11 * mass)).sqrt() / 86_400.
     }
 
     /// Predict the planet type based on surface_temperature and mass.
@@ -80,15 +119,147 @@ impl PlanetGen {
         let mass = self.mass_gen.sample(gen);
 
         // Magic constant, needed to scale back since scaling needed to fit gamma.
-        let orbit_distance = PlanetGen::MIN_DIST + 1000. * self.orbit_dist_gen.sample(gen);
+        let orbit_distance = self.min_dist + 1000. * self.orbit_dist_gen.sample(gen);
 
-        // TODO: Make something a bit more accurate regarding planet type and gravity.
+        let radius = PlanetGen::calculate_radius(mass);
         Some(
             PlanetBuilder::default()
                 .mass(mass)
+                .radius(radius)
                 .orbit_distance(orbit_distance)
-                .gravity(mass)
+                .gravity(PlanetGen::calculate_gravity(mass, radius))
                 .to_owned(),
         )
     }
+
+    /// Generates unnamed moons for a planet with the given mass and surface
+    /// temperature, ordered by distance to the planet. Heavier planets hold
+    /// more moons.
+    pub fn generate_moons<R: Rng>(
+        &self,
+        gen: &mut R,
+        planet_mass: f64,
+        surface_temperature: f64,
+    ) -> Vec<Planet> {
+        let count = match Poisson::new((planet_mass / 3.).min(4.)) {
+            Ok(distribution) => distribution.sample(gen).round() as usize,
+            Err(_) => 0,
+        };
+        let planet_radius = PlanetGen::calculate_radius(planet_mass) * EARTH_RADIUS;
+        let mut moons = (0..count)
+            .map(|_| {
+                let mass = planet_mass * gen.gen_range(0.0005, 0.02);
+                let radius = PlanetGen::calculate_radius(mass);
+                // The Moon orbits at 60 Earth radii, little more than one
+                // light second.
+                let orbit_distance = planet_radius * gen.gen_range(15., 150.);
+                let planet_type = PlanetGen::predict_type(gen, surface_temperature, mass);
+                let economic_type = PlanetGen::predict_economy(gen, &planet_type);
+                Planet {
+                    name: String::new(),
+                    mass,
+                    radius,
+                    gravity: PlanetGen::calculate_gravity(mass, radius),
+                    orbit_distance,
+                    orbital_period: PlanetGen::calculate_orbital_period(
+                        orbit_distance,
+                        planet_mass * EARTH_MASS,
+                    ),
+                    surface_temperature,
+                    planet_type,
+                    economic_type,
+                    moons: vec![],
+                }
+            })
+            .collect::<Vec<_>>();
+        moons.sort_by(|a, b| {
+            a.orbit_distance
+                .partial_cmp(&b.orbit_distance)
+                .unwrap_or(Ordering::Equal)
+        });
+        moons
+    }
+
+    /// Generates an unnamed asteroid belt around the given star, mostly mined
+    /// for ore.
+    pub fn generate_belt<R: Rng>(&self, gen: &mut R, star: &Star) -> AsteroidBelt {
+        let orbit_distance = self.min_dist + 1000. * self.orbit_dist_gen.sample(gen);
+        AsteroidBelt {
+            name: String::new(),
+            mass: gen.gen_range(0.0005, 0.005),
+            orbit_distance,
+            orbital_period: PlanetGen::calculate_orbital_period(
+                orbit_distance,
+                star.total_mass() * SOLAR_MASS,
+            ),
+            economic_type: if gen.gen::<f64>() < 0.8 {
+                PlanetEconomy::Extraction
+            } else {
+                PlanetEconomy::Refinary
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::star::StarType;
+    use rand::{ChaChaRng, SeedableRng};
+
+    #[test]
+    fn test_physics() {
+        assert!((PlanetGen::calculate_radius(1.) - 1.).abs() < 1e-9);
+        // Heavy planets grow slower, without a jump between the relations.
+        let (rocky, neptune) = (
+            PlanetGen::calculate_radius(2.03),
+            PlanetGen::calculate_radius(2.05),
+        );
+        assert!((rocky - neptune).abs() < 0.1);
+
+        assert_eq!(PlanetGen::calculate_gravity(1., 1.), 1.);
+        assert_eq!(PlanetGen::calculate_gravity(2., 2.), 0.5);
+
+        // The Earth around the Sun and the Moon around the Earth.
+        let year = PlanetGen::calculate_orbital_period(499., SOLAR_MASS);
+        assert!((year - 365.25).abs() < 1.);
+        let month = PlanetGen::calculate_orbital_period(1.28, EARTH_MASS);
+        assert!((month - 27.3).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_surface_temperature() {
+        let sun = Star::new(1., 1., StarType::Single);
+        let (inner, outer) = sun.habitable_zone();
+        let temperature = |distance| PlanetGen::calculate_surface_temperature(distance, &sun);
+
+        // The Earth is warmed to about 288 K.
+        assert!((temperature(499.) - 288.).abs() < 2.);
+
+        // Warming is continuous at the edges of the habitable zone.
+        for &edge in &[inner, outer] {
+            assert!((temperature(edge - 0.01) - temperature(edge + 0.01)).abs() < 0.1);
+        }
+        let beyond = outer + 0.2 * (outer - inner);
+        assert!(temperature(outer) - temperature(beyond) > 33.);
+    }
+
+    #[test]
+    fn test_moon_orbits() {
+        let seed: &[_] = &[2];
+        let mut rng = ChaChaRng::from_seed(seed);
+        let gen = PlanetGen::new(500.);
+        let moons = (0..20)
+            .flat_map(|_| gen.generate_moons(&mut rng, 10., 200.))
+            .collect::<Vec<_>>();
+        assert!(!moons.is_empty());
+
+        // Moons orbit outside the planet, but well within a few light seconds.
+        let planet_radius = PlanetGen::calculate_radius(10.) * EARTH_RADIUS;
+        for moon in &moons {
+            assert!(moon.orbit_distance > planet_radius);
+            assert!(moon.orbit_distance < 10.);
+            assert!(moon.mass < 10.);
+        }
+    }
 }
@@ -1,16 +1,10 @@
-use rand::{seq, ChaChaRng, SeedableRng};
+use rand::{ChaChaRng, Rng, SeedableRng};
 use rayon::prelude::*;
-use std::{
-    collections::HashMap,
-    iter::FromIterator,
-    sync::atomic::{AtomicBool, Ordering},
-    time::Instant,
-    usize::MAX,
-};
-
-use astronomicals::Sector;
-use entities::Faction;
+use std::{cmp::Ordering, time::Instant, usize::MAX};
+
+use astronomicals::{system::SystemId, Sector};
 use game_config::GameConfig;
+use resources::{fetch_resource, FactionResource};
 use utils::Point;
 
 /// Used for generating sectors.
@@ -23,7 +17,8 @@ impl SectorGen {
     }
 
     /// Split the systems in to a set number of clusters using K-means.
-    pub fn generate(&self, config: &GameConfig, system_locations: Vec<Point>) -> Vec<Sector> {
+    /// Systems are identified by their index in the given locations.
+    pub fn generate(&self, config: &GameConfig, system_locations: &[Point]) -> Vec<Sector> {
         // Measure time for generation.
         let now = Instant::now();
 
@@ -31,130 +26,347 @@ impl SectorGen {
         let seed: &[_] = &[config.map_seed as u32];
         let mut rng: ChaChaRng = ChaChaRng::from_seed(seed);
 
-        // Setup initial centroids
-        let mut centroids =
-            seq::sample_iter(&mut rng, system_locations.iter(), config.number_of_sectors)
-                .unwrap()
-                .into_iter()
-                .cloned()
-                .collect::<Vec<_>>();
-
-        // System to cluster_id mapping
-        let mut cluster_map: HashMap<Point, usize> =
-            HashMap::from_iter(system_locations.into_iter().map(|point| (point, 0)));
-
-        // Run K means until convergence, i.e until no reassignments
-        let mut has_assigned = true;
-        while has_assigned {
-            let wrapped_assigned = AtomicBool::new(false);
-
-            // Assign to closest centroid
-            cluster_map
-                .par_iter_mut()
-                .for_each(|(system_location, cluster_id)| {
-                    let mut closest_cluster = *cluster_id;
-                    let mut closest_distance = system_location.distance(&centroids[*cluster_id]);
-                    for (i, centroid) in centroids.iter().enumerate() {
-                        let distance = system_location.distance(centroid);
-                        if distance < closest_distance {
-                            wrapped_assigned.store(true, Ordering::Relaxed);
-                            closest_cluster = i;
-                            closest_distance = distance;
-                        }
-                    }
-                    *cluster_id = closest_cluster;
-                });
-
-            has_assigned = wrapped_assigned.load(Ordering::Relaxed);
-
-            // Calculate new centroids
-            centroids
-                //.par_iter_mut()
-                .iter_mut()
-                .enumerate()
-                .for_each(|(id, centroid)| {
-                    let mut count = 0.;
-                    let mut new_centroid = Point::origin();
-                    for (system_location, _) in cluster_map.iter()
-                        .filter(|&(_, c_id)| *c_id == id) {
-                            new_centroid += *system_location;
-                            count += 1.;
-                        }
-                    new_centroid *= 1. / count;
-                    *centroid = new_centroid;
-                });
-        }
-
-        // Setup cluster vectors
-        let mut sector_vecs =
-            (0..config.number_of_sectors).fold(Vec::<Vec<Point>>::new(), |mut sectors, _| {
-                sectors.push(vec![]);
-                sectors
-            });
+        // There can be no more sectors than systems.
+        let count = config.number_of_sectors.min(system_locations.len());
+        let (centroids, cluster_map) = cluster(
+            system_locations,
+            count,
+            config.sector_iterations,
+            config.balance_sectors,
+            &mut rng,
+        );
 
         // Map systems to final cluster
-        for (system_location, id) in cluster_map {
-            sector_vecs[id].push(system_location);
+        let mut sector_vecs = vec![vec![]; count];
+        for (system_id, id) in cluster_map.into_iter().enumerate() {
+            sector_vecs[id].push(SystemId(system_id as u32));
         }
 
-        // Create sector for each cluster
+        // Create sector for each cluster, with the faction seeded by its
+        // location so that sectors of equal size differ.
+        let factions = fetch_resource::<FactionResource>().unwrap();
         let sectors = sector_vecs
             .into_iter()
-            .map(|system_locations| {
-                let sector_seed: &[_] = &[system_locations.len() as u32];
+            .zip(centroids)
+            .map(|(system_ids, centroid)| {
+                let sector_seed: &[_] = &[config.map_seed, centroid.seed()];
                 let mut faction_rng: ChaChaRng = SeedableRng::from_seed(sector_seed);
                 Sector {
-                    system_locations,
-                    faction: Faction::random_faction(&mut faction_rng),
+                    name: String::new(),
+                    system_ids,
+                    border: vec![],
+                    faction: factions.random_faction(&mut faction_rng),
+                    neighbors: vec![],
+                    contested_for: 0,
                 }
             })
             .collect::<Vec<Sector>>();
 
         info!(
-            "Mapped galaxy into {} sectors of {} systems, avg size: {}, 
-          max size {}, min size {}, taking {} ms \n 
-          Sectors include: {} Cartel, {} Empire, {} Federation, {} Independent",
+            "Mapped galaxy into {} sectors of {} systems, avg size: {},
+          max size {}, min size {}, taking {} ms",
             sectors.len(),
             sectors
                 .iter()
-                .fold(0, |acc, ref sec| acc + sec.system_locations.len()),
+                .fold(0, |acc, ref sec| acc + sec.system_ids.len()),
             sectors
                 .iter()
-                .fold(0, |acc, ref sec| acc + sec.system_locations.len())
-                / sectors.len(),
+                .fold(0, |acc, ref sec| acc + sec.system_ids.len())
+                / sectors.len().max(1),
             sectors
                 .iter()
-                .fold(0, |acc, ref sec| acc.max(sec.system_locations.len())),
+                .fold(0, |acc, ref sec| acc.max(sec.system_ids.len())),
             sectors
                 .iter()
-                .fold(MAX, |acc, ref sec| acc.min(sec.system_locations.len())),
-            ((now.elapsed().as_secs() * 1_000) + u64::from(now.elapsed().subsec_millis())),
-            sectors
-                .iter()
-                .fold(0, |acc, ref sec| acc + match sec.faction {
-                    Faction::Cartel => 1,
-                    _ => 0,
-                }),
-            sectors
-                .iter()
-                .fold(0, |acc, ref sec| acc + match sec.faction {
-                    Faction::Empire => 1,
-                    _ => 0,
-                }),
-            sectors
+                .fold(MAX, |acc, ref sec| acc.min(sec.system_ids.len())),
+            ((now.elapsed().as_secs() * 1_000) + u64::from(now.elapsed().subsec_millis()))
+        );
+        info!(
+            "Sectors include: {}",
+            factions
+                .factions
                 .iter()
-                .fold(0, |acc, ref sec| acc + match sec.faction {
-                    Faction::Federation => 1,
-                    _ => 0,
-                }),
-            sectors
+                .map(|definition| format!(
+                    "{} {}",
+                    sectors
+                        .iter()
+                        .filter(|sec| sec.faction == definition.id)
+                        .count(),
+                    definition.id
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        sectors
+    }
+}
+
+/// Clusters the locations into the given number of non-empty clusters using
+/// K-means with K-means++ seeding, running at most the given number of
+/// iterations. Returns the centroids and the cluster of each location.
+/// Balanced clusters hold at most the average number of locations rounded up.
+fn cluster<R: Rng>(
+    locations: &[Point],
+    count: usize,
+    max_iterations: u32,
+    balance: bool,
+    rng: &mut R,
+) -> (Vec<Point>, Vec<usize>) {
+    if count == 0 {
+        return (vec![], vec![0; locations.len()]);
+    }
+    let mut centroids = seed_centroids(locations, count, rng);
+    let mut cluster_map = vec![0; locations.len()];
+
+    // Run K means until convergence, i.e until no reassignments.
+    for iteration in 0..max_iterations {
+        let assigned = assign(locations, &centroids, &mut cluster_map);
+        let refilled = update_centroids(locations, &mut centroids, &mut cluster_map);
+        if !assigned && !refilled {
+            debug!("K-means converged after {} iterations", iteration + 1);
+            break;
+        }
+    }
+
+    if balance {
+        let capacity = locations.len().div_ceil(count);
+        balance_clusters(locations, &centroids, &mut cluster_map, capacity);
+        update_centroids(locations, &mut centroids, &mut cluster_map);
+    }
+    (centroids, cluster_map)
+}
+
+/// Picks initial centroids among the locations using K-means++, where each
+/// new centroid is picked with probability proportional to the squared
+/// distance to the closest centroid picked so far.
+fn seed_centroids<R: Rng>(locations: &[Point], count: usize, rng: &mut R) -> Vec<Point> {
+    let mut centroids = vec![locations[rng.gen_range(0, locations.len())]];
+    let mut distances = locations
+        .par_iter()
+        .map(|location| location.distance_squared(&centroids[0]))
+        .collect::<Vec<_>>();
+
+    while centroids.len() < count {
+        let total = distances.iter().sum::<f64>();
+        let next = if total > 0. {
+            let mut target = rng.gen_range(0., total);
+            distances
                 .iter()
-                .fold(0, |acc, ref sec| acc + match sec.faction {
-                    Faction::Independent => 1,
-                    _ => 0,
+                .position(|&distance| {
+                    target -= distance;
+                    target < 0.
                 })
+                .unwrap_or(locations.len() - 1)
+        } else {
+            // All locations coincide with centroids, any will do.
+            rng.gen_range(0, locations.len())
+        };
+        let centroid = locations[next];
+        centroids.push(centroid);
+        distances
+            .par_iter_mut()
+            .zip(locations.par_iter())
+            .for_each(|(distance, location)| {
+                *distance = distance.min(location.distance_squared(&centroid));
+            });
+    }
+    centroids
+}
+
+/// Returns the index of the closest centroid.
+fn closest(location: &Point, centroids: &[Point]) -> usize {
+    centroids
+        .iter()
+        .map(|centroid| location.distance_squared(centroid))
+        .enumerate()
+        .fold((0, f64::INFINITY), |best, (index, distance)| {
+            if distance < best.1 {
+                (index, distance)
+            } else {
+                best
+            }
+        })
+        .0
+}
+
+/// Assigns each location to the closest centroid, returning true if any
+/// location changed cluster.
+fn assign(locations: &[Point], centroids: &[Point], cluster_map: &mut [usize]) -> bool {
+    cluster_map
+        .par_iter_mut()
+        .zip(locations.par_iter())
+        .map(|(cluster_id, location)| {
+            let closest = closest(location, centroids);
+            let changed = closest != *cluster_id;
+            *cluster_id = closest;
+            changed
+        })
+        .reduce(|| false, |a, b| a || b)
+}
+
+/// Moves each centroid to the mean of its locations in a single pass. Empty
+/// clusters take over the location farthest from its centroid, returning true
+/// if any did.
+fn update_centroids(
+    locations: &[Point],
+    centroids: &mut [Point],
+    cluster_map: &mut [usize],
+) -> bool {
+    let count = centroids.len();
+    let (sums, mut sizes) = locations
+        .par_iter()
+        .zip(cluster_map.par_iter())
+        .fold(
+            || (vec![Point::origin(); count], vec![0usize; count]),
+            |(mut sums, mut sizes), (location, &id)| {
+                sums[id] += *location;
+                sizes[id] += 1;
+                (sums, sizes)
+            },
+        )
+        .reduce(
+            || (vec![Point::origin(); count], vec![0usize; count]),
+            |(mut sums, mut sizes), (other_sums, other_sizes)| {
+                for id in 0..count {
+                    sums[id] += other_sums[id];
+                    sizes[id] += other_sizes[id];
+                }
+                (sums, sizes)
+            },
         );
 
-        sectors
+    for (centroid, (mut sum, &size)) in centroids.iter_mut().zip(sums.into_iter().zip(&sizes)) {
+        if size > 0 {
+            sum *= 1. / size as f64;
+            *centroid = sum;
+        }
+    }
+
+    let mut refilled = false;
+    for empty in 0..count {
+        if sizes[empty] > 0 {
+            continue;
+        }
+        // Take the worst fitting location of a cluster which can spare one.
+        let farthest = (0..locations.len())
+            .filter(|&index| sizes[cluster_map[index]] > 1)
+            .max_by(|&a, &b| {
+                let distance = |index: usize| {
+                    locations[index].distance_squared(&centroids[cluster_map[index]])
+                };
+                distance(a)
+                    .partial_cmp(&distance(b))
+                    .unwrap_or(Ordering::Equal)
+            });
+        if let Some(index) = farthest {
+            sizes[cluster_map[index]] -= 1;
+            sizes[empty] = 1;
+            cluster_map[index] = empty;
+            centroids[empty] = locations[index];
+            refilled = true;
+        }
+    }
+    refilled
+}
+
+/// Reassigns the locations so that no cluster holds more than the given
+/// capacity. Locations which lose the most by not getting their closest
+/// centroid pick first, each taking the closest centroid with room left.
+fn balance_clusters(
+    locations: &[Point],
+    centroids: &[Point],
+    cluster_map: &mut [usize],
+    capacity: usize,
+) {
+    let distances = locations
+        .par_iter()
+        .map(|location| {
+            centroids
+                .iter()
+                .map(|centroid| location.distance(centroid))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // Regret is the difference between the closest and second closest.
+    let regrets = distances
+        .iter()
+        .map(|distances| {
+            let (first, second) = distances.iter().fold(
+                (f64::INFINITY, f64::INFINITY),
+                |(first, second), &distance| {
+                    if distance < first {
+                        (distance, first)
+                    } else {
+                        (first, second.min(distance))
+                    }
+                },
+            );
+            if second.is_finite() {
+                second - first
+            } else {
+                0.
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut order = (0..locations.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        regrets[b]
+            .partial_cmp(&regrets[a])
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let mut sizes = vec![0; centroids.len()];
+    for index in order {
+        let best = (0..centroids.len())
+            .filter(|&id| sizes[id] < capacity)
+            .min_by(|&a, &b| {
+                distances[index][a]
+                    .partial_cmp(&distances[index][b])
+                    .unwrap_or(Ordering::Equal)
+            });
+        // The capacity always leaves room for every location.
+        if let Some(id) = best {
+            sizes[id] += 1;
+            cluster_map[index] = id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_empty_clusters() {
+        let seed: &[_] = &[1];
+        let mut rng = ChaChaRng::from_seed(seed);
+        // Mostly coinciding locations would leave clusters empty.
+        let mut locations = vec![Point::new(1., 1.); 20];
+        locations.extend((0..5).map(|i| Point::new(f64::from(i) * 10., 0.)));
+
+        let (centroids, cluster_map) = cluster(&locations, 8, 50, false, &mut rng);
+        assert_eq!(centroids.len(), 8);
+        for id in 0..8 {
+            assert!(cluster_map.contains(&id));
+        }
+    }
+
+    #[test]
+    fn test_balanced_clusters() {
+        let seed: &[_] = &[2];
+        let mut rng = ChaChaRng::from_seed(seed);
+        // A dense blob next to a sparse one.
+        let locations = (0..90)
+            .map(|i| Point::new(f64::from(i % 10) * 0.1, f64::from(i / 10) * 0.1))
+            .chain((0..10).map(|i| Point::new(50. + f64::from(i), 0.)))
+            .collect::<Vec<_>>();
+
+        let (_, cluster_map) = cluster(&locations, 4, 50, true, &mut rng);
+        for id in 0..4 {
+            let size = cluster_map.iter().filter(|&&cluster| cluster == id).count();
+            assert!(size <= 25);
+        }
     }
 }
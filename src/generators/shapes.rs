@@ -0,0 +1,336 @@
+use rand::Rng;
+use rayon::prelude::*;
+use spade::rtree::RTree;
+use statrs::distribution::{Distribution, Normal};
+use std::f64::consts::PI;
+
+use utils::Point;
+
+/// Overall morphology of a galaxy, with sizes relative to the system spread.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind")]
+pub enum GalaxyShape {
+    /// One of the shapes below with typical parameters, chosen by the seed.
+    Random,
+    /// Systems normally distributed around the center.
+    Normal,
+    /// Central bulge with the given number of arms winding around it, twist
+    /// being the angle turned per unit of distance from the center.
+    Spiral { arms: u32, twist: f64 },
+    /// Spiral with arms starting at the ends of a central bar.
+    BarredSpiral {
+        arms: u32,
+        twist: f64,
+        bar_length: f64,
+    },
+    /// Elongated blob, axis ratio being the length of the minor axis relative
+    /// to the major.
+    Elliptical { axis_ratio: f64 },
+    /// Small core surrounded by a ring at the given radius.
+    Ring { radius: f64, width: f64 },
+    /// Several separate clusters of systems.
+    Clusters { count: u32, size: f64 },
+}
+
+impl GalaxyShape {
+    /// Checks that the parameters of the shape can be used to place systems,
+    /// returning a description of the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = |name: &str, value: f64| {
+            if value > 0. && value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("shape {} must be positive", name))
+            }
+        };
+        let finite = |name: &str, value: f64| {
+            if value.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("shape {} must be finite", name))
+            }
+        };
+        match *self {
+            GalaxyShape::Random | GalaxyShape::Normal => Ok(()),
+            GalaxyShape::Spiral { arms, twist } => {
+                positive("arms", f64::from(arms))?;
+                finite("twist", twist)
+            }
+            GalaxyShape::BarredSpiral {
+                arms,
+                twist,
+                bar_length,
+            } => {
+                positive("arms", f64::from(arms))?;
+                positive("bar_length", bar_length)?;
+                finite("twist", twist)
+            }
+            GalaxyShape::Elliptical { axis_ratio } => {
+                positive("axis_ratio", axis_ratio)?;
+                if axis_ratio > 1. {
+                    Err(String::from("shape axis_ratio must be at most 1"))
+                } else {
+                    Ok(())
+                }
+            }
+            GalaxyShape::Ring { radius, width } => {
+                positive("radius", radius)?;
+                positive("width", width)
+            }
+            GalaxyShape::Clusters { count, size } => {
+                positive("count", f64::from(count))?;
+                positive("size", size)
+            }
+        }
+    }
+}
+
+impl Default for GalaxyShape {
+    fn default() -> GalaxyShape {
+        GalaxyShape::Random
+    }
+}
+
+/// Generated location of a system together with the age of the surrounding
+/// stellar population, from 0 for the youngest to 1 for the oldest.
+pub struct Placement {
+    pub location: Point,
+    pub age: f64,
+}
+
+/// Fraction of spiral galaxies in the central bulge or bar.
+const BULGE_FRACTION: f64 = 0.2;
+/// Length of spiral arms, relative to the system spread.
+const ARM_LENGTH: f64 = 3.;
+/// Fraction of ring galaxies in the core.
+const CORE_FRACTION: f64 = 0.1;
+/// Radius within which neighbors are counted when estimating density,
+/// relative to the system spread.
+const DENSITY_RADIUS: f64 = 0.2;
+
+/// Places the given number of systems according to the shape, with the
+/// systems in the densest regions having the oldest populations.
+pub fn place_systems<R: Rng>(
+    shape: &GalaxyShape,
+    count: usize,
+    spread: f64,
+    rng: &mut R,
+) -> Vec<Placement> {
+    let shape = match *shape {
+        GalaxyShape::Random => random_shape(rng),
+        ref shape => shape.clone(),
+    };
+    info!("Placing systems in galaxy of shape {:?}", shape);
+
+    let locations = match shape {
+        GalaxyShape::Clusters {
+            count: clusters,
+            size,
+        } => {
+            // Cluster centers are spread uniformly over a disc.
+            let centers = (0..clusters.max(1))
+                .map(|_| {
+                    polar(
+                        3. * spread * rng.gen::<f64>().sqrt(),
+                        2. * PI * rng.gen::<f64>(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            (0..count)
+                .map(|_| {
+                    let center = *rng.choose(&centers).unwrap();
+                    center + gaussian(rng, size * spread)
+                })
+                .collect()
+        }
+        ref shape => (0..count)
+            .map(|_| place_system(shape, spread, rng))
+            .collect::<Vec<_>>(),
+    };
+
+    let ages = density_ranks(&locations, DENSITY_RADIUS * spread);
+    locations
+        .into_iter()
+        .zip(ages)
+        .map(|(location, age)| Placement { location, age })
+        .collect()
+}
+
+/// Picks a shape with typical parameters.
+fn random_shape<R: Rng>(rng: &mut R) -> GalaxyShape {
+    match rng.gen_range(0, 5) {
+        0 => GalaxyShape::Spiral {
+            arms: rng.gen_range(2, 6),
+            twist: rng.gen_range(1., 2.),
+        },
+        1 => GalaxyShape::BarredSpiral {
+            arms: 2,
+            twist: rng.gen_range(1., 2.),
+            bar_length: rng.gen_range(0.5, 1.),
+        },
+        2 => GalaxyShape::Elliptical {
+            axis_ratio: rng.gen_range(0.4, 0.9),
+        },
+        3 => GalaxyShape::Ring {
+            radius: rng.gen_range(1.5, 2.5),
+            width: rng.gen_range(0.15, 0.35),
+        },
+        _ => GalaxyShape::Clusters {
+            count: rng.gen_range(3, 7),
+            size: rng.gen_range(0.3, 0.6),
+        },
+    }
+}
+
+/// Places a single system according to the shape.
+fn place_system<R: Rng>(shape: &GalaxyShape, spread: f64, rng: &mut R) -> Point {
+    match *shape {
+        GalaxyShape::Spiral { arms, twist } => {
+            if rng.gen::<f64>() < BULGE_FRACTION {
+                gaussian(rng, 0.3 * spread)
+            } else {
+                arm(rng, arms, twist, 0., spread)
+            }
+        }
+        GalaxyShape::BarredSpiral {
+            arms,
+            twist,
+            bar_length,
+        } => {
+            if rng.gen::<f64>() < BULGE_FRACTION {
+                let x = rng.gen_range(-bar_length, bar_length) * spread;
+                Point::new(x, 0.) + gaussian(rng, 0.1 * spread)
+            } else {
+                arm(rng, arms, twist, bar_length, spread)
+            }
+        }
+        GalaxyShape::Elliptical { axis_ratio } => {
+            let normal = Normal::new(0., spread).unwrap();
+            Point::new(normal.sample(rng), normal.sample(rng) * axis_ratio.max(0.))
+        }
+        GalaxyShape::Ring { radius, width } => {
+            if rng.gen::<f64>() < CORE_FRACTION {
+                gaussian(rng, 0.2 * spread)
+            } else {
+                let distance = Normal::new(radius * spread, width * spread)
+                    .unwrap()
+                    .sample(rng);
+                polar(distance, 2. * PI * rng.gen::<f64>())
+            }
+        }
+        // Clusters are placed together and random shapes resolved before.
+        GalaxyShape::Normal | GalaxyShape::Clusters { .. } | GalaxyShape::Random => {
+            gaussian(rng, spread)
+        }
+    }
+}
+
+/// Places a system along one of the spiral arms, starting at the given
+/// distance from the center.
+fn arm<R: Rng>(rng: &mut R, arms: u32, twist: f64, start: f64, spread: f64) -> Point {
+    let arm = rng.gen_range(0, arms.max(1));
+    // Systems are spread evenly along the arm, thinning out as it widens.
+    let distance = rng.gen_range(0.2, ARM_LENGTH);
+    let angle = 2. * PI * f64::from(arm) / f64::from(arms.max(1)) + twist * distance;
+    let location = polar((start + distance) * spread, angle);
+    location + gaussian(rng, (0.08 + 0.04 * distance) * spread)
+}
+
+/// Returns a normally distributed offset with the given deviation.
+fn gaussian<R: Rng>(rng: &mut R, deviation: f64) -> Point {
+    let normal = Normal::new(0., deviation.max(1e-6)).unwrap();
+    Point::new(normal.sample(rng), normal.sample(rng))
+}
+
+/// Returns the point at the given distance and angle from the origin.
+fn polar(distance: f64, angle: f64) -> Point {
+    Point::new(distance * angle.cos(), distance * angle.sin())
+}
+
+/// Ranks each location by the number of neighbors within the given radius,
+/// from 0 for the sparsest to 1 for the densest.
+fn density_ranks(locations: &[Point], radius: f64) -> Vec<f64> {
+    let map = RTree::bulk_load(locations.to_vec());
+    let densities = locations
+        .par_iter()
+        .map(|location| map.lookup_in_circle(location, &radius.powi(2)).len())
+        .collect::<Vec<_>>();
+
+    let mut order = (0..locations.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&index| densities[index]);
+    let mut ranks = vec![0.; locations.len()];
+    let last = (locations.len().max(2) - 1) as f64;
+    for (rank, index) in order.into_iter().enumerate() {
+        ranks[index] = rank as f64 / last;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{ChaChaRng, SeedableRng};
+
+    #[test]
+    fn test_ring_shape() {
+        let seed: &[_] = &[3];
+        let mut rng = ChaChaRng::from_seed(seed);
+        let shape = GalaxyShape::Ring {
+            radius: 2.,
+            width: 0.1,
+        };
+        let placements = place_systems(&shape, 1000, 10., &mut rng);
+        assert_eq!(placements.len(), 1000);
+
+        // Most systems lie on the ring, the rest in the core.
+        let on_ring = placements
+            .iter()
+            .filter(|placement| (placement.location.distance(&Point::origin()) - 20.).abs() < 4.)
+            .count();
+        assert!(on_ring > 850);
+        assert!(placements.iter().all(|p| p.age >= 0. && p.age <= 1.));
+    }
+
+    #[test]
+    fn test_validate() {
+        let seed: &[_] = &[5];
+        let mut rng = ChaChaRng::from_seed(seed);
+        for _ in 0..20 {
+            assert_eq!(random_shape(&mut rng).validate(), Ok(()));
+        }
+
+        let invalid = [
+            GalaxyShape::Spiral { arms: 0, twist: 1. },
+            GalaxyShape::BarredSpiral {
+                arms: 2,
+                twist: 1.,
+                bar_length: 0.,
+            },
+            GalaxyShape::Elliptical { axis_ratio: 1.5 },
+            GalaxyShape::Ring {
+                radius: 2.,
+                width: 0.,
+            },
+            GalaxyShape::Clusters {
+                count: 3,
+                size: -1.,
+            },
+        ];
+        for shape in &invalid {
+            assert!(shape.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_density_ranks() {
+        let locations = [
+            Point::new(0., 0.),
+            Point::new(0.1, 0.),
+            Point::new(0., 0.1),
+            Point::new(10., 10.),
+        ];
+        let ranks = density_ranks(&locations, 1.);
+        assert_eq!(ranks[3], 0.);
+        assert!(ranks[0] > 0.);
+    }
+}
@@ -1,4 +1,4 @@
-use astronomicals::star::{Star, StarType};
+use astronomicals::star::{SpectralClass, Star, StarType, SOLAR_TEMPERATURE};
 use rand;
 use statrs::distribution::{Distribution, Gamma};
 
@@ -14,21 +14,135 @@ impl StarGen {
         StarGen { mass_gen }
     }
 
+    /// Calculate the radius of a main sequence star from its mass.
+    pub fn calculate_radius(mass: f64) -> f64 {
+        if mass < 1. {
+            mass.powf(0.8)
+        } else {
+            mass.powf(0.57)
+        }
+    }
+
+    /// Calculate the surface temperature in Kelvin from the luminosity and
+    /// radius using the Stefan-Boltzmann law.
+    pub fn calculate_temperature(luminosity: f64, radius: f64) -> f64 {
+        SOLAR_TEMPERATURE * (luminosity / radius.powi(2)).powf(0.25)
+    }
+
+    /// Calculate the luminosity from the radius and surface temperature.
+    pub fn calculate_luminosity(radius: f64, temperature: f64) -> f64 {
+        radius.powi(2) * (temperature / SOLAR_TEMPERATURE).powi(4)
+    }
+
+    /// Creates a main sequence star of the given mass, classified by its
+    /// surface temperature.
+    fn main_sequence(mass: f64) -> Star {
+        // Mass-luminosity relation.
+        let luminosity = mass.powf(3.5);
+        let radius = StarGen::calculate_radius(mass);
+        let temperature = StarGen::calculate_temperature(luminosity, radius);
+        Star {
+            temperature,
+            radius,
+            class: SpectralClass::from_temperature(temperature),
+            ..Star::new(mass, luminosity, StarType::Single)
+        }
+    }
+
+    /// Creates the remnant of a burnt out star, where white dwarfs are the
+    /// most common and black holes are rare.
+    fn remnant<R: rand::Rng>(gen: &mut R) -> Star {
+        let (class, mass, radius, temperature) = match gen.gen::<f64>() {
+            x if x < 0.8 => (
+                SpectralClass::WhiteDwarf,
+                gen.gen_range(0.5, 1.4),
+                0.012,
+                gen.gen_range(4_000., 30_000.),
+            ),
+            x if x < 0.95 => (
+                SpectralClass::NeutronStar,
+                gen.gen_range(1.2, 2.),
+                1.6e-5,
+                gen.gen_range(300_000., 1_000_000.),
+            ),
+            _ => {
+                // Black holes emit no light, their radius is the event horizon.
+                let mass = gen.gen_range(5., 20.);
+                (SpectralClass::BlackHole, mass, 4.24e-6 * mass, 0.)
+            }
+        };
+        Star {
+            temperature,
+            radius,
+            class,
+            ..Star::new(
+                mass,
+                StarGen::calculate_luminosity(radius, temperature),
+                StarType::Single,
+            )
+        }
+    }
+
     /// Generates a new Star from the _distribution_ using the provided random
-    /// generator.
-    pub fn generate<R: rand::Rng>(&self, gen: &mut R) -> Option<Star> {
+    /// generator, for a stellar population of the given age between 0 and 1.
+    /// Massive stars more often have a lighter main sequence companion.
+    pub fn generate<R: rand::Rng>(&self, gen: &mut R, age: f64) -> Option<Star> {
+        // Massive stars burn out first, so older populations are lighter.
         // Do not want too small stars.
-        let mass = self.mass_gen.sample(gen).max(0.1);
+        let mass = (self.mass_gen.sample(gen) * (1.5 - age)).max(0.1);
 
-        // Stars with high mass are binary stars.
-        let startype = if mass > 3. {
-            StarType::Binary
+        // Older populations also hold the remnants of burnt out stars.
+        let mut star = if gen.gen::<f64>() < 0.1 * age {
+            StarGen::remnant(gen)
         } else {
-            StarType::Single
+            StarGen::main_sequence(mass)
         };
 
-        // Mass-luminosity relation.
-        let luminosity = mass.powf(3.5);
-        Some(Star::new(mass, luminosity, startype))
+        // The companion is lighter than the star it orbits, remnant or not.
+        if gen.gen::<f64>() < (0.2 + 0.1 * star.mass).min(0.8) {
+            let companion_mass = (star.mass * gen.gen_range(0.1, 1.)).max(0.1);
+            star.startype = StarType::Binary;
+            star.companion = Some(Box::new(StarGen::main_sequence(companion_mass)));
+        }
+        Some(star)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{ChaChaRng, SeedableRng};
+
+    #[test]
+    fn test_main_sequence() {
+        // The Sun is a G star.
+        let sun = StarGen::main_sequence(1.);
+        assert!((sun.temperature - SOLAR_TEMPERATURE).abs() < 1.);
+        assert_eq!(sun.class, SpectralClass::G);
+
+        assert_eq!(StarGen::main_sequence(0.3).class, SpectralClass::M);
+        assert_eq!(StarGen::main_sequence(3.).class, SpectralClass::B);
+        assert_eq!(StarGen::main_sequence(20.).class, SpectralClass::O);
+    }
+
+    #[test]
+    fn test_companions() {
+        let seed: &[_] = &[7];
+        let mut rng = ChaChaRng::from_seed(seed);
+        let gen = StarGen::new();
+        let stars = (0..1000)
+            .filter_map(|_| gen.generate(&mut rng, 1.))
+            .collect::<Vec<_>>();
+        assert!(stars
+            .iter()
+            .any(|star| star.class == SpectralClass::WhiteDwarf));
+
+        // Companions are main sequence stars lighter than the star they orbit.
+        for star in &stars {
+            if let Some(ref companion) = star.companion {
+                assert!(companion.mass <= star.mass);
+                assert!(companion.companion.is_none());
+            }
+        }
     }
 }
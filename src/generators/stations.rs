@@ -0,0 +1,148 @@
+use rand::{seq::sample_indices, Rng};
+use statrs::distribution::{Distribution, Poisson};
+
+use astronomicals::{
+    planet::{PlanetBuilder, PlanetEconomy, PlanetType},
+    station::{Service, Station, StationKind},
+    system::SystemSecurity,
+};
+
+/// Used for generating the stations and outposts of systems.
+pub struct StationGen {
+    /// Distribution of the number of stations, if any are generated.
+    num_stations_gen: Option<Poisson>,
+}
+
+impl StationGen {
+    /// Create a new station generator placing the given average number of
+    /// stations in each system.
+    pub fn new(stations_per_system: f64) -> Self {
+        StationGen {
+            num_stations_gen: if stations_per_system > 0. {
+                Poisson::new(stations_per_system).ok()
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Predict the station economy based on its kind and the orbited planet.
+    pub fn predict_economy<R: Rng>(
+        rng: &mut R,
+        kind: StationKind,
+        planet_type: Option<&PlanetType>,
+    ) -> PlanetEconomy {
+        let random_val: f64 = rng.gen();
+        match (kind, planet_type) {
+            // Outposts harvest the gas of gas giants.
+            (StationKind::Outpost, Some(&PlanetType::GasGiant)) => PlanetEconomy::Refinary,
+            (StationKind::Outpost, _) if random_val < 0.6 => PlanetEconomy::Extraction,
+            (StationKind::Outpost, _) => PlanetEconomy::Refinary,
+            (StationKind::Station, _) if random_val < 0.3 => PlanetEconomy::HighTech,
+            (StationKind::Station, _) if random_val < 0.6 => PlanetEconomy::Industrial,
+            (StationKind::Station, _) if random_val < 0.8 => PlanetEconomy::Military,
+            (StationKind::Station, _) => PlanetEconomy::Agriculture,
+        }
+    }
+
+    /// Generates unnamed stations orbiting distinct planets among the given
+    /// ones. Systems with lower security hold more outposts and black markets.
+    pub fn generate<R: Rng>(
+        &self,
+        rng: &mut R,
+        planets: &[PlanetBuilder],
+        security: &SystemSecurity,
+    ) -> Vec<Station> {
+        let count = match self.num_stations_gen {
+            Some(ref gen) => gen.sample::<R>(rng).round() as usize,
+            None => 0,
+        };
+        let (outpost_chance, black_market_chance) = match *security {
+            SystemSecurity::Anarchy => (0.8, 0.6),
+            SystemSecurity::Low => (0.6, 0.3),
+            SystemSecurity::Medium => (0.3, 0.1),
+            SystemSecurity::High => (0.1, 0.),
+        };
+
+        let mut orbited = sample_indices(rng, planets.len(), count.min(planets.len()));
+        orbited.sort();
+        orbited
+            .into_iter()
+            .map(|planet| {
+                let kind = if rng.gen::<f64>() < outpost_chance {
+                    StationKind::Outpost
+                } else {
+                    StationKind::Station
+                };
+                let economic_type =
+                    StationGen::predict_economy(rng, kind, planets[planet].planet_type.as_ref());
+
+                // Stations offer the basic services, outposts only fuel for
+                // sure. Shipyards are found at stations building ships.
+                let builds_ships = matches!(
+                    economic_type,
+                    PlanetEconomy::HighTech | PlanetEconomy::Military
+                );
+                let services = Service::values()
+                    .filter(|service| match (**service, kind) {
+                        (Service::Fuel, _) => true,
+                        (Service::Market, StationKind::Station)
+                        | (Service::Repair, StationKind::Station) => true,
+                        (Service::Shipyard, StationKind::Station) => {
+                            builds_ships || rng.gen::<f64>() < 0.2
+                        }
+                        (Service::Market, StationKind::Outpost) => rng.gen::<f64>() < 0.5,
+                        (Service::Repair, StationKind::Outpost) => rng.gen::<f64>() < 0.3,
+                        (Service::Shipyard, StationKind::Outpost) => false,
+                        (Service::BlackMarket, _) => rng.gen::<f64>() < black_market_chance,
+                    })
+                    .cloned()
+                    .collect();
+
+                Station {
+                    name: String::new(),
+                    kind,
+                    planet,
+                    economic_type,
+                    services,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{ChaChaRng, SeedableRng};
+
+    #[test]
+    fn test_generate() {
+        let seed: &[_] = &[3];
+        let mut rng = ChaChaRng::from_seed(seed);
+        let planets = (0..4)
+            .map(|_| {
+                PlanetBuilder::default()
+                    .planet_type(PlanetType::Rocky)
+                    .to_owned()
+            })
+            .collect::<Vec<_>>();
+        let station_gen = StationGen::new(10.);
+
+        for _ in 0..20 {
+            let stations = station_gen.generate(&mut rng, &planets, &SystemSecurity::High);
+            // At most one station orbits each planet.
+            assert!(stations.len() <= planets.len());
+            assert!(stations
+                .windows(2)
+                .all(|pair| pair[0].planet < pair[1].planet));
+            for station in stations {
+                assert!(station.services.contains(&Service::Fuel));
+                assert!(!station.services.contains(&Service::BlackMarket));
+            }
+        }
+        assert!(StationGen::new(0.)
+            .generate(&mut rng, &planets, &SystemSecurity::Anarchy)
+            .is_empty());
+    }
+}
@@ -1,13 +1,19 @@
 use rand::{ChaChaRng, Rng, SeedableRng};
 use statrs::distribution::{Distribution, Poisson};
-use std::f64;
+use std::sync::Arc;
 
 use astronomicals::{
     planet::PlanetBuilder,
-    system::{Reputation, SystemBuilder, SystemSecurity, SystemState},
+    system::{SystemBuilder, SystemId, SystemSecurity, SystemState},
 };
 use entities::Faction;
-use generators::{planets::PlanetGen, stars::StarGen};
+use game_config::{GameConfig, SecurityWeights};
+use generators::{
+    planets::{PlanetGen, SOLAR_MASS},
+    stars::StarGen,
+    stations::StationGen,
+};
+use resources::{fetch_resource, FactionResource};
 use utils::Point;
 
 /// Used for generating systems.
@@ -15,43 +21,51 @@ pub struct SystemGen {
     num_planets_gen: Poisson,
     star_gen: StarGen,
     planet_gen: PlanetGen,
+    station_gen: StationGen,
+    security: SecurityWeights,
+    factions: Arc<FactionResource>,
 }
 
 impl SystemGen {
-    /// Create a new system generator.
-    pub fn new() -> SystemGen {
+    /// Create a new system generator using the parameters of the given config.
+    pub fn new(config: &GameConfig) -> SystemGen {
         // Create Star generator.
         let star_gen = StarGen::new();
 
         // Create Planet generator.
-        let planet_gen = PlanetGen::new();
+        let planet_gen = PlanetGen::new(config.min_orbit_distance);
 
         SystemGen {
-            num_planets_gen: Poisson::new(3.).unwrap(),
+            num_planets_gen: Poisson::new(config.planets_per_system).unwrap(),
             star_gen,
             planet_gen,
+            station_gen: StationGen::new(config.stations_per_system),
+            security: config.security.clone(),
+            factions: fetch_resource::<FactionResource>().unwrap(),
         }
     }
 
-    /// Generate a new star system at the given location with the given faction.
+    /// Generate a new star system with the given id at the given location with
+    /// the given faction, where the stellar population has the given age.
+    /// Stations, moons and belts are left unnamed until the planets have been
+    /// named.
     pub fn generate(
         &self,
+        id: SystemId,
         location: Point,
+        age: f64,
         faction: Faction,
     ) -> (SystemBuilder, Vec<PlanetBuilder>) {
-        // Calculate hash.
-        let hash = location.hash();
-        let seed: &[_] = &[hash as u32];
+        let seed: &[_] = &[location.seed()];
         let mut rng = ChaChaRng::from_seed(seed);
 
-        let star = self.star_gen.generate(&mut rng).unwrap();
+        let star = self.star_gen.generate(&mut rng, age).unwrap();
 
-        // TODO: Replace constant in config.
         let num_planets =
             (self.num_planets_gen.sample::<ChaChaRng>(&mut rng).round() as u32).max(1);
 
         // Fallback to planet name: Unnamed if no name could be generated.
-        let satelites: Vec<PlanetBuilder> = (0..num_planets)
+        let mut satelites: Vec<PlanetBuilder> = (0..num_planets)
             .map(|_| {
                 let mut builder = self.planet_gen.generate(&mut rng).unwrap();
                 let mass = builder.mass.unwrap();
@@ -61,7 +75,12 @@ impl SystemGen {
                 );
                 let planet_type = PlanetGen::predict_type(&mut rng, surface_temperature, mass);
                 let economic_type = PlanetGen::predict_economy(&mut rng, &planet_type);
+                let orbital_period = PlanetGen::calculate_orbital_period(
+                    builder.orbit_distance.unwrap(),
+                    star.total_mass() * SOLAR_MASS,
+                );
                 builder
+                    .orbital_period(orbital_period)
                     .surface_temperature(surface_temperature)
                     .planet_type(planet_type)
                     .economic_type(economic_type);
@@ -69,29 +88,45 @@ impl SystemGen {
             })
             .collect();
 
-        // Set the security level based on faction and a probability.
-        let random_val: f64 = rng.gen();
-        let security_level = match faction {
-            Faction::Empire if random_val < 0.5 => SystemSecurity::High,
-            Faction::Empire if random_val >= 0.5 => SystemSecurity::Medium,
-            Faction::Federation if random_val < 0.4 => SystemSecurity::Low,
-            Faction::Federation if random_val < 0.8 => SystemSecurity::Medium,
-            Faction::Federation if random_val >= 0.8 => SystemSecurity::High,
-            Faction::Cartel if random_val < 0.5 => SystemSecurity::Medium,
-            Faction::Cartel if random_val >= 0.5 => SystemSecurity::Anarchy,
-            Faction::Independent if random_val < 0.5 => SystemSecurity::Anarchy,
-            Faction::Independent if random_val >= 0.5 => SystemSecurity::Low,
-            _ => SystemSecurity::Low,
+        // Set the security level based on the faction's distribution.
+        let security_level = match self.factions.get(&faction) {
+            Some(definition) => definition.random_security(&mut rng, &self.security),
+            None => SystemSecurity::Low,
+        };
+
+        // Stations are generated after the rest to keep it unchanged.
+        let stations = self
+            .station_gen
+            .generate(&mut rng, &satelites, &security_level);
+
+        // Followed by moons and belts, which are named with the planets.
+        for builder in &mut satelites {
+            let moons = self.planet_gen.generate_moons(
+                &mut rng,
+                builder.mass.unwrap(),
+                builder.surface_temperature.unwrap(),
+            );
+            builder.moons(moons);
+        }
+        let belts = match rng.gen::<f64>() {
+            x if x < 0.1 => 2,
+            x if x < 0.4 => 1,
+            _ => 0,
         };
+        let belts = (0..belts)
+            .map(|_| self.planet_gen.generate_belt(&mut rng, &star))
+            .collect::<Vec<_>>();
 
         let mut system = SystemBuilder::default();
         system
+            .id(id)
             .location(location)
             .faction(faction)
             .security(security_level)
             .state(SystemState::Boom)
-            .reputation(Reputation::default())
-            .star(star);
+            .star(star)
+            .stations(stations)
+            .belts(belts);
         (system, satelites)
     }
 }
@@ -8,10 +8,12 @@ use event::{Event, HANDLER};
 mod alert;
 mod confirm;
 mod multi;
+mod new_game;
 
 pub use self::alert::AlertDialog;
 pub use self::confirm::ConfirmDialog;
-pub use self::multi::MultiDialog;
+pub use self::multi::{Action, MultiDialog};
+pub use self::new_game::NewGameDialog;
 
 /// A dialog box.
 pub trait Dialog {
@@ -5,7 +5,8 @@ use tui::{
     widgets::{Block, Borders, SelectableList, Widget},
 };
 
-type Action = Fn(&mut Sender<Event>) -> Option<GUIEvent>;
+/// Action taken when choosing an option, possibly resulting in a GUI event.
+pub type Action = Fn(&mut Sender<Event>) -> Option<GUIEvent>;
 
 /// Multiple choice dialog window.
 pub struct MultiDialog {
@@ -0,0 +1,192 @@
+use super::*;
+use rand;
+use tui::{
+    layout::{Direction, Group, Rect, Size},
+    style::{Color, Style},
+    widgets::{Block, Borders, Paragraph, SelectableList, Widget},
+};
+
+use game_config::Difficulty;
+use resources::{fetch_resource, ScenarioResource};
+use scenario::Scenario;
+
+/// Rows of the new game dialog.
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    Difficulty,
+    Scenario,
+    Seed,
+    Start,
+    Cancel,
+}
+
+const ROWS: [Row; 5] = [
+    Row::Difficulty,
+    Row::Scenario,
+    Row::Seed,
+    Row::Start,
+    Row::Cancel,
+];
+
+/// Dialog window for choosing the difficulty, scenario and map seed of a new
+/// game.
+pub struct NewGameDialog {
+    selected: usize,
+    difficulty: usize,
+    scenarios: Vec<Scenario>,
+    /// Index of the chosen scenario plus one, zero for none.
+    scenario: usize,
+    seed: String,
+}
+
+impl NewGameDialog {
+    /// Create a new game dialog initially showing the given difficulty, seed
+    /// and scenario.
+    pub fn new(difficulty: Difficulty, seed: u32, scenario: Option<&String>) -> Self {
+        let scenarios = fetch_resource::<ScenarioResource>()
+            .map(|resource| resource.scenarios.clone())
+            .unwrap_or_default();
+        NewGameDialog {
+            selected: 0,
+            difficulty: Difficulty::values()
+                .iter()
+                .position(|value| *value == difficulty)
+                .unwrap_or(0),
+            scenario: scenario
+                .and_then(|name| {
+                    scenarios
+                        .iter()
+                        .position(|scenario| scenario.name.eq_ignore_ascii_case(name))
+                })
+                .map_or(0, |index| index + 1),
+            scenarios,
+            seed: seed.to_string(),
+        }
+    }
+
+    /// Returns the chosen scenario, if any.
+    fn scenario(&self) -> Option<&Scenario> {
+        self.scenario
+            .checked_sub(1)
+            .and_then(|index| self.scenarios.get(index))
+    }
+
+    /// Returns the event asking for confirmation before starting the game,
+    /// using a random seed if none was entered.
+    fn start(&self) -> GUIEvent {
+        let difficulty = Difficulty::values()[self.difficulty];
+        let seed = self.seed.parse().unwrap_or_else(|_| rand::random());
+        let scenario = self.scenario().map(|scenario| scenario.name.clone());
+        let confirm_action = Box::new(move |_: &mut Sender<Event>| {
+            Some(GUIEvent::StartNewGame(difficulty, seed, scenario.clone()))
+        });
+        let cancel_action = Box::new(|_: &mut Sender<Event>| Some(GUIEvent::CloseDialog));
+        GUIEvent::OpenDialog(Box::new(ConfirmDialog::new(
+            String::from("Are you sure? All saves will be lost"),
+            confirm_action,
+            cancel_action,
+        )))
+    }
+}
+
+impl Dialog for NewGameDialog {
+    /// Returns the title string describing the dialog box.
+    fn title(&self) -> String {
+        String::from("New game")
+    }
+
+    /// Handles the user provided event.
+    fn handle_event(&mut self, event: Event) -> Option<GUIEvent> {
+        if let Event::Input(input) = event {
+            let count = Difficulty::values().len();
+            let scenarios = self.scenarios.len() + 1;
+            match (ROWS[self.selected], input) {
+                // Move up.
+                (_, keyevent::Key::Char('k')) => self.selected = self.selected.max(1) - 1,
+                // Move down.
+                (_, keyevent::Key::Char('j')) => {
+                    self.selected = (self.selected + 1).min(ROWS.len() - 1)
+                }
+                (Row::Difficulty, keyevent::Key::Char('h')) => {
+                    self.difficulty = (self.difficulty + count - 1) % count
+                }
+                (Row::Difficulty, keyevent::Key::Char('l')) => {
+                    self.difficulty = (self.difficulty + 1) % count
+                }
+                (Row::Scenario, keyevent::Key::Char('h')) => {
+                    self.scenario = (self.scenario + scenarios - 1) % scenarios
+                }
+                (Row::Scenario, keyevent::Key::Char('l')) => {
+                    self.scenario = (self.scenario + 1) % scenarios
+                }
+                // Ignore digits making the seed too large.
+                (Row::Seed, keyevent::Key::Char(c))
+                    if c.is_ascii_digit()
+                        && format!("{}{}", self.seed, c).parse::<u32>().is_ok() =>
+                {
+                    self.seed.push(c)
+                }
+                (Row::Seed, keyevent::Key::Backspace) => {
+                    self.seed.pop();
+                }
+                (Row::Cancel, keyevent::Key::Char('\n')) | (_, keyevent::Key::Backspace) => {
+                    return Some(GUIEvent::CloseDialog)
+                }
+                (_, keyevent::Key::Char('\n')) => return Some(self.start()),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Draws the dialog in the given terminal and area.
+    fn draw(&self, term: &mut Terminal<MouseBackend>, area: &Rect) {
+        let dialog_rect = Rect::new((area.width - 60) / 2, (area.height - 12) / 2, 60, 12);
+        let labels = ROWS
+            .iter()
+            .map(|row| {
+                let label = match *row {
+                    Row::Difficulty => {
+                        format!("Difficulty: < {} >", Difficulty::values()[self.difficulty])
+                    }
+                    Row::Scenario => format!(
+                        "Scenario: < {} >",
+                        self.scenario()
+                            .map_or("None", |scenario| scenario.name.as_str())
+                    ),
+                    Row::Seed => match self.scenario().and_then(|scenario| scenario.seed) {
+                        Some(seed) => format!("Seed: {} (set by scenario)", seed),
+                        None if self.seed.is_empty() => String::from("Seed: random"),
+                        None => format!("Seed: {}", self.seed),
+                    },
+                    Row::Start => String::from("Start"),
+                    Row::Cancel => String::from("Cancel"),
+                };
+                format!("{:^1$}", label, 58)
+            })
+            .collect::<Vec<_>>();
+        Group::default()
+            .direction(Direction::Vertical)
+            .sizes(&[Size::Fixed(ROWS.len() as u16 + 2), Size::Min(0)])
+            .render(term, &dialog_rect, |term, chunks| {
+                SelectableList::default()
+                    .items(&labels)
+                    .select(self.selected)
+                    .block(Block::default().borders(Borders::ALL).title(&self.title()))
+                    .style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+                    .highlight_style(Style::default().fg(Color::Yellow).bg(Color::Gray))
+                    .render(term, &chunks[0]);
+                Paragraph::default()
+                    .block(Block::default().borders(Borders::ALL))
+                    .style(Style::default().fg(Color::Green).bg(Color::DarkGray))
+                    .wrap(true)
+                    .text(
+                        self.scenario()
+                            .map_or("Free start without a scenario.", |scenario| {
+                                scenario.description.as_str()
+                            }),
+                    )
+                    .render(term, &chunks[1]);
+            });
+    }
+}
@@ -8,8 +8,11 @@ use tui::{
     Terminal,
 };
 
-use event::{add_keyboard_handler, add_player_handler, add_update_handler, Event, HANDLER};
+use event::{
+    add_clock_handler, add_keyboard_handler, add_player_handler, add_update_handler, Event, HANDLER,
+};
 use game::Game;
+use game_config::Difficulty;
 
 pub mod dialog;
 mod info;
@@ -23,7 +26,8 @@ use simulator::Simulator;
 
 /// Events used for communicating specifically between GUI components.
 pub enum GUIEvent {
-    StartNewGame,
+    /// Starts a new game with the given difficulty, map seed and scenario.
+    StartNewGame(Difficulty, u32, Option<String>),
     LoadExistingGame,
     OpenDialog(Box<dyn Dialog>),
     CloseDialog,
@@ -37,6 +41,7 @@ pub struct Gui {
     selected_tab: usize,
     dialog: Option<Box<Dialog>>,
     title_page: Option<TitlePage>,
+    catch_up: Option<(u32, u32)>,
 }
 
 impl Gui {
@@ -46,20 +51,24 @@ impl Gui {
         add_keyboard_handler();
 
         Gui {
+            title_page: Some(TitlePage::new(simulator.game_config())),
             simulator,
             size: Rect::default(),
             tabs: vec![], //tab::create_tabs(&game),
             selected_tab: 0,
             dialog: None,
-            title_page: Some(TitlePage::new()),
+            catch_up: None,
         }
     }
 
-    fn start_main_game(&mut self, game_state: &Arc<Game>) {
+    /// Leaves the title page and starts playing the given game.
+    pub fn start_main_game(&mut self, game_state: &Arc<Game>) {
         // TODO: Move to some where more reasonable.
         add_player_handler(game_state.clone());
         // TODO: Move to some where more reasonable.
         add_update_handler(game_state.clone());
+        // TODO: Move to some where more reasonable.
+        add_clock_handler(game_state.clone());
 
         // Initialize all tabs.
         self.tabs = tab::create_tabs(&game_state);
@@ -112,7 +121,7 @@ impl Gui {
                             _ => self.tabs[self.selected_tab].handle_event(evt),
                         };
                         match gui_event {
-                            Some(GUIEvent::StartNewGame) => {
+                            Some(GUIEvent::StartNewGame(difficulty, seed, scenario)) => {
                                 // Draw loading screen.
                                 draw_info_page(
                                     &mut term,
@@ -123,6 +132,7 @@ impl Gui {
                                 // Ensure that the screen gets redrawn.
                                 term.draw().unwrap();
 
+                                self.simulator.configure(difficulty, seed, scenario);
                                 let game_state = self.simulator.new_game();
                                 self.start_main_game(&game_state);
                             }
@@ -148,6 +158,26 @@ impl Gui {
                         };
                     }
                 },
+                Event::DockingRefused => {
+                    self.dialog = Some(Box::new(dialog::AlertDialog::new(
+                        String::from("Docking request denied"),
+                        Box::new(|_| Some(GUIEvent::CloseDialog)),
+                    )));
+                }
+                Event::SmugglingBust(amount) => {
+                    self.dialog = Some(Box::new(dialog::AlertDialog::new(
+                        format!("{} units of illegal cargo confiscated", amount),
+                        Box::new(|_| Some(GUIEvent::CloseDialog)),
+                    )));
+                }
+                Event::CatchUpProgress(done, total) => {
+                    // Keep track of progress until done.
+                    self.catch_up = if done < total {
+                        Some((done, total))
+                    } else {
+                        None
+                    };
+                }
                 _ => {
                     // Forward all general events to all tabs.
                     for tab in &mut self.tabs {
@@ -170,8 +200,14 @@ impl Gui {
                     .direction(Direction::Vertical)
                     .sizes(&[Size::Fixed(3), Size::Min(0)])
                     .render(term, &self.size, |term, chunks| {
+                        let title = match self.catch_up {
+                            Some((done, total)) => {
+                                format!("Tabs - Simulating time passed: {}/{}", done, total)
+                            }
+                            None => String::from("Tabs"),
+                        };
                         Tabs::default()
-                            .block(Block::default().borders(Borders::ALL).title("Tabs"))
+                            .block(Block::default().borders(Borders::ALL).title(&title))
                             .titles(&self.tabs.iter().map(|tab| tab.title()).collect::<Vec<_>>())
                             .style(Style::default().fg(Color::Green))
                             .highlight_style(Style::default().fg(Color::Yellow))
@@ -0,0 +1,61 @@
+use super::*;
+use tui::{
+    layout::{Direction, Group, Rect, Size},
+    style::{Color, Style},
+    widgets::{Block, Borders, SelectableList, Widget},
+};
+
+/// Displays the economy diagnostics tab, intended for debugging.
+pub struct DiagnosticsTab {
+    state: Arc<Game>,
+}
+
+impl Tab for DiagnosticsTab {
+    /// Creates a diagnostics tab.
+    fn new(state: Arc<Game>, _send_handle: Sender<Event>) -> Box<Self> {
+        Box::new(DiagnosticsTab { state })
+    }
+
+    /// Returns the title string describing the tab.
+    fn title(&self) -> String {
+        String::from("Diagnostics")
+    }
+
+    /// Handles the user provided event.
+    fn handle_event(&mut self, _event: Event) -> Option<GUIEvent> {
+        None
+    }
+
+    /// Draws the tab in the given terminal and area.
+    fn draw(&self, term: &mut Terminal<MouseBackend>, area: &Rect) {
+        let economy = self.state.economy.lock().unwrap();
+        let diagnostics = economy.diagnostics();
+
+        let summary = vec![
+            format!("Ticks checked:     {}", diagnostics.ticks),
+            format!("Total violations:  {}", diagnostics.total_violations),
+            format!("Last tick:         {}", diagnostics.violations.len()),
+        ];
+        let violations = diagnostics
+            .violations
+            .iter()
+            .map(|&(market, ref violation)| format!("Market {}: {}", market, violation))
+            .collect::<Vec<_>>();
+
+        Group::default()
+            .direction(Direction::Vertical)
+            .sizes(&[Size::Fixed(5), Size::Min(1)])
+            .render(term, area, |term, chunks| {
+                SelectableList::default()
+                    .items(&summary)
+                    .block(Block::default().title("Economy").borders(Borders::ALL))
+                    .style(Style::default().fg(Color::Yellow))
+                    .render(term, &chunks[0]);
+                SelectableList::default()
+                    .items(&violations)
+                    .block(Block::default().title("Violations").borders(Borders::ALL))
+                    .style(Style::default().fg(Color::Red))
+                    .render(term, &chunks[1]);
+            });
+    }
+}
@@ -8,20 +8,24 @@ use tui::{
 };
 
 use super::GUIEvent;
-use astronomicals::System;
-use entities::Faction;
+use astronomicals::{
+    system::{SystemId, SystemState},
+    System,
+};
+use entities::{Faction, Relations, Stance};
 use player::Player;
-use utils::Point;
+use resources::{fetch_resource, FactionResource};
+use utils::{parse_color, Point};
 
 lazy_static! {
     /// Color mapping for each faction.
     static ref FACTION_COLORS: HashMap<Faction, Color> = {
-        let mut m = HashMap::new();
-        m.insert(Faction::Empire, Color::Red);
-        m.insert(Faction::Federation, Color::Yellow);
-        m.insert(Faction::Cartel, Color::Magenta);
-        m.insert(Faction::Independent, Color::LightGreen);
-        m
+        fetch_resource::<FactionResource>()
+            .unwrap()
+            .factions
+            .iter()
+            .map(|definition| (definition.id.clone(), parse_color(&definition.color)))
+            .collect()
     };
 
     /// Styling for unselected item.
@@ -37,10 +41,13 @@ pub struct GalaxyMapTab {
     sender: Sender<Event>,
     search_mode: bool,
     search_str: String,
-    selected: Option<Point>,
-    route: Option<(u32, Vec<Point>)>,
+    selected: Option<SystemId>,
+    route: Option<(u32, Vec<(SystemId, Point)>)>,
     cursor: Point,
     map_scale: f64,
+    /// Whether systems are colored by the class of their star instead of
+    /// their faction.
+    star_colors: bool,
 }
 
 impl GalaxyMapTab {
@@ -57,21 +64,24 @@ impl GalaxyMapTab {
             None => 0,
         };
         // Plan route if possible.
-        self.route = galaxy.route(player.location(), self.selected.unwrap(), range, max_jumps);
+        self.route = galaxy.route(player.system(), self.selected.unwrap(), range, max_jumps);
     }
 
     /// Moves the player's location to the selected system.
     fn travel_to_selected(&mut self) {
+        // Wait until the galaxy has caught up with the time passed.
+        if self.state.is_catching_up() {
+            return;
+        }
         let player = &mut self.state.player.lock().unwrap();
 
         // Only travel if the selected system is the same as the cursor and
         // and the final destination for the route.
         if let Some((_, ref route)) = self.route {
-            if self.selected.is_some()
-                && self.selected.unwrap() == self.cursor
-                && self.selected.unwrap() == *route.last().unwrap()
-            {
-                player.set_route(route.clone());
+            let &(destination, location) = route.last().unwrap();
+            if self.selected == Some(destination) && location == self.cursor {
+                let now = self.state.clock.lock().unwrap().now();
+                player.set_route(route.clone(), now);
                 self.sender.send(Event::Travel).unwrap();
             }
         }
@@ -97,7 +107,8 @@ impl GalaxyMapTab {
     /// Draw system ship information for the selected system, if any.
     fn draw_system_info(
         &self,
-        player_loc: &Point,
+        player: &Player,
+        relations: &Relations,
         selected_system: Option<&System>,
         term: &mut Terminal<MouseBackend>,
         area: Rect,
@@ -108,24 +119,48 @@ impl GalaxyMapTab {
         }
         let system = selected_system.unwrap();
         let populations = self.state.economy.lock().unwrap().populations(&system);
+        let enemies = relations
+            .factions()
+            .iter()
+            .filter(|faction| relations.stance(&system.faction, faction) == Stance::War)
+            .map(|faction| faction.to_string())
+            .collect::<Vec<_>>();
 
         let system_data = vec![
             format!("Faction:       {}", system.faction.to_string()),
             format!("State:         {}", system.state.to_string()),
             format!("Security:      {}", system.security.to_string()),
-            format!("Reputation:    {}", system.reputation.to_string()),
+            format!(
+                "At war with:   {}",
+                if enemies.is_empty() {
+                    String::from("-")
+                } else {
+                    enemies.join(", ")
+                }
+            ),
+            format!("Standing:      {}", player.standing().rank(system)),
             format!(
                 "Distance:      {:.1} ly",
-                player_loc.distance(&system.location)
+                player.location().distance(&system.location)
             ),
             format!("Star mass:     {:.1} M", system.star.mass),
-            format!("Star type:     {}", system.star.startype.to_string()),
+            format!(
+                "Star class:    {} ({:.0} K)",
+                system.star.class, system.star.temperature
+            ),
+            match system.star.companion {
+                Some(ref companion) => format!(
+                    "Star type:     {} with {}",
+                    system.star.startype, companion.class
+                ),
+                None => format!("Star type:     {}", system.star.startype),
+            },
             format!("Bodies:        {}", system.satelites.len()),
         ];
 
         Group::default()
             .direction(Direction::Vertical)
-            .sizes(&[Size::Fixed(9), Size::Min(1)])
+            .sizes(&[Size::Fixed(11), Size::Min(1)])
             .render(term, &area, |term, chunks| {
                 SelectableList::default()
                     .items(&system_data)
@@ -181,8 +216,18 @@ impl GalaxyMapTab {
             .block(Block::default().title("Systems").borders(Borders::ALL))
             .paint(|ctx| {
                 for system in systems.iter() {
-                    let color = *FACTION_COLORS.get(&system.faction).unwrap();
-                    ctx.print(system.location.x, system.location.y, ".", color);
+                    let color = if self.star_colors {
+                        system.star.class.color()
+                    } else {
+                        *FACTION_COLORS.get(&system.faction).unwrap_or(&Color::White)
+                    };
+                    // Highlight systems in conflict.
+                    let symbol = match system.state {
+                        SystemState::Contested => "+",
+                        SystemState::CivilWar => "x",
+                        _ => ".",
+                    };
+                    ctx.print(system.location.x, system.location.y, symbol, color);
                 }
                 // Draw player location.
                 ctx.print(player_loc.x, player_loc.y, "X", Color::White);
@@ -192,13 +237,13 @@ impl GalaxyMapTab {
 
                 // Draw route if available.
                 if let Some((_, ref route)) = self.route {
-                    for system in route {
+                    for &(_, ref system) in route {
                         ctx.print(system.x, system.y, "X", Color::Yellow);
                     }
                     ctx.print(player_loc.x, player_loc.y, "S", Color::Yellow);
                     ctx.print(
-                        route.last().unwrap().x,
-                        route.last().unwrap().y,
+                        route.last().unwrap().1.x,
+                        route.last().unwrap().1.y,
                         "G",
                         Color::Yellow,
                     );
@@ -226,16 +271,20 @@ impl GalaxyMapTab {
 impl Tab for GalaxyMapTab {
     /// Creates a map tab.
     fn new(state: Arc<Game>, send_handle: Sender<Event>) -> Box<Self> {
-        let cursor = state.player.lock().unwrap().location();
+        let (selected, cursor) = {
+            let player = state.player.lock().unwrap();
+            (player.system(), player.location())
+        };
         Box::new(GalaxyMapTab {
             state,
             sender: send_handle,
-            selected: Some(cursor),
+            selected: Some(selected),
             search_mode: false,
             search_str: String::new(),
             route: None,
             cursor,
             map_scale: 1.,
+            star_colors: false,
         })
     }
 
@@ -281,6 +330,8 @@ impl Tab for GalaxyMapTab {
                         self.cursor = player.location();
                     }
                 }
+                // Toggle coloring systems by star class.
+                keyevent::Key::Char('c') => self.star_colors = !self.star_colors,
                 // Start search mode.
                 keyevent::Key::Char('/') => {
                     self.search_mode = true;
@@ -330,10 +381,11 @@ impl Tab for GalaxyMapTab {
             self.selected = None;
 
             // Check if cursor should snap to closest system.
-            if let Some(neighbor) = self.state.galaxy.lock().unwrap().nearest(&self.cursor) {
+            let galaxy = self.state.galaxy.lock().unwrap();
+            if let Some(neighbor) = galaxy.nearest(&self.cursor) {
                 if self.cursor.distance(&neighbor) < MIN_SNAP_DIST {
                     self.cursor = *neighbor;
-                    self.selected = Some(*neighbor);
+                    self.selected = galaxy.system_at(neighbor).map(|system| system.id);
                 }
             }
         }
@@ -356,8 +408,9 @@ impl Tab for GalaxyMapTab {
                     .sizes(&[Size::Min(1), Size::Fixed(3)])
                     .render(term, &chunks[0], |term, sidebar_chunk| {
                         self.draw_system_info(
-                            &player.location(),
-                            self.selected.map(|point| galaxy.system(&point).unwrap()),
+                            player,
+                            &galaxy.relations,
+                            self.selected.and_then(|id| galaxy.system(id)),
                             term,
                             sidebar_chunk[0],
                         );
@@ -372,7 +425,7 @@ impl Tab for GalaxyMapTab {
                 let systems = galaxy
                     .reachable_rect(&upper_left, &lower_right)
                     .into_iter()
-                    .map(|loc| galaxy.system(&loc).unwrap())
+                    .map(|loc| galaxy.system_at(&loc).unwrap())
                     .collect::<Vec<_>>();
                 self.draw_galaxy_map(player, &systems, map_radius, term, chunks[1]);
             });
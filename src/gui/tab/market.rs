@@ -2,10 +2,12 @@ use super::*;
 use termion::event as keyevent;
 use tui::layout::Rect;
 use tui::style::{Color, Style};
-use tui::widgets::{Block, Borders, Row, Table, Widget};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, Widget};
 
 use super::GUIEvent;
+use astronomicals::station::Service;
 use player::PlayerState;
+use resources::{fetch_resource, FactionResource};
 
 lazy_static! {
     /// Styling for selected item.
@@ -21,6 +23,7 @@ pub struct MarketTab {
     max_selected: usize,
     state: Arc<Game>,
     sender: Sender<Event>,
+    factions: Arc<FactionResource>,
 }
 
 impl Tab for MarketTab {
@@ -29,7 +32,7 @@ impl Tab for MarketTab {
         let cloned_state = state.clone();
         let galaxy = state.galaxy.lock().unwrap();
         let system = galaxy
-            .system(&state.player.lock().unwrap().location())
+            .system(state.player.lock().unwrap().system())
             .unwrap();
         let max_selected = state.economy.lock().unwrap().commodity_prices(system).len() - 1;
 
@@ -38,6 +41,7 @@ impl Tab for MarketTab {
             max_selected,
             state: cloned_state,
             sender: send_handle,
+            factions: fetch_resource::<FactionResource>().unwrap(),
         })
     }
 
@@ -64,7 +68,7 @@ impl Tab for MarketTab {
                 // Update maximum index if needed.
                 let galaxy = self.state.galaxy.lock().unwrap();
                 let system = galaxy
-                    .system(&self.state.player.lock().unwrap().location())
+                    .system(self.state.player.lock().unwrap().system())
                     .unwrap();
                 self.max_selected = self
                     .state
@@ -86,11 +90,23 @@ impl Tab for MarketTab {
 
         if let PlayerState::Docked(_) = player.state() {
             let galaxy = self.state.galaxy.lock().unwrap();
-            let system = galaxy.system(&player.location()).unwrap();
+            let system = galaxy.system(player.system()).unwrap();
+            // Illegal commodities are traded openly at black markets.
+            let black_market = player.can_use(Service::BlackMarket, system);
+            if !player.can_use(Service::Market, system) && !black_market {
+                Paragraph::default()
+                    .block(Block::default().title("Commodities").borders(Borders::ALL))
+                    .text("There is no market here.")
+                    .render(term, area);
+                return;
+            }
             let prices = self.state.economy.lock().unwrap().commodity_prices(system);
+            // Allied players are given a discount.
+            let price_factor = player.standing().price_factor(system);
+            let definition = self.factions.get(&system.faction);
 
             Table::new(
-                ["Commodity", "Buy", "Sell"].into_iter(),
+                ["Commodity", "Buy", "Sell", "Mass", "Legality"].into_iter(),
                 prices.iter().enumerate().map(|(idx, (commodity, price))| {
                     let style: &Style = if idx == self.selected {
                         &SELECTED_STYLE
@@ -100,16 +116,32 @@ impl Tab for MarketTab {
                     Row::StyledData(
                         vec![
                             commodity.to_string(),
-                            format!("{:.1}", price),
+                            format!("{:.1}", (*price as f64 * price_factor) as i64),
                             format!("{:.1}", (*price as f64 * 0.8) as i64),
+                            commodity
+                                .definition()
+                                .map_or(String::from("-"), |definition| {
+                                    format!("{:.1} t", definition.mass)
+                                }),
+                            match definition {
+                                Some(definition) if !definition.is_legal(commodity) => {
+                                    if black_market {
+                                        String::from("Black Market")
+                                    } else {
+                                        String::from("Illegal")
+                                    }
+                                }
+                                _ => String::from("Legal"),
+                            },
                         ].into_iter(),
                         &style,
                     )
                 }),
-            ).block(Block::default().title("Commodities").borders(Borders::ALL))
-                .header_style(Style::default().fg(Color::Yellow))
-                .widths(&[40, 50, 50, 90])
-                .render(term, &area);
+            )
+            .block(Block::default().title("Commodities").borders(Borders::ALL))
+            .header_style(Style::default().fg(Color::Yellow))
+            .widths(&[40, 50, 50, 30, 90])
+            .render(term, &area);
         }
     }
 }
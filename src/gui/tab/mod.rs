@@ -5,9 +5,11 @@ use super::GUIEvent;
 use event::{Event, HANDLER};
 use game::Game;
 
+mod diagnostics;
 mod galaxymap;
 mod market;
 mod mission;
+mod sectors;
 mod shipyard;
 mod status;
 mod systemmap;
@@ -34,9 +36,11 @@ pub fn create_tabs(state: &Arc<Game>) -> Vec<Box<Tab>> {
     vec![
         status::StatusTab::new(state.clone(), HANDLER.send_handle()),
         galaxymap::GalaxyMapTab::new(state.clone(), HANDLER.send_handle()),
+        sectors::SectorsTab::new(state.clone(), HANDLER.send_handle()),
         systemmap::SystemMapTab::new(state.clone(), HANDLER.send_handle()),
         market::MarketTab::new(state.clone(), HANDLER.send_handle()),
         mission::MissionTab::new(state.clone(), HANDLER.send_handle()),
         shipyard::ShipyardTab::new(state.clone(), HANDLER.send_handle()),
+        diagnostics::DiagnosticsTab::new(state.clone(), HANDLER.send_handle()),
     ]
 }
@@ -0,0 +1,192 @@
+use super::*;
+use termion::event as keyevent;
+use tui::{
+    layout::{Direction, Group, Rect, Size},
+    style::{Color, Style},
+    widgets::{Block, Borders, Row, SelectableList, Table, Widget},
+};
+
+use economy::MarketStatistics;
+
+/// Displays an overview of the sectors in the galaxy.
+pub struct SectorsTab {
+    state: Arc<Game>,
+    selected: usize,
+    max_selected: usize,
+}
+
+impl Tab for SectorsTab {
+    /// Creates a sectors tab.
+    fn new(state: Arc<Game>, _send_handle: Sender<Event>) -> Box<Self> {
+        let max_selected = state.galaxy.lock().unwrap().sectors.len().max(1) - 1;
+        Box::new(SectorsTab {
+            state,
+            selected: 0,
+            max_selected,
+        })
+    }
+
+    /// Returns the title string describing the tab.
+    fn title(&self) -> String {
+        String::from("Sectors")
+    }
+
+    /// Handles the user provided event.
+    fn handle_event(&mut self, event: Event) -> Option<GUIEvent> {
+        if let Event::Input(input) = event {
+            match input {
+                // Move up sector list.
+                keyevent::Key::Char('k') => self.selected = self.selected.max(1) - 1,
+                // Move down sector list.
+                keyevent::Key::Char('j') => {
+                    self.selected = (self.selected + 1).min(self.max_selected)
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Draws the tab in the given terminal and area.
+    fn draw(&self, term: &mut Terminal<MouseBackend>, area: &Rect) {
+        // Gather what is needed from the galaxy before locking the economy.
+        let (names, details) = {
+            let galaxy = self.state.galaxy.lock().unwrap();
+            let names = galaxy
+                .sectors
+                .iter()
+                .enumerate()
+                .map(|(index, sector)| {
+                    format!("{} ({})", sector_name(index, &sector.name), sector.faction)
+                })
+                .collect::<Vec<_>>();
+            let details = galaxy.sectors.get(self.selected).map(|sector| {
+                let planets = sector
+                    .system_ids
+                    .iter()
+                    .filter_map(|id| galaxy.system(*id))
+                    .map(|system| system.satelites.len())
+                    .sum::<usize>();
+                vec![
+                    format!(
+                        "Name:             {}",
+                        sector_name(self.selected, &sector.name)
+                    ),
+                    format!("Faction:          {}", sector.faction),
+                    format!("Systems:          {}", sector.system_ids.len()),
+                    format!("Planets:          {}", planets),
+                    format!(
+                        "Dominant economy: {}",
+                        galaxy
+                            .dominant_economy(sector)
+                            .map_or(String::from("None"), |economy| economy.to_string())
+                    ),
+                    format!("Area:             {:.0}", sector.area()),
+                    format!("Neighbors:        {}", sector.neighbors.len()),
+                    format!(
+                        "Contested:        {}",
+                        if sector.contested_for > 0 {
+                            format!("for {} updates", sector.contested_for)
+                        } else {
+                            String::from("No")
+                        }
+                    ),
+                ]
+            });
+            (names, details.unwrap_or_default())
+        };
+        let statistics = self
+            .state
+            .economy
+            .lock()
+            .unwrap()
+            .market_statistics(self.selected);
+
+        Group::default()
+            .direction(Direction::Horizontal)
+            .sizes(&[Size::Fixed(40), Size::Min(1)])
+            .render(term, area, |term, chunks| {
+                SelectableList::default()
+                    .block(Block::default().title("Sectors").borders(Borders::ALL))
+                    .items(&names)
+                    .select(self.selected)
+                    .style(Style::default())
+                    .highlight_style(Style::default().bg(Color::White))
+                    .render(term, &chunks[0]);
+                Group::default()
+                    .direction(Direction::Vertical)
+                    .sizes(&[Size::Fixed(details.len() as u16 + 6), Size::Min(1)])
+                    .render(term, &chunks[1], |term, chunks| {
+                        draw_details(&details, statistics.as_ref(), term, chunks[0]);
+                        draw_prices(statistics.as_ref(), term, chunks[1]);
+                    });
+            });
+    }
+}
+
+/// Returns the name of the sector, falling back on its index for unnamed
+/// sectors.
+fn sector_name(index: usize, name: &str) -> String {
+    if name.is_empty() {
+        format!("Sector {}", index + 1)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Draw the sector details together with the aggregate market statistics.
+fn draw_details(
+    details: &[String],
+    statistics: Option<&MarketStatistics>,
+    term: &mut Terminal<MouseBackend>,
+    area: Rect,
+) {
+    let mut items = details.to_vec();
+    if let Some(statistics) = statistics {
+        items.push(format!("Population:       {:.0}", statistics.population));
+        items.push(format!("Credits:          {}", statistics.credits));
+        items.push(format!("Traded amount:    {}", statistics.traded_amount));
+        items.push(format!("Traded value:     {}", statistics.traded_value));
+    }
+    SelectableList::default()
+        .block(Block::default().title("Overview").borders(Borders::ALL))
+        .items(&items)
+        .style(Style::default().fg(Color::Yellow))
+        .render(term, &area);
+}
+
+/// Draw the average price of each commodity traded in the sector market.
+fn draw_prices(
+    statistics: Option<&MarketStatistics>,
+    term: &mut Terminal<MouseBackend>,
+    area: Rect,
+) {
+    let mut prices = statistics
+        .map(|statistics| {
+            statistics
+                .average_prices
+                .iter()
+                .map(|(commodity, price)| (commodity.to_string(), *price))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    prices.sort();
+    let style = Style::default();
+    Table::new(
+        ["Commodity", "Average price"].into_iter(),
+        prices.iter().map(|&(ref commodity, price)| {
+            Row::StyledData(
+                vec![commodity.clone(), price.to_string()].into_iter(),
+                &style,
+            )
+        }),
+    )
+    .block(
+        Block::default()
+            .title("Average prices")
+            .borders(Borders::ALL),
+    )
+    .header_style(Style::default().fg(Color::Yellow))
+    .widths(&[20, 15])
+    .render(term, &area);
+}
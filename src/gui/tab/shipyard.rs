@@ -8,6 +8,7 @@ use tui::{
     widgets::{Block, Borders, Paragraph, SelectableList, Widget},
 };
 
+use astronomicals::station::Service;
 use ship::ShipCharacteristics;
 
 /// Displays the shipyard tab.
@@ -27,14 +28,13 @@ impl Tab for ShipyardTab {
         // TODO: Feels bad to clone the arc just to avoid borrower here.
         let dup_state = state.clone();
         let galaxy = dup_state.galaxy.lock().unwrap();
-        let player_system = galaxy
-            .system(&dup_state.player.lock().unwrap().location())
-            .unwrap();
+        let player = dup_state.player.lock().unwrap();
+        let player_system = galaxy.system(player.system()).unwrap();
         let available_ships = dup_state
             .shipyard
             .lock()
             .unwrap()
-            .get_available(player_system);
+            .get_available(player_system, player.standing());
 
         Box::new(ShipyardTab {
             state,
@@ -69,15 +69,14 @@ impl Tab for ShipyardTab {
                 // Find all ships available at the current system.
                 // If player is not at a system something is very wrong.
                 let galaxy = self.state.galaxy.lock().unwrap();
-                let player_system = galaxy
-                    .system(&self.state.player.lock().unwrap().location())
-                    .unwrap();
+                let player = self.state.player.lock().unwrap();
+                let player_system = galaxy.system(player.system()).unwrap();
                 self.available_ships = self
                     .state
                     .shipyard
                     .lock()
                     .unwrap()
-                    .get_available(player_system);
+                    .get_available(player_system, player.standing());
                 self.max_selected = self.available_ships.len() - 1;
                 // Guard against the number of ships being reduced.
                 self.selected = self.selected.min(self.max_selected);
@@ -89,6 +88,22 @@ impl Tab for ShipyardTab {
 
     /// Draws the tab in the given terminal and area.
     fn draw(&self, term: &mut Terminal<MouseBackend>, area: &Rect) {
+        // Ships are only sold while docked at a shipyard.
+        let has_shipyard = {
+            let galaxy = self.state.galaxy.lock().unwrap();
+            let player = self.state.player.lock().unwrap();
+            galaxy
+                .system(player.system())
+                .map_or(false, |system| player.can_use(Service::Shipyard, system))
+        };
+        if !has_shipyard {
+            Paragraph::default()
+                .block(Block::default().title("Ships").borders(Borders::ALL))
+                .text("There is no shipyard here.")
+                .render(term, area);
+            return;
+        }
+
         Group::default()
             .direction(Direction::Horizontal)
             //.sizes(&[Size::Percent(10), Size::Percent(90)])
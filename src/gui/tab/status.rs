@@ -1,4 +1,5 @@
 use super::*;
+use termion::event as keyevent;
 use tui::{
     layout::{Direction, Group, Rect, Size},
     style::{Color, Style},
@@ -28,7 +29,16 @@ impl Tab for StatusTab {
     }
 
     /// Handles the user provided event.
-    fn handle_event(&mut self, _event: Event) -> Option<GUIEvent> {
+    fn handle_event(&mut self, event: Event) -> Option<GUIEvent> {
+        if let Event::Input(input) = event {
+            match input {
+                // Pause or resume game time.
+                keyevent::Key::Char('p') => self.sender.send(Event::TogglePause).unwrap(),
+                // Skip ahead one day.
+                keyevent::Key::Char('n') => self.sender.send(Event::AdvanceDays(1)).unwrap(),
+                _ => {}
+            }
+        }
         None
     }
 
@@ -54,18 +64,25 @@ fn draw_player_info(
     // Data fields to be displayed in a table like format.
     let ship = player.ship();
     let galaxy = state.galaxy.lock().unwrap();
+    let clock = state.clock.lock().unwrap();
     let player_data = vec![
+        format!(
+            "Date:      {}{}",
+            clock.now().format("%Y-%m-%d %H:%M"),
+            if clock.is_paused() { " (Paused)" } else { "" }
+        ),
+        format!("Speed:     {}x", clock.time_scale()),
         format!(
             "Location:  {}",
             match player.state() {
                 PlayerState::InSystem => {
-                    galaxy.system(&player.location()).unwrap().name.clone() + " System"
+                    galaxy.system(player.system()).unwrap().name.clone() + " System"
                 }
-                PlayerState::Docked(planet_id) => {
-                    let system = galaxy.system(&player.location()).unwrap();
+                PlayerState::Docked(_) => {
+                    let system = galaxy.system(player.system()).unwrap();
                     let system_name = &system.name;
-                    let planet_name = &system.satelites[planet_id].name;
-                    format!("{}, {} System", planet_name, system_name)
+                    let body_name = player.docked_at(system).map_or("-", |body| body.name());
+                    format!("{}, {} System", body_name, system_name)
                 }
                 _ => String::from("-"),
             }
@@ -73,8 +90,8 @@ fn draw_player_info(
         format!(
             "Status:    {}",
             match player.state() {
-                PlayerState::Traveling { .. } => match player.eta() {
-                    Some((eta, system_loc)) => match galaxy.system(&system_loc) {
+                PlayerState::Traveling { .. } => match player.eta(&clock) {
+                    Some((eta, destination)) => match galaxy.system(destination) {
                         Some(system) => {
                             format!("Traveling to {} System, ETA: {}", system.name.clone(), eta)
                         }
@@ -108,6 +125,30 @@ fn draw_player_info(
                 None => String::from("-"),
             }
         ),
+        format!(
+            "   Cargo:     {}",
+            match *ship {
+                Some(ref ship) => {
+                    let mut cargo = ship
+                        .cargo()
+                        .iter()
+                        .filter(|&(_, &amount)| amount > 0)
+                        .collect::<Vec<_>>();
+                    cargo.sort();
+                    let cargo = cargo
+                        .into_iter()
+                        .map(|(commodity, amount)| format!("{} {}", amount, commodity))
+                        .collect::<Vec<_>>();
+                    format!(
+                        "{}/{} {}",
+                        ship.cargo_used(),
+                        ship.characteristics().cargo,
+                        cargo.join(", ")
+                    )
+                }
+                None => String::from("-"),
+            }
+        ),
     ];
 
     // TODO: Move image to resource file.
@@ -1,13 +1,18 @@
 use super::*;
+use std::f64::consts::PI;
 use termion::event as keyevent;
 
-use astronomicals::System;
+use astronomicals::{
+    planet::PlanetType,
+    station::{Dockable, Service, StationKind},
+    System,
+};
 use player::PlayerState;
 use tui::layout::{Direction, Group, Rect, Size};
 use tui::style::{Color, Style};
-use tui::widgets::{Block, Borders, Row, Table, Widget};
+use tui::widgets::{canvas::Canvas, Block, Borders, Paragraph, Row, Table, Widget};
 
-use gui::dialog::MultiDialog;
+use gui::dialog::{Action, MultiDialog};
 
 lazy_static! {
     /// Styling for selected item.
@@ -29,25 +34,42 @@ pub struct SystemMapTab {
 }
 
 impl SystemMapTab {
-    /// Returns the last index of satelites in the current system, defaults to zero.
+    /// Returns the last index of bodies in the current system, defaults to
+    /// zero.
     fn num_astronomicals(state: &Arc<Game>) -> usize {
         let player = state.player.lock().unwrap();
         match player.state() {
             PlayerState::InSystem | PlayerState::Docked(_) => {
                 let galaxy = state.galaxy.lock().unwrap();
-                galaxy.system(&player.location()).unwrap().satelites.len() - 1
+                galaxy
+                    .system(player.system())
+                    .unwrap()
+                    .dockables()
+                    .len()
+                    .saturating_sub(1)
             }
             _ => 0,
         }
     }
 
-    /// Opens dialog for planet interaction.
-    /// Actions available depends on the current player state.
+    /// Returns the index among the dockable bodies of the selected body, which
+    /// are listed as they orbit.
+    fn selected_dockable(&self, system: &System) -> usize {
+        system
+            .hierarchy()
+            .get(self.selected_astronomical)
+            .map_or(0, |&(index, _)| index)
+    }
+
+    /// Opens dialog for planet and station interaction.
+    /// Actions available depends on the current player state and the services
+    /// offered.
     fn try_open_dialog(&self) -> Option<Box<MultiDialog>> {
         let player = self.state.player.lock().unwrap();
         let galaxy = self.state.galaxy.lock().unwrap();
-        let system = galaxy.system(&player.location()).unwrap();
-        let planet_id = self.selected_astronomical;
+        let system = galaxy.system(player.system()).unwrap();
+        let planet_id = self.selected_dockable(system);
+        let body = system.dockable(planet_id)?;
 
         match player.state() {
             PlayerState::InSystem => {
@@ -58,27 +80,37 @@ impl SystemMapTab {
                 });
 
                 Some(Box::new(MultiDialog::new(
-                    system.satelites[self.selected_astronomical].name.clone(),
+                    body.name().to_string(),
                     vec![("Dock", dock_fn)],
                 )))
             }
-            PlayerState::Docked(id) if id == self.selected_astronomical => {
-                // If docked system we can refuel.
-                let refuel_fn = Box::new(|sender: &mut Sender<Event>| {
-                    sender.send(Event::Refuel).unwrap();
-                    Some(GUIEvent::CloseDialog)
-                });
-
+            PlayerState::Docked(id) if id == planet_id => {
                 // If docked system we can undock.
                 let undock_fn = Box::new(move |sender: &mut Sender<Event>| {
                     sender.send(Event::Undock(planet_id)).unwrap();
                     Some(GUIEvent::CloseDialog)
                 });
+                let mut actions: Vec<(&str, Box<Action>)> = vec![("Undock", undock_fn)];
 
-                Some(Box::new(MultiDialog::new(
-                    system.satelites[self.selected_astronomical].name.clone(),
-                    vec![("Undock", undock_fn), ("Refuel", refuel_fn)],
-                )))
+                // If docked where fuel is sold we can refuel.
+                if body.offers(Service::Fuel) {
+                    let refuel_fn = Box::new(|sender: &mut Sender<Event>| {
+                        sender.send(Event::Refuel).unwrap();
+                        Some(GUIEvent::CloseDialog)
+                    });
+                    actions.push(("Refuel", refuel_fn));
+                }
+
+                // If docked where repairs are offered we can repair.
+                if body.offers(Service::Repair) {
+                    let repair_fn = Box::new(|sender: &mut Sender<Event>| {
+                        sender.send(Event::Repair).unwrap();
+                        Some(GUIEvent::CloseDialog)
+                    });
+                    actions.push(("Repair", repair_fn));
+                }
+
+                Some(Box::new(MultiDialog::new(body.name().to_string(), actions)))
             }
             _ => None,
         }
@@ -137,37 +169,49 @@ impl Tab for SystemMapTab {
     fn draw(&self, term: &mut Terminal<MouseBackend>, area: &Rect) {
         Group::default()
             .direction(Direction::Horizontal)
-            .sizes(&[Size::Fixed(85), Size::Percent(70)])
+            .sizes(&[Size::Fixed(100), Size::Min(1)])
             .render(term, area, |term, chunks| {
                 let player = self.state.player.lock().unwrap();
                 match player.state() {
                     PlayerState::InSystem => {
                         let galaxy = self.state.galaxy.lock().unwrap();
-                        let system = galaxy.system(&player.location()).unwrap();
+                        let system = galaxy.system(player.system()).unwrap();
                         let populations = self.state.economy.lock().unwrap().populations(&system);
                         draw_system_table(
-                            self.selected_astronomical,
+                            self.selected_dockable(system),
                             None,
                             &populations,
                             &system,
                             term,
                             chunks[0],
                         );
-                        draw_system_map(self.selected_astronomical, &system, term, chunks[1]);
+                        draw_system_map(
+                            self.selected_dockable(system),
+                            None,
+                            &system,
+                            term,
+                            chunks[1],
+                        );
                     }
                     PlayerState::Docked(id) => {
                         let galaxy = self.state.galaxy.lock().unwrap();
-                        let system = galaxy.system(&player.location()).unwrap();
+                        let system = galaxy.system(player.system()).unwrap();
                         let populations = self.state.economy.lock().unwrap().populations(&system);
                         draw_system_table(
-                            self.selected_astronomical,
+                            self.selected_dockable(system),
                             Some(id),
                             &populations,
                             &system,
                             term,
                             chunks[0],
                         );
-                        draw_system_map(self.selected_astronomical, &system, term, chunks[1]);
+                        draw_system_map(
+                            self.selected_dockable(system),
+                            Some(id),
+                            &system,
+                            term,
+                            chunks[1],
+                        );
                     }
                     _ => {}
                 }
@@ -175,6 +219,9 @@ impl Tab for SystemMapTab {
     }
 }
 
+/// Draws a table of the bodies of the system as they orbit, with moons and
+/// stations indented below their planet, and the services offered by the
+/// selected one.
 fn draw_system_table(
     selected: usize,
     docked_at: Option<usize>,
@@ -183,49 +230,192 @@ fn draw_system_table(
     term: &mut Terminal<MouseBackend>,
     area: Rect,
 ) {
-    Table::new(
-        // Prepending empty character to get alignment with list above.
-        [
-            " Planet",
-            "Mass",
-            "Population",
-            "Temperature",
-            "Type",
-            "Economy",
-        ].into_iter(),
-        system
-            .satelites
-            .iter()
-            .enumerate()
-            .map(|(idx, ref planet)| {
-                let style: &Style = match docked_at {
-                    _ if idx == selected => &SELECTED_STYLE,
-                    Some(id) if idx == id => &DOCKED_STYLE,
-                    _ => &DEFAULT_STYLE,
-                };
-                Row::StyledData(
-                    vec![
-                        format!(" {}", planet.name.clone()),
-                        format!("{:.1}", planet.mass),
-                        format!("{:.1} M", populations[idx]),
-                        format!("{:.1}", planet.surface_temperature),
-                        planet.planet_type.to_string(),
-                        planet.economic_type.to_string(),
-                    ].into_iter(),
-                    &style,
-                )
-            }),
-    ).block(Block::default().title(&system.name).borders(Borders::ALL))
-        .header_style(Style::default().fg(Color::Yellow))
-        .widths(&[15, 5, 15, 15, 10, 15])
-        .render(term, &area);
+    let dockables = system.dockables();
+    let services = dockables
+        .get(selected)
+        .map(|body| {
+            body.services()
+                .iter()
+                .map(|service| service.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default();
+
+    Group::default()
+        .direction(Direction::Vertical)
+        .sizes(&[Size::Min(1), Size::Fixed(3)])
+        .render(term, &area, |term, chunks| {
+            Table::new(
+                // Prepending empty character to get alignment with list above.
+                [
+                    " Body",
+                    "Mass",
+                    "Gravity",
+                    "Period",
+                    "Population",
+                    "Temp",
+                    "Type",
+                    "Economy",
+                ].into_iter(),
+                system.hierarchy().into_iter().map(|(idx, depth)| {
+                    let body = dockables[idx];
+                    let style: &Style = match docked_at {
+                        _ if idx == selected => &SELECTED_STYLE,
+                        Some(id) if idx == id => &DOCKED_STYLE,
+                        _ => &DEFAULT_STYLE,
+                    };
+                    let (mass, gravity, period, temperature, kind) = match body {
+                        Dockable::Planet(planet) => (
+                            format!("{:.2}", planet.mass),
+                            format!("{:.2} g", planet.gravity),
+                            format!("{:.0} d", planet.orbital_period),
+                            format!("{:.1}", planet.surface_temperature),
+                            if depth > 0 {
+                                String::from("Moon")
+                            } else {
+                                planet.planet_type.to_string()
+                            },
+                        ),
+                        Dockable::Station(station) => (
+                            String::from("-"),
+                            String::from("-"),
+                            String::from("-"),
+                            String::from("-"),
+                            station.kind.to_string(),
+                        ),
+                        Dockable::Belt(belt) => (
+                            format!("{:.3}", belt.mass),
+                            String::from("-"),
+                            format!("{:.0} d", belt.orbital_period),
+                            String::from("-"),
+                            String::from("Belt"),
+                        ),
+                    };
+                    Row::StyledData(
+                        vec![
+                            format!(" {}{}", "  ".repeat(depth), body.name()),
+                            mass,
+                            gravity,
+                            period,
+                            format!("{:.1} M", populations.get(idx).cloned().unwrap_or(0.)),
+                            temperature,
+                            kind,
+                            body.economy().to_string(),
+                        ].into_iter(),
+                        &style,
+                    )
+                }),
+            ).block(Block::default().title(&system.name).borders(Borders::ALL))
+                .header_style(Style::default().fg(Color::Yellow))
+                .widths(&[24, 6, 7, 8, 11, 6, 10, 12])
+                .render(term, &chunks[0]);
+            Paragraph::default()
+                .block(Block::default().title("Services").borders(Borders::ALL))
+                .wrap(true)
+                .text(&services)
+                .render(term, &chunks[1]);
+        });
 }
 
+/// Draws the stars of the system surrounded by its belts and planets, each
+/// with the moons and stations orbiting it. Planets are spread out by a fixed
+/// angle and their distance from the star is compressed.
 fn draw_system_map(
-    _selected: usize,
-    _system: &System,
-    _term: &mut Terminal<MouseBackend>,
-    _area: Rect,
+    selected: usize,
+    docked_at: Option<usize>,
+    system: &System,
+    term: &mut Terminal<MouseBackend>,
+    area: Rect,
 ) {
-    // TODO: Find decent presentation of a system, ascii art?
+    // Golden angle, which avoids lining planets up.
+    let angle = 2.4;
+    let positions = system
+        .satelites
+        .iter()
+        .enumerate()
+        .map(|(index, planet)| {
+            let radius = planet.orbit_distance.sqrt();
+            let angle = index as f64 * angle;
+            (radius * angle.cos(), radius * angle.sin())
+        })
+        .collect::<Vec<_>>();
+    let bounds = positions
+        .iter()
+        .map(|&(x, y)| x.abs().max(y.abs()))
+        .chain(system.belts.iter().map(|belt| belt.orbit_distance.sqrt()))
+        .fold(1., f64::max)
+        * 1.2;
+    let color = |index: usize, default: Color| match docked_at {
+        _ if index == selected => Color::Yellow,
+        Some(id) if index == id => Color::Green,
+        _ => default,
+    };
+
+    // Moons and belts are listed after the planets and stations.
+    let first_moon = system.satelites.len() + system.stations.len();
+    let first_belt = first_moon
+        + system
+            .satelites
+            .iter()
+            .map(|planet| planet.moons.len())
+            .sum::<usize>();
+
+    Canvas::default()
+        .block(Block::default().title("System").borders(Borders::ALL))
+        .paint(|ctx| {
+            ctx.print(0., 0., "*", system.star.class.color());
+            if let Some(ref companion) = system.star.companion {
+                ctx.print(bounds * 0.05, 0., "*", companion.class.color());
+            }
+            for (index, belt) in system.belts.iter().enumerate() {
+                let radius = belt.orbit_distance.sqrt();
+                for step in 0..24 {
+                    let angle = f64::from(step) * PI / 12.;
+                    ctx.print(
+                        radius * angle.cos(),
+                        radius * angle.sin(),
+                        ":",
+                        color(first_belt + index, Color::Gray),
+                    );
+                }
+            }
+            let mut moon_index = first_moon;
+            for (index, planet) in system.satelites.iter().enumerate() {
+                let (x, y) = positions[index];
+                let symbol = match planet.planet_type {
+                    PlanetType::GasGiant => "O",
+                    _ => "o",
+                };
+                ctx.print(x, y, symbol, color(index, Color::White));
+                // Moons are drawn below the planet they orbit.
+                for moon in 0..planet.moons.len() {
+                    ctx.print(
+                        x,
+                        y - bounds * 0.05 * (moon + 1) as f64,
+                        ".",
+                        color(moon_index + moon, Color::White),
+                    );
+                }
+                moon_index += planet.moons.len();
+            }
+            // Stations are drawn next to the planet they orbit.
+            for (index, station) in system.stations.iter().enumerate() {
+                if let Some(&(x, y)) = positions.get(station.planet) {
+                    let symbol = match station.kind {
+                        StationKind::Station => "#",
+                        StationKind::Outpost => "+",
+                    };
+                    ctx.print(
+                        x + bounds * 0.05,
+                        y,
+                        symbol,
+                        color(system.satelites.len() + index, Color::Cyan),
+                    );
+                }
+            }
+        })
+        .x_bounds([-bounds, bounds])
+        .y_bounds([-bounds, bounds])
+        .render(term, &area);
 }
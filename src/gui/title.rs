@@ -1,6 +1,5 @@
 use event::Event;
 use std::fmt;
-use std::sync::mpsc::Sender;
 use termion::event as keyevent;
 use tui::{backend::MouseBackend, layout::Rect, Terminal};
 use tui::{
@@ -10,7 +9,10 @@ use tui::{
 };
 
 use super::GUIEvent;
-use gui::dialog::ConfirmDialog;
+use game_config::{Difficulty, GameConfig};
+use gui::dialog::NewGameDialog;
+use mods::mods;
+use resources::mod_errors;
 
 /// Actions available on the title page.
 #[derive(Clone, Copy)]
@@ -37,17 +39,41 @@ pub struct TitlePage {
     title: String,
     selected: usize,
     actions: Vec<Action>,
+    mods: String,
+    difficulty: Difficulty,
+    seed: u32,
+    scenario: Option<String>,
 }
 
 impl TitlePage {
-    /// Creates a new title page.
-    pub fn new() -> Self {
+    /// Creates a new title page, offering new games like the given config.
+    pub fn new(config: &GameConfig) -> Self {
         let title = String::from(include_str!("../../res/title.txt"));
         let actions = vec![Action::NewGame, Action::LoadGame];
+        let names = mods()
+            .iter()
+            .map(|active_mod| active_mod.name.clone())
+            .collect::<Vec<_>>();
+        let mut mods = if names.is_empty() {
+            String::new()
+        } else {
+            format!("Active mods: {}", names.join(", "))
+        };
+        let errors = mod_errors().len();
+        if errors > 0 {
+            mods.push_str(&format!(
+                "\n{} invalid mod resources were skipped, run validate-resources for details",
+                errors
+            ));
+        }
         TitlePage {
             title,
             selected: 0,
             actions,
+            mods,
+            difficulty: config.difficulty,
+            seed: config.map_seed,
+            scenario: config.scenario.clone(),
         }
     }
 
@@ -64,16 +90,9 @@ impl TitlePage {
             return match input {
                 keyevent::Key::Char('\n') => match self.actions[self.selected] {
                     Action::NewGame => {
-                        let confirm_action =
-                            Box::new(|_: &mut Sender<Event>| Some(GUIEvent::StartNewGame));
-
-                        let cancel_action =
-                            Box::new(|_: &mut Sender<Event>| Some(GUIEvent::CloseDialog));
-                        Some(GUIEvent::OpenDialog(Box::new(ConfirmDialog::new(
-                            String::from("Are you sure? All saves will be lost"),
-                            confirm_action,
-                            cancel_action,
-                        ))))
+                        let dialog =
+                            NewGameDialog::new(self.difficulty, self.seed, self.scenario.as_ref());
+                        Some(GUIEvent::OpenDialog(Box::new(dialog)))
                     }
                     Action::LoadGame => Some(GUIEvent::LoadExistingGame),
                 },
@@ -93,7 +112,7 @@ impl TitlePage {
             .collect::<Vec<_>>();
         Group::default()
             .direction(Direction::Vertical)
-            .sizes(&[Size::Percent(50), Size::Percent(30)])
+            .sizes(&[Size::Percent(50), Size::Percent(30), Size::Percent(20)])
             .render(term, &page_area, |term, chunks| {
                 Paragraph::default()
                     .block(Block::default())
@@ -108,6 +127,13 @@ impl TitlePage {
                     .style(Style::default().fg(Color::Green))
                     .highlight_style(Style::default().fg(Color::Yellow))
                     .render(term, &chunks[1]);
+                Paragraph::default()
+                    .block(Block::default())
+                    .style(Style::default().fg(Color::DarkGray))
+                    .alignment(Alignment::Center)
+                    .wrap(true)
+                    .text(&self.mods)
+                    .render(term, &chunks[2]);
             });
     }
 }
@@ -0,0 +1,185 @@
+use serde_json;
+use std::{
+    io::{self, Write},
+    time::Instant,
+};
+
+use astronomicals::{sector::Sector, system::System};
+use economy::{Commodity, Economy, MarketStatistics};
+use game::{Game, Updatable};
+use game_config::GameConfig;
+use generators::generate_galaxy;
+
+/// Output formats supported by the headless simulation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Csv,
+    Json,
+}
+
+/// Statistics for all markets after a single simulation tick.
+#[derive(Serialize, Debug)]
+pub struct TickStatistics {
+    pub tick: u32,
+    pub markets: Vec<MarketStatistics>,
+}
+
+/// Generates a galaxy from the given config and runs the economy for the given
+/// number of ticks without any user interface, writing per tick statistics to
+/// the given output.
+pub fn simulate<W: Write>(
+    config: &GameConfig,
+    ticks: u32,
+    format: OutputFormat,
+    output: &mut W,
+) -> io::Result<()> {
+    info!("Generating galaxy for headless simulation...");
+    let galaxy = generate_galaxy(config);
+    let mut economy = Economy::new(&galaxy, config.agent_credits);
+
+    // Measure time for simulation.
+    let now = Instant::now();
+
+    if format == OutputFormat::Csv {
+        write_csv_header(output)?;
+    }
+
+    let mut history = vec![];
+    for tick in 1..=ticks {
+        economy.update();
+        let statistics = TickStatistics {
+            tick,
+            markets: economy.statistics(),
+        };
+
+        // CSV can be written as we go, JSON is written as a whole at the end.
+        match format {
+            OutputFormat::Csv => write_csv_rows(output, &statistics)?,
+            OutputFormat::Json => history.push(statistics),
+        }
+    }
+
+    if format == OutputFormat::Json {
+        serde_json::to_writer_pretty(&mut *output, &history)?;
+        writeln!(output)?;
+    }
+
+    info!(
+        "Headless simulation of {} ticks finished, took {} ms",
+        ticks,
+        ((now.elapsed().as_secs() * 1_000) + u64::from(now.elapsed().subsec_millis()))
+    );
+    output.flush()
+}
+
+/// Writes the header row, with one price column per commodity.
+fn write_csv_header<W: Write>(output: &mut W) -> io::Result<()> {
+    write!(
+        output,
+        "tick,market,population,credits,traded_amount,traded_value"
+    )?;
+    for commodity in Commodity::values() {
+        write!(output, ",price_{}", commodity.id())?;
+    }
+    writeln!(output)
+}
+
+/// Writes one row per market for the given tick.
+fn write_csv_rows<W: Write>(output: &mut W, statistics: &TickStatistics) -> io::Result<()> {
+    for (index, market) in statistics.markets.iter().enumerate() {
+        write!(
+            output,
+            "{},{},{:.1},{},{},{}",
+            statistics.tick,
+            index,
+            market.population,
+            market.credits,
+            market.traded_amount,
+            market.traded_value
+        )?;
+        for commodity in Commodity::values() {
+            write!(
+                output,
+                ",{}",
+                market.average_prices.get(commodity).unwrap_or(&0)
+            )?;
+        }
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+/// Galaxy as written by the generate command, with systems ordered by id.
+#[derive(Serialize)]
+struct GalaxyDump<'a> {
+    seed: u32,
+    sectors: &'a [Sector],
+    systems: Vec<&'a System>,
+}
+
+/// Generates a galaxy from the given config and writes it as JSON to the
+/// given output.
+pub fn generate<W: Write>(config: &GameConfig, output: &mut W) -> io::Result<()> {
+    let galaxy = generate_galaxy(config);
+    let mut systems = galaxy.systems().collect::<Vec<_>>();
+    systems.sort_by_key(|system| system.id);
+
+    let dump = GalaxyDump {
+        seed: config.map_seed,
+        sectors: &galaxy.sectors,
+        systems,
+    };
+    serde_json::to_writer_pretty(&mut *output, &dump)?;
+    writeln!(output)?;
+    output.flush()
+}
+
+/// Writes a human readable summary of the given game to the given output.
+pub fn inspect<W: Write>(game: &Game, output: &mut W) -> io::Result<()> {
+    let (now, paused, time_scale) = {
+        let clock = game.clock.lock().unwrap();
+        (clock.now(), clock.is_paused(), clock.time_scale())
+    };
+    let player = game.player.lock().unwrap();
+    let galaxy = game.galaxy.lock().unwrap();
+
+    writeln!(
+        output,
+        "Game time:  {}{}",
+        now.format("%Y-%m-%d %H:%M"),
+        if paused { " (paused)" } else { "" }
+    )?;
+    writeln!(output, "Time scale: {}", time_scale)?;
+    writeln!(output, "Credits:    {}", player.balance())?;
+    match galaxy.system(player.system()) {
+        Some(system) => writeln!(
+            output,
+            "Location:   {} ({}, {} security)",
+            system.name, system.faction, system.security
+        )?,
+        None => writeln!(output, "Location:   unknown")?,
+    }
+    match *player.ship() {
+        Some(ref ship) => writeln!(
+            output,
+            "Ship:       {} (fuel {}, cargo {}/{})",
+            ship.characteristics().name,
+            ship.fuel(),
+            ship.cargo_used(),
+            ship.characteristics().cargo
+        )?,
+        None => writeln!(output, "Ship:       none")?,
+    }
+    writeln!(
+        output,
+        "Galaxy:     {} systems in {} sectors",
+        galaxy.systems.len(),
+        galaxy.sectors.len()
+    )?;
+    writeln!(
+        output,
+        "Markets:    {}",
+        game.economy.lock().unwrap().statistics().len()
+    )?;
+    output.flush()
+}
@@ -2,19 +2,26 @@ extern crate app_dirs;
 extern crate bincode;
 extern crate chrono;
 #[macro_use]
+extern crate clap;
+#[macro_use]
 extern crate derive_builder;
 extern crate fern;
+#[cfg(test)]
+#[macro_use]
+extern crate quickcheck;
 extern crate inflector;
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
 extern crate log;
+extern crate png;
 extern crate rand;
 extern crate rayon;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+extern crate serde_path_to_error;
 extern crate spade;
 extern crate statrs;
 extern crate termion;
@@ -23,28 +30,32 @@ extern crate toml;
 extern crate tui;
 
 mod astronomicals;
+mod cli;
+mod clock;
 mod economy;
 mod entities;
 mod event;
+mod export;
 mod game;
 mod game_config;
 mod generators;
 mod gui;
+mod headless;
+mod mods;
 mod player;
 mod resources;
+mod scenario;
 mod ship;
 mod simulator;
 mod utils;
 
-use app_dirs::{get_data_root, AppDataType};
 use log::LevelFilter;
-use simulator::Simulator;
+use std::{fs::create_dir_all, path::PathBuf, process};
 
-/// Setup logging to file in user data dir.
-pub fn setup_logger() -> Result<(), fern::InitError> {
-    let output_path = get_data_root(AppDataType::UserConfig)
-        .unwrap()
-        .join("gemini/debug.log");
+/// Setup logging at the given level to file in the data dir.
+pub fn setup_logger(level: LevelFilter) -> Result<(), fern::InitError> {
+    let root = game_config::data_root();
+    create_dir_all(&root)?;
     fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
@@ -56,23 +67,47 @@ pub fn setup_logger() -> Result<(), fern::InitError> {
             ))
         })
         .level(log::LevelFilter::Off)
-        .level_for("gemini", LevelFilter::Trace)
-        .chain(fern::log_file(output_path)?)
+        .level_for("gemini", level)
+        .chain(fern::log_file(root.join("debug.log"))?)
         .apply()?;
     Ok(())
 }
 
 fn main() {
-    // Init logger
-    setup_logger().unwrap();
+    let matches = cli::app().get_matches();
 
-    // Start event handler
-    event::EventHandler::start();
+    // Global options affect everything else, so apply them first.
+    if let Some(path) = matches.value_of("data-dir") {
+        game_config::set_data_root(PathBuf::from(path));
+    }
+    let level = value_t!(matches, "log-level", LevelFilter).unwrap_or_else(|err| err.exit());
+
+    // Init logger
+    setup_logger(level).unwrap();
 
-    // Start simulator
-    let simulator = Simulator::new();
+    // Validate resources, reporting all problems before anything uses them.
+    let errors = resources::validate_resources();
+    for error in &errors {
+        eprintln!("{}", error);
+    }
+    if matches.subcommand_name() == Some("validate-resources") {
+        let mod_errors = resources::mod_errors();
+        for error in &mod_errors {
+            eprintln!("{}", error);
+        }
+        if errors.is_empty() && mod_errors.is_empty() {
+            println!("All resources are valid");
+            return;
+        }
+        process::exit(1);
+    }
+    if !errors.is_empty() {
+        eprintln!("Failed loading resources, see above for details");
+        process::exit(1);
+    }
 
-    // Init and start gui
-    let mut gui = gui::Gui::new(simulator);
-    gui.start();
+    if let Err(msg) = cli::run(&matches) {
+        eprintln!("{}", msg);
+        process::exit(1);
+    }
 }
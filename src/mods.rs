@@ -0,0 +1,263 @@
+use serde_json::{self, Map, Value};
+use std::{
+    collections::HashMap,
+    fs::{read_dir, File},
+    io::prelude::*,
+    path::Path,
+};
+
+use game_config::data_root;
+use resources::{Problem, ResourceError};
+
+/// Location of the user mods relative to the data root.
+const MODS_PATH: &str = "mods/";
+
+/// Optional file in the mods directory listing mod names in load order.
+const LOAD_ORDER_FILE: &str = "load_order.txt";
+
+/// Top level key of a mod resource which replaces the built-in resource
+/// instead of being merged into it.
+const REPLACE_KEY: &str = "replace";
+
+/// Keys used to identify entries of resource lists when merging.
+const ID_KEYS: [&str; 2] = ["id", "name"];
+
+lazy_static! {
+    /// Mods found in the user mods directory, in load order.
+    static ref MODS: Vec<Mod> = Mod::load_all(&data_root().join(MODS_PATH));
+}
+
+/// A user mod, i.e. a directory of resource files named after the resource
+/// they override, e.g. `ships.json`.
+#[derive(Debug)]
+pub struct Mod {
+    pub name: String,
+    resources: HashMap<String, Value>,
+    /// Resource files of the mod which could not be loaded.
+    errors: Vec<ResourceError>,
+}
+
+impl Mod {
+    /// Loads all mods in the given directory, ordered by the load order file
+    /// if present, followed by remaining mods in alphabetical order.
+    fn load_all(path: &Path) -> Vec<Mod> {
+        let mut names = match read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect::<Vec<_>>(),
+            Err(_) => return vec![],
+        };
+        names.sort();
+
+        let load_order = read_file(&path.join(LOAD_ORDER_FILE)).unwrap_or_default();
+        let ordered = load_order
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter(|name| names.iter().any(|other| other == name))
+            .map(String::from)
+            .collect::<Vec<_>>();
+        names.retain(|name| !ordered.contains(name));
+
+        ordered
+            .into_iter()
+            .chain(names)
+            .map(|name| Mod::load(&name, &path.join(&name)))
+            .collect()
+    }
+
+    /// Loads the resource files of the mod in the given directory, recording
+    /// files which are not valid JSON.
+    fn load(name: &str, path: &Path) -> Mod {
+        let mut resources = HashMap::new();
+        let mut errors = vec![];
+        if let Ok(entries) = read_dir(path) {
+            for path in entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+            {
+                if path
+                    .extension()
+                    .map_or(true, |extension| extension != "json")
+                {
+                    continue;
+                }
+                let key = match path.file_stem().and_then(|stem| stem.to_str()) {
+                    Some(key) => String::from(key),
+                    None => continue,
+                };
+                let file = format!("mods/{}/{}.json", name, key);
+                let error = match read_file(&path).map(|content| serde_json::from_str(&content)) {
+                    Some(Ok(value)) => {
+                        resources.insert(key, value);
+                        continue;
+                    }
+                    Some(Err(msg)) => ResourceError::syntax(file, &msg),
+                    None => ResourceError {
+                        file,
+                        problems: vec![Problem::new("-", "could not be read")],
+                    },
+                };
+                error!("Skipping resource of mod {}. {}", name, error);
+                errors.push(error);
+            }
+        }
+        info!(
+            "Loaded mod {} overriding {} resources",
+            name,
+            resources.len()
+        );
+
+        Mod {
+            name: String::from(name),
+            resources,
+            errors,
+        }
+    }
+
+    /// Returns the resource with the given key if overridden by the mod.
+    pub fn resource(&self, key: &str) -> Option<&Value> {
+        self.resources.get(key)
+    }
+
+    /// Returns the errors of resource files which could not be loaded.
+    pub fn errors(&self) -> &[ResourceError] {
+        &self.errors
+    }
+}
+
+/// Returns all active mods in load order.
+pub fn mods() -> &'static [Mod] {
+    &MODS
+}
+
+/// Layers the given mod resource on top of the base resource. The mod
+/// resource replaces the base if its top level `replace` key is true,
+/// otherwise the two are merged.
+pub fn layer(base: &mut Value, resource: &Value) {
+    let mut resource = resource.clone();
+    let replace = resource
+        .as_object_mut()
+        .and_then(|object| object.remove(REPLACE_KEY))
+        .and_then(|replace| replace.as_bool())
+        .unwrap_or(false);
+
+    if replace {
+        *base = resource;
+    } else {
+        merge(base, resource);
+    }
+}
+
+/// Merges the value into the base. Objects are merged key by key, list
+/// entries replace entries with the same id or name and other entries are
+/// appended, any other value replaces the base.
+fn merge(base: &mut Value, value: Value) {
+    match (base, value) {
+        (&mut Value::Object(ref mut base), Value::Object(value)) => merge_objects(base, value),
+        (&mut Value::Array(ref mut base), Value::Array(value)) => {
+            for entry in value {
+                match base.iter().position(|other| same_entry(other, &entry)) {
+                    Some(index) => merge(&mut base[index], entry),
+                    None => base.push(entry),
+                }
+            }
+        }
+        (base, value) => *base = value,
+    }
+}
+
+fn merge_objects(base: &mut Map<String, Value>, value: Map<String, Value>) {
+    for (key, value) in value {
+        if base.contains_key(&key) {
+            merge(base.get_mut(&key).unwrap(), value);
+        } else {
+            base.insert(key, value);
+        }
+    }
+}
+
+/// Returns true if the list entries refer to the same item, either by being
+/// equal or by sharing an identifying key.
+fn same_entry(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    ID_KEYS.iter().any(|key| match (a.get(key), b.get(key)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    })
+}
+
+fn read_file(path: &Path) -> Option<String> {
+    let mut content = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut content))
+        .ok()
+        .map(|_| content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    fn layered(base: &str, resource: &str) -> Value {
+        let mut base = serde_json::from_str(base).unwrap();
+        layer(&mut base, &serde_json::from_str(resource).unwrap());
+        base
+    }
+
+    #[test]
+    fn test_merge_lists_by_id() {
+        let base = r#"{"ships": [{"name": "Scarab", "cost": 10}, {"name": "Dao", "cost": 20}]}"#;
+        let resource = r#"{"ships": [{"name": "Dao", "cost": 30}, {"name": "Kite", "cost": 5}]}"#;
+        assert_eq!(
+            layered(base, resource),
+            serde_json::from_str::<Value>(
+                r#"{"ships": [
+                    {"name": "Scarab", "cost": 10},
+                    {"name": "Dao", "cost": 30},
+                    {"name": "Kite", "cost": 5}
+                ]}"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_merge_names_and_replace() {
+        let base = r#"{"names": ["Sol", "Vega"], "greek": ["Alpha"]}"#;
+        assert_eq!(
+            layered(base, r#"{"names": ["Vega", "Rigel"]}"#),
+            serde_json::from_str::<Value>(
+                r#"{"names": ["Sol", "Vega", "Rigel"], "greek": ["Alpha"]}"#
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            layered(base, r#"{"replace": true, "names": ["Rigel"]}"#),
+            serde_json::from_str::<Value>(r#"{"names": ["Rigel"]}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_json() {
+        let path = env::temp_dir().join(format!("gemini-mods-{}", std::process::id()));
+        let mod_path = path.join("broken");
+        fs::create_dir_all(&mod_path).unwrap();
+        fs::write(mod_path.join("ships.json"), r#"{"ships": [}"#).unwrap();
+        fs::write(mod_path.join("factions.json"), r#"{"factions": []}"#).unwrap();
+
+        let mods = Mod::load_all(&path);
+        fs::remove_dir_all(&path).unwrap();
+        assert_eq!(mods.len(), 1);
+        assert!(mods[0].resource("factions").is_some());
+        assert!(mods[0].resource("ships").is_none());
+        assert_eq!(mods[0].errors().len(), 1);
+        assert_eq!(mods[0].errors()[0].file, "mods/broken/ships.json");
+        assert_eq!(mods[0].errors()[0].problems[0].path, "line 1, column 12");
+    }
+}
@@ -1,33 +1,55 @@
 use chrono::{DateTime, Duration, Local, TimeZone, Utc};
 
+use std::u32;
+
+use astronomicals::{
+    station::{Dockable, Service},
+    system::{System, SystemId, SystemSecurity},
+};
+use clock::Clock;
+use entities::{Standing, StandingEvent};
+use resources::{fetch_resource, FactionResource};
 use ship::Ship;
 use utils::Point;
 
+/// Outcome of a docking request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Docking {
+    Docked,
+    /// Docked after the given units of illegal cargo were confiscated.
+    Busted(u32),
+    Refused,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 /// Player type holding the player's current ship, credits etc.
 pub struct Player {
     credits: u32,
     ship: Option<Ship>,
+    system: SystemId,
     location: Point,
     state: PlayerState,
+    standing: Standing,
 }
 
 impl Player {
-    /// Travling speed between systems, ly/ms.
+    /// Travling speed between systems, ly/ms of game time.
     const TRAVEL_SPEED: f64 = 10. / 60000.;
 
-    /// Create a new player.
-    pub fn new(credits: u32, ship: Ship, location: &Point) -> Self {
+    /// Create a new player located in the given system.
+    pub fn new(credits: u32, ship: Ship, system: &System) -> Self {
         Player {
             credits,
             ship: Some(ship),
-            location: *location,
+            system: system.id,
+            location: system.location,
             state: PlayerState::InSystem,
+            standing: Standing::default(),
         }
     }
 
-    /// Update the player state.
-    pub fn update_state(&mut self) {
+    /// Update the player state to the given game time.
+    pub fn update_state(&mut self, now: DateTime<Utc>) {
         // Should we continue to update?
         let mut repeat = true;
         while repeat {
@@ -41,10 +63,9 @@ impl Player {
                 } => {
                     match route.split_first() {
                         // Arrived at next system in route?
-                        Some((next, rest))
-                            if self.location.distance(&route[0])
-                                <= Utc::now().signed_duration_since(*start).num_milliseconds()
-                                    as f64
+                        Some((&(next_system, next), rest))
+                            if self.location.distance(&next)
+                                <= now.signed_duration_since(*start).num_milliseconds() as f64
                                     * Player::TRAVEL_SPEED =>
                         {
                             let new_start = *start
@@ -53,7 +74,8 @@ impl Player {
                                 );
 
                             // Update position and reduce fuel.
-                            self.location = *next;
+                            self.system = next_system;
+                            self.location = next;
                             if let Some(ref mut ship) = self.ship {
                                 ship.reduce_fuel();
                             }
@@ -97,6 +119,12 @@ impl Player {
         &mut self.ship
     }
 
+    /// Get the system the player is currently in, or last visited while
+    /// traveling.
+    pub fn system(&self) -> SystemId {
+        self.system
+    }
+
     /// Get the current player location.
     pub fn location(&self) -> Point {
         self.location
@@ -107,60 +135,160 @@ impl Player {
         self.state.clone()
     }
 
-    /// Docks the player to the planet with the given id.
-    pub fn dock(&mut self, planet_id: usize) {
+    /// Returns the player's standing with factions and systems.
+    pub fn standing(&self) -> &Standing {
+        &self.standing
+    }
+
+    /// Returns a mutable reference to the player's standing.
+    pub fn standing_mut(&mut self) -> &mut Standing {
+        &mut self.standing
+    }
+
+    /// Returns the body in the given system the player is docked at, if any.
+    pub fn docked_at<'a>(&self, system: &'a System) -> Option<Dockable<'a>> {
+        match self.state {
+            PlayerState::Docked(id) if system.id == self.system => system.dockable(id),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the player is docked at a body offering the given
+    /// service in the given system.
+    pub fn can_use(&self, service: Service, system: &System) -> bool {
+        self.docked_at(system)
+            .map_or(false, |body| body.offers(service))
+    }
+
+    /// Docks the player to the planet or station with the given id in the
+    /// given system. Docking is refused due to hostile standing or if there is
+    /// no such body. Outside of black markets, illegal cargo is confiscated in
+    /// systems patrolled well enough.
+    pub fn dock(&mut self, planet_id: usize, system: &System) -> Docking {
+        let black_market = match system.dockable(planet_id) {
+            Some(body) if self.standing.can_dock(system) => body.offers(Service::BlackMarket),
+            _ => return Docking::Refused,
+        };
         self.state = PlayerState::Docked(planet_id);
+
+        let patrolled = match system.security {
+            SystemSecurity::Medium | SystemSecurity::High => true,
+            SystemSecurity::Low | SystemSecurity::Anarchy => false,
+        };
+        let faction = fetch_resource::<FactionResource>().unwrap();
+        let definition = match faction.get(&system.faction) {
+            Some(definition) if patrolled && !black_market => definition,
+            _ => return Docking::Docked,
+        };
+        let confiscated = match self.ship {
+            Some(ref mut ship) => {
+                let illegal = ship
+                    .cargo()
+                    .keys()
+                    .filter(|commodity| !definition.is_legal(commodity))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                illegal
+                    .iter()
+                    .map(|commodity| ship.unload(commodity, u32::MAX))
+                    .sum()
+            }
+            None => 0,
+        };
+        if confiscated > 0 {
+            self.standing.record(StandingEvent::SmugglingBust, system);
+            Docking::Busted(confiscated)
+        } else {
+            Docking::Docked
+        }
     }
 
-    /// Undocks the player from its current planet.
+    /// Undocks the player from its current planet or station.
     pub fn undock(&mut self) {
         self.state = PlayerState::InSystem;
     }
 
-    /// Attemps to fuel up the player ship as far as credits reaches.
-    pub fn refuel(&mut self) {
+    /// Attemps to fuel up the player ship as far as credits reaches, buying
+    /// fuel counts as trading with the given system. Requires being docked
+    /// where fuel is sold.
+    pub fn refuel(&mut self, system: &System) {
+        if !self.can_use(Service::Fuel, system) {
+            return;
+        }
         if let Some(ref mut ship) = self.ship {
             // TODO: Assumes each fuel unit costs 10 credits.
-            let to_fill = (ship.characteristics().fuel - ship.fuel()).min(self.credits / 10);
-            self.credits -= to_fill * 10;
+            let unit_price = ((10. * self.standing.price_factor(system)).round() as u32).max(1);
+            let to_fill =
+                (ship.characteristics().fuel - ship.fuel()).min(self.credits / unit_price);
+            self.credits -= to_fill * unit_price;
             ship.add_fuel(to_fill);
+            if to_fill > 0 {
+                self.standing
+                    .record(StandingEvent::Trade(to_fill * unit_price), system);
+            }
         }
     }
 
-    /// Sets the route for the player.
-    pub fn set_route(&mut self, route: Vec<Point>) {
-        self.state = PlayerState::Traveling {
-            start: Utc::now(),
-            route,
-        };
+    /// Attempts to repair the player ship as far as credits reaches, paying
+    /// for repairs counts as trading with the given system. Requires being
+    /// docked where repairs are offered.
+    pub fn repair(&mut self, system: &System) {
+        if !self.can_use(Service::Repair, system) {
+            return;
+        }
+        if let Some(ref mut ship) = self.ship {
+            // TODO: Assumes each integrity unit costs 20 credits.
+            let unit_price = ((20. * self.standing.price_factor(system)).round() as u32).max(1);
+            let to_repair = (ship.characteristics().integrity - *ship.integrity())
+                .min(self.credits / unit_price);
+            self.credits -= to_repair * unit_price;
+            ship.repair(to_repair);
+            if to_repair > 0 {
+                self.standing
+                    .record(StandingEvent::Trade(to_repair * unit_price), system);
+            }
+        }
+    }
+
+    /// Sets the route for the player, starting at the given game time.
+    pub fn set_route(&mut self, route: Vec<(SystemId, Point)>, now: DateTime<Utc>) {
+        self.state = PlayerState::Traveling { start: now, route };
     }
 
     /// Get the player's currrent route, if available.
     pub fn route(&self) -> Option<Vec<&Point>> {
         match self.state {
-            PlayerState::Traveling { ref route, .. } => Some(route.iter().collect::<Vec<_>>()),
+            PlayerState::Traveling { ref route, .. } => {
+                Some(route.iter().map(|&(_, ref location)| location).collect())
+            }
             _ => None,
         }
     }
 
     /// Get the estimated time of arrival (local time) and the destination of the current route.
-    pub fn eta(&self) -> Option<(String, Point)> {
+    /// The arrival time is unknown while the clock is paused.
+    pub fn eta(&self, clock: &Clock) -> Option<(String, SystemId)> {
         match self.state {
             PlayerState::Traveling {
                 ref start,
                 ref route,
             } => {
-                let (dist, destination) = route
-                    .iter()
-                    .fold((0., &self.location), |(dist, prev), curr| {
-                        (dist + prev.distance(curr), curr)
-                    });
-                let eta = Local.from_utc_datetime(
-                    &(*start + Duration::milliseconds((dist / Player::TRAVEL_SPEED) as i64))
-                        .naive_utc(),
+                let (dist, _, destination) = route.iter().fold(
+                    (0., self.location, self.system),
+                    |(dist, prev, _), &(system, location)| {
+                        (dist + prev.distance(&location), location, system)
+                    },
                 );
-                // Format in HH:MM:SS and AM/PM.
-                Some((eta.format("%r").to_string(), *destination))
+                let arrival = *start + Duration::milliseconds((dist / Player::TRAVEL_SPEED) as i64);
+                let eta = match clock.to_real(arrival) {
+                    // Format in HH:MM:SS and AM/PM.
+                    Some(real_arrival) => Local
+                        .from_utc_datetime(&real_arrival.naive_utc())
+                        .format("%r")
+                        .to_string(),
+                    None => String::from("Paused"),
+                };
+                Some((eta, destination))
             }
             _ => None,
         }
@@ -172,8 +300,10 @@ impl Default for Player {
         Player {
             credits: 0,
             ship: None,
+            system: SystemId(0),
             location: Point::origin(),
             state: PlayerState::InSystem,
+            standing: Standing::default(),
         }
     }
 }
@@ -185,6 +315,74 @@ pub enum PlayerState {
     Docked(usize),
     Traveling {
         start: DateTime<Utc>,
-        route: Vec<Point>,
+        route: Vec<(SystemId, Point)>,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        planet::{PlanetBuilder, PlanetEconomy, PlanetType},
+        star::{Star, StarType},
+        system::{SystemBuilder, SystemState},
+    };
+    use economy::Commodity;
+    use entities::{standing::Rank, Faction};
+    use resources::ShipResource;
+    use ship::Shipyard;
+
+    fn system(security: SystemSecurity) -> System {
+        let planet = PlanetBuilder::default()
+            .name(String::from("Test"))
+            .mass(1.)
+            .gravity(1.)
+            .orbit_distance(1000.)
+            .surface_temperature(290.)
+            .planet_type(PlanetType::Earth)
+            .economic_type(PlanetEconomy::Agriculture)
+            .build()
+            .unwrap();
+        SystemBuilder::default()
+            .id(SystemId(0))
+            .location(Point::origin())
+            .name(String::from("Test"))
+            .faction(Faction::new("Federation"))
+            .security(security)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![planet])
+            .build()
+            .unwrap()
+    }
+
+    fn smuggler(system: &System) -> Player {
+        let mut shipyard = Shipyard::new();
+        shipyard.add_ships(&fetch_resource::<ShipResource>().unwrap());
+        let mut ship = shipyard.create_base_ship();
+        assert!(ship.load(&Commodity::new("IllegalDrug"), 2));
+        assert!(ship.load(&Commodity::new("Food"), 1));
+        Player::new(1000, ship, system)
+    }
+
+    #[test]
+    fn test_smuggling_bust() {
+        // Lawless systems do not check cargo.
+        let lawless = system(SystemSecurity::Anarchy);
+        let mut player = smuggler(&lawless);
+        assert_eq!(player.dock(0, &lawless), Docking::Docked);
+        assert_eq!(player.ship().as_ref().unwrap().cargo_used(), 3);
+
+        let patrolled = system(SystemSecurity::High);
+        let mut player = smuggler(&patrolled);
+        assert_eq!(player.dock(1, &patrolled), Docking::Refused);
+        assert_eq!(player.dock(0, &patrolled), Docking::Busted(2));
+        assert_eq!(player.ship().as_ref().unwrap().cargo_used(), 1);
+        assert_eq!(
+            player.standing().system(patrolled.id).rank(),
+            Rank::Unfriendly
+        );
+        // Nothing left to find.
+        assert_eq!(player.dock(0, &patrolled), Docking::Docked);
+    }
+}
@@ -1,87 +1,420 @@
-use serde::de::Deserialize;
-use serde_json;
-use std::{collections::HashMap, str};
+use bincode;
+use rand::Rng;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{self, Value};
+use statrs::distribution::{Categorical, Distribution};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
 
-use astronomicals::planet::PlanetEconomy;
-use economy::Commodity;
 use entities::Faction;
-use ship::ShipCharacteristics;
+use mods::{layer, mods};
+use scenario::Scenario;
 
-/// Generic Resource trait to be implemented by all resource types which should
-/// be loaded at compile time.
+/// Types of the bundled resources and the constraints they are checked
+/// against. The build script includes this module as well, so it may only
+/// depend on the standard library and serde.
+pub mod types;
+
+pub use self::types::{
+    AgentResource, AstronomicalNamesResource, CommodityResource, FactionResource,
+    NameStyleResource, Problem, ScenarioResource, ShipResource,
+};
+
+/// Generic Resource trait to be implemented by all resource types which are
+/// bundled with the game.
 /// KEY must be unique to the specific resource (e.g the filename of the
 /// resource).
-pub trait Resource: Deserialize<'static> {
+pub trait Resource: Serialize + DeserializeOwned + Send + Sync + 'static {
     const KEY: &'static str;
+
+    /// Checks the semantic constraints of the resource, returning all
+    /// problems found.
+    fn validate(&self) -> Vec<Problem> {
+        vec![]
+    }
+}
+
+/// Error returned when a resource fails to load, listing all problems found
+/// in the given file.
+#[derive(Debug, Clone)]
+pub struct ResourceError {
+    pub file: String,
+    pub problems: Vec<Problem>,
+}
+
+impl ResourceError {
+    /// Creates an error from a file which is not valid JSON.
+    pub fn syntax(file: String, error: &serde_json::Error) -> Self {
+        ResourceError {
+            file,
+            problems: vec![Problem::new(
+                format!("line {}, column {}", error.line(), error.column()),
+                error.to_string(),
+            )],
+        }
+    }
+
+    /// Creates an error from a failed deserialization of the given file,
+    /// located by the path of the offending field.
+    fn parse(file: String, error: &serde_path_to_error::Error<serde_json::Error>) -> Self {
+        ResourceError {
+            file,
+            problems: vec![Problem::new(
+                error.path().to_string(),
+                error.inner().to_string(),
+            )],
+        }
+    }
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid resource {}:", self.file)?;
+        for problem in &self.problems {
+            write!(f, "\n  {}: {}", problem.path, problem.message)?;
+        }
+        Ok(())
+    }
 }
 
 lazy_static! {
-    // Load resources at compile time.
-    // TODO: Convert to resource at compile time to save resources.
-    static ref RESOURCES: HashMap<&'static str, &'static str> = {
+    // Resources are validated and converted to bincode at build time, see
+    // build.rs.
+    static ref RESOURCES: HashMap<&'static str, &'static [u8]> = {
         let mut res = HashMap::new();
         res.insert(
             AstronomicalNamesResource::KEY,
-            include_str!("../res/astronomical_names.json"),
+            &include_bytes!(concat!(env!("OUT_DIR"), "/astronomical_names.bin"))[..],
         );
         res.insert(
             ShipResource::KEY,
-            include_str!("../res/ships.json"),
+            &include_bytes!(concat!(env!("OUT_DIR"), "/ships.bin"))[..],
         );
         res.insert(
             AgentResource::KEY,
-            include_str!("../res/economic_agents.json"),
+            &include_bytes!(concat!(env!("OUT_DIR"), "/economic_agents.bin"))[..],
+        );
+        res.insert(
+            CommodityResource::KEY,
+            &include_bytes!(concat!(env!("OUT_DIR"), "/commodities.bin"))[..],
+        );
+        res.insert(
+            NameStyleResource::KEY,
+            &include_bytes!(concat!(env!("OUT_DIR"), "/name_styles.bin"))[..],
+        );
+        res.insert(
+            FactionResource::KEY,
+            &include_bytes!(concat!(env!("OUT_DIR"), "/factions.bin"))[..],
+        );
+        res.insert(
+            ScenarioResource::KEY,
+            &include_bytes!(concat!(env!("OUT_DIR"), "/scenarios.bin"))[..],
         );
         res
     };
+
+    /// Loaded resources shared between all users, by key.
+    static ref CACHE: Mutex<HashMap<&'static str, Arc<dyn Any + Send + Sync>>> =
+        Mutex::new(HashMap::new());
+
+    /// Errors of mod resources which were skipped while layering.
+    static ref MOD_ERRORS: Mutex<Vec<ResourceError>> = Mutex::new(vec![]);
 }
 
-/// Attempts to returns the resource with the given type, will return None
-/// if the type has no resource or if the deserialization fails.
-pub fn fetch_resource<T: Resource>() -> Option<T> {
-    let res_str = RESOURCES.get(T::KEY).unwrap();
-    match serde_json::from_str(res_str) {
-        Ok(res) => Some(res),
-        Err(msg) => {
-            error!("{}", msg);
-            None
-        }
+/// Attempts to returns the resource with the given type, with the active mods
+/// layered on top. Fails if the built-in resource does not exist, cannot be
+/// deserialized or breaks the constraints of the type.
+/// Resources are only loaded once and then shared.
+pub fn fetch_resource<T: Resource>() -> Result<Arc<T>, ResourceError> {
+    let cached = CACHE.lock().unwrap().get(T::KEY).cloned();
+    if let Some(resource) = cached.and_then(|resource| resource.downcast::<T>().ok()) {
+        return Ok(resource);
+    }
+
+    // The lock is not held while loading since validation may fetch other
+    // resources.
+    let resource = Arc::new(layer_mods::<T>()?);
+    CACHE.lock().unwrap().insert(T::KEY, resource.clone());
+    Ok(resource)
+}
+
+/// Validates all resources with the active mods layered on top, returning
+/// the errors of the resources which failed.
+pub fn validate_resources() -> Vec<ResourceError> {
+    vec![
+        fetch_resource::<CommodityResource>().err(),
+        fetch_resource::<FactionResource>().err(),
+        fetch_resource::<AgentResource>().err(),
+        fetch_resource::<ShipResource>().err(),
+        fetch_resource::<AstronomicalNamesResource>().err(),
+        fetch_resource::<NameStyleResource>().err(),
+        fetch_resource::<ScenarioResource>().err(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Returns the errors of mod resources which were skipped because they could
+/// not be read or would have made the resource invalid.
+pub fn mod_errors() -> Vec<ResourceError> {
+    mods()
+        .iter()
+        .flat_map(|active_mod| active_mod.errors().iter().cloned())
+        .chain(MOD_ERRORS.lock().unwrap().iter().cloned())
+        .collect()
+}
+
+fn file_name<T: Resource>() -> String {
+    format!("{}.json", T::KEY)
+}
+
+/// Deserializes and validates the given value as a resource of the given
+/// type, attributing errors to the given file.
+fn check<T: Resource>(value: &Value, file: String) -> Result<T, ResourceError> {
+    let resource = serde_path_to_error::deserialize::<_, T>(value.clone())
+        .map_err(|msg| ResourceError::parse(file.clone(), &msg))?;
+    validated(resource, file)
+}
+
+/// Returns the resource if it breaks none of the constraints of its type.
+fn validated<T: Resource>(resource: T, file: String) -> Result<T, ResourceError> {
+    let problems = resource.validate();
+    if problems.is_empty() {
+        Ok(resource)
+    } else {
+        Err(ResourceError { file, problems })
     }
 }
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
-/// Resource used in name generation of celestial objects.
-pub struct AstronomicalNamesResource {
-    pub names: Vec<String>,
-    pub scientific_names: Vec<String>,
-    pub greek: Vec<String>,
-    pub roman: Vec<String>,
-    pub decorators: Vec<String>,
+/// Layers the active mods in load order on top of the built-in resource with
+/// the given type. Mods which would make the resource invalid are skipped.
+/// Built-in resources are validated at build time, they are only checked
+/// again when mods are active since mods may break references between them.
+fn layer_mods<T: Resource>() -> Result<T, ResourceError> {
+    let built_in_error = |message: String| ResourceError {
+        file: file_name::<T>(),
+        problems: vec![Problem::new("-", message)],
+    };
+    let bytes = RESOURCES
+        .get(T::KEY)
+        .ok_or_else(|| built_in_error(String::from("no such built-in resource")))?;
+    let mut resource: T =
+        bincode::deserialize(bytes).map_err(|msg| built_in_error(msg.to_string()))?;
+    if mods().is_empty() {
+        return Ok(resource);
+    }
+    resource = validated(resource, file_name::<T>())?;
+
+    // Mods are layered on the JSON form of the resource, only built once a
+    // mod touches it.
+    let mut value: Option<Value> = None;
+    for active_mod in mods() {
+        if let Some(mod_resource) = active_mod.resource(T::KEY) {
+            let mut layered = match value {
+                Some(ref value) => value.clone(),
+                None => serde_json::to_value(&resource)
+                    .map_err(|msg| built_in_error(msg.to_string()))?,
+            };
+            layer(&mut layered, mod_resource);
+            let file = format!("mods/{}/{}", active_mod.name, file_name::<T>());
+            match check::<T>(&layered, file) {
+                Ok(layered_resource) => {
+                    value = Some(layered);
+                    resource = layered_resource;
+                }
+                Err(error) => {
+                    error!("Skipping mod {}. {}", active_mod.name, error);
+                    MOD_ERRORS.lock().unwrap().push(error);
+                }
+            }
+        }
+    }
+    Ok(resource)
 }
 
 impl Resource for AstronomicalNamesResource {
     const KEY: &'static str = "astronomical_names";
+
+    fn validate(&self) -> Vec<Problem> {
+        self.problems()
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-/// Resource with all ships available in the game.
-pub struct ShipResource {
-    pub ships: Vec<ShipCharacteristics>,
+impl Resource for NameStyleResource {
+    const KEY: &'static str = "name_styles";
+
+    fn validate(&self) -> Vec<Problem> {
+        self.problems()
+    }
 }
 
 impl Resource for ShipResource {
     const KEY: &'static str = "ships";
-}
 
-#[derive(Serialize, Deserialize, Debug)]
-/// Resource containing all production/consumptions for factions and planets.
-pub struct AgentResource {
-    pub faction_ideals: HashMap<Faction, HashMap<Commodity, u64>>,
-    pub faction_production: HashMap<Faction, HashMap<Commodity, u64>>,
-    pub planet_ideals: HashMap<PlanetEconomy, HashMap<Commodity, u64>>,
-    pub planet_production: HashMap<PlanetEconomy, HashMap<Commodity, u64>>,
+    fn validate(&self) -> Vec<Problem> {
+        let factions = fetch_resource::<FactionResource>().ok();
+        self.problems(factions.as_deref())
+    }
 }
 
 impl Resource for AgentResource {
     const KEY: &'static str = "economic_agents";
+
+    fn validate(&self) -> Vec<Problem> {
+        let factions = fetch_resource::<FactionResource>().ok();
+        let catalogue = fetch_resource::<CommodityResource>().ok();
+        self.problems(factions.as_deref(), catalogue.as_deref())
+    }
+}
+
+impl FactionResource {
+    /// Generate a random faction according to the spawn weights.
+    pub fn random_faction<R: Rng>(&self, gen: &mut R) -> Faction {
+        let weights = self
+            .factions
+            .iter()
+            .map(|definition| definition.weight)
+            .collect::<Vec<_>>();
+        let probs = Categorical::new(&weights).unwrap();
+        self.factions[probs.sample::<R>(gen) as usize].id.clone()
+    }
+}
+
+impl Resource for FactionResource {
+    const KEY: &'static str = "factions";
+
+    fn validate(&self) -> Vec<Problem> {
+        let catalogue = fetch_resource::<CommodityResource>().ok();
+        self.problems(catalogue.as_deref())
+    }
+}
+
+impl Resource for CommodityResource {
+    const KEY: &'static str = "commodities";
+
+    fn validate(&self) -> Vec<Problem> {
+        self.problems()
+    }
+}
+
+impl ScenarioResource {
+    /// Returns the scenario with the given name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&Scenario> {
+        self.scenarios
+            .iter()
+            .find(|scenario| scenario.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Resource for ScenarioResource {
+    const KEY: &'static str = "scenarios";
+
+    fn validate(&self) -> Vec<Problem> {
+        let ships = fetch_resource::<ShipResource>().ok();
+        let factions = fetch_resource::<FactionResource>().ok();
+        let catalogue = fetch_resource::<CommodityResource>().ok();
+        self.problems(ships.as_deref(), factions.as_deref(), catalogue.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use economy::Commodity;
+    use scenario::StartLocation;
+
+    #[test]
+    fn test_built_in_resources_valid() {
+        assert!(validate_resources().is_empty());
+    }
+
+    #[test]
+    fn test_problems_have_paths() {
+        let mut ships = (*fetch_resource::<ShipResource>().unwrap()).clone();
+        ships.ships[1].range = 0.;
+        ships.ships[2].name = ships.ships[0].name.clone();
+        assert_eq!(
+            ships.validate(),
+            vec![
+                Problem::new(
+                    "ships[2]",
+                    format!("duplicate entry {}", ships.ships[0].name)
+                ),
+                Problem::new("ships[1].range", "must be greater than 0"),
+            ]
+        );
+        assert_eq!(
+            ShipResource { ships: vec![] }.validate(),
+            vec![Problem::new("ships", "must not be empty")]
+        );
+    }
+
+    #[test]
+    fn test_scenario_problems() {
+        let valid = fetch_resource::<ScenarioResource>().unwrap();
+        assert!(valid.get("smuggler").is_some());
+
+        let mut scenarios = (*valid).clone();
+        scenarios.scenarios[1].name = scenarios.scenarios[0].name.clone();
+        scenarios.scenarios[0]
+            .cargo
+            .insert(Commodity::new("Unobtainium"), 1);
+        scenarios.scenarios[1]
+            .cargo
+            .insert(Commodity::new("Food"), 10_000);
+        scenarios.scenarios[2].ship = Some(String::from("Flying Dutchman"));
+        scenarios.scenarios[3].location = StartLocation::Random {
+            faction: Some(Faction::new("Aliens")),
+            security: None,
+            economy: None,
+        };
+
+        let paths = scenarios
+            .validate()
+            .into_iter()
+            .map(|problem| problem.path)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "scenarios[1]",
+                "scenarios[0].cargo.Unobtainium",
+                "scenarios[1].cargo",
+                "scenarios[2].ship",
+                "scenarios[3].location.faction",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors_have_paths() {
+        let mut value = serde_json::to_value(&*fetch_resource::<ShipResource>().unwrap()).unwrap();
+        value["ships"][3]["range"] = Value::from("far");
+        let error = check::<ShipResource>(&value, file_name::<ShipResource>()).unwrap_err();
+        assert_eq!(error.file, "ships.json");
+        assert_eq!(error.problems.len(), 1);
+        assert_eq!(error.problems[0].path, "ships[3].range");
+    }
+
+    fn check_round_trip<T: Resource>() {
+        let value = serde_json::to_value(&*fetch_resource::<T>().unwrap()).unwrap();
+        assert!(check::<T>(&value, file_name::<T>()).is_ok());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        // Mods are layered on the JSON form of the built-in resources.
+        check_round_trip::<AstronomicalNamesResource>();
+        check_round_trip::<NameStyleResource>();
+        check_round_trip::<ShipResource>();
+        check_round_trip::<AgentResource>();
+        check_round_trip::<FactionResource>();
+        check_round_trip::<CommodityResource>();
+        check_round_trip::<ScenarioResource>();
+    }
 }
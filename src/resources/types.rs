@@ -0,0 +1,711 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    slice::Iter,
+};
+
+/// A single problem found in a resource, located by its field path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    pub path: String,
+    pub message: String,
+}
+
+impl Problem {
+    pub fn new<P: Into<String>, M: Into<String>>(path: P, message: M) -> Self {
+        Problem {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Represents a single Faction which is assigned on Sector level, identified
+/// by the id used in the faction resource.
+#[derive(Serialize, Deserialize, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Faction(String);
+
+impl Faction {
+    /// Creates a faction with the given id.
+    pub fn new(id: &str) -> Faction {
+        Faction(String::from(id))
+    }
+
+    /// Returns the id of the faction.
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Faction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Definition of a faction as described in the faction resource.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FactionDefinition {
+    pub id: Faction,
+    pub name: String,
+    pub color: String,
+    /// Relative probability of the faction being assigned to a sector.
+    pub weight: f64,
+    /// Relative probability of each security level in the faction's systems.
+    pub security: HashMap<SystemSecurity, f64>,
+    /// Commodities which may not be traded in the faction's systems.
+    #[serde(default)]
+    pub illegal: Vec<Commodity>,
+    /// Commodities which may be traded even if illegal by default.
+    #[serde(default)]
+    pub legal: Vec<Commodity>,
+    /// Initial relations with other factions.
+    #[serde(default)]
+    pub relations: HashMap<Faction, i32>,
+    /// Consumption ideals, overriding those of the agent resource.
+    #[serde(default)]
+    pub ideals: HashMap<Commodity, u64>,
+    /// Production, overriding that of the agent resource.
+    #[serde(default)]
+    pub production: HashMap<Commodity, u64>,
+}
+
+/// A tradable and possibly producable commodity, identified by the id used in
+/// the commodity resource.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Commodity(String);
+
+impl Commodity {
+    /// Creates a commodity with the given id, resources name commodities
+    /// through deserialization instead.
+    #[cfg(test)]
+    pub fn new(id: &str) -> Commodity {
+        Commodity(String::from(id))
+    }
+
+    /// Returns the id of the commodity.
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Definition of a commodity as described in the commodity resource.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CommodityDefinition {
+    pub id: Commodity,
+    pub name: String,
+    pub category: String,
+    /// Price markets start out at before any trading has taken place.
+    pub base_price: u64,
+    /// Mass in tonnes of a single unit.
+    pub mass: f64,
+    /// Whether the commodity may be traded unless a faction says otherwise.
+    #[serde(default = "default_legal")]
+    pub legal: bool,
+    /// Fraction of the stock which spoils each time step.
+    #[serde(default)]
+    pub perishability: f64,
+}
+
+fn default_legal() -> bool {
+    true
+}
+
+/// Represents the different security levels a system is in at a given point.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SystemSecurity {
+    Anarchy,
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+/// Different types of planet economies, depends on the planet type.
+pub enum PlanetEconomy {
+    Agriculture,
+    Extraction,
+    HighTech,
+    Industrial,
+    None,
+    Military,
+    Refinary,
+}
+
+impl PlanetEconomy {
+    pub fn values() -> Iter<'static, PlanetEconomy> {
+        static ECONOMIES: [PlanetEconomy; 7] = [
+            PlanetEconomy::Agriculture,
+            PlanetEconomy::Extraction,
+            PlanetEconomy::HighTech,
+            PlanetEconomy::Industrial,
+            PlanetEconomy::None,
+            PlanetEconomy::Military,
+            PlanetEconomy::Refinary,
+        ];
+        ECONOMIES.iter()
+    }
+}
+
+/// Ranks of standing, ordered from worst to best.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rank {
+    Hostile,
+    Unfriendly,
+    Neutral,
+    Friendly,
+    Allied,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Represents the characteristics of a given ship model.
+pub struct ShipCharacteristics {
+    pub name: String,
+    pub manufacturer: String,
+    pub faction: Option<Faction>,
+    /// Minimum rank with the faction required to buy the ship.
+    #[serde(default)]
+    pub min_rank: Option<Rank>,
+    pub kind: ShipType,
+    pub description: String,
+    pub integrity: u32,
+    pub size: Dimensions,
+    pub mass: u32,
+    pub slots: u32,
+    pub cost: u32,
+    pub range: f64,
+    pub fuel: u32,
+    pub cargo: u32,
+    pub detectability: u32,
+    pub maneuverability: u32,
+    pub defense: u32,
+    pub shield: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub enum ShipType {
+    Assault,
+    Corvette,
+    Freighter,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// The size of a ship.
+pub struct Dimensions {
+    length: f64,
+    width: f64,
+    height: f64,
+}
+
+impl fmt::Display for Dimensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}L, {}W, {}H", self.length, self.width, self.height)
+    }
+}
+
+/// Where the player starts a new game. Tagged by kind in human readable
+/// formats, and by variant in bincode which can not read tagged enums.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(remote = "Self", tag = "kind")]
+pub enum StartLocation {
+    /// The system nearest the center of the galaxy.
+    #[default]
+    Center,
+    /// The system nearest the given point.
+    Nearest { x: f64, y: f64 },
+    /// The system with the given name, which depends on the map seed.
+    System { name: String },
+    /// A random system matching all of the given constraints.
+    Random {
+        #[serde(default)]
+        faction: Option<Faction>,
+        #[serde(default)]
+        security: Option<SystemSecurity>,
+        /// Economy of at least one of the planets in the system.
+        #[serde(default)]
+        economy: Option<PlanetEconomy>,
+    },
+}
+
+/// Start location as stored in binary formats.
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "StartLocation")]
+enum BinaryStartLocation {
+    Center,
+    Nearest {
+        x: f64,
+        y: f64,
+    },
+    System {
+        name: String,
+    },
+    Random {
+        faction: Option<Faction>,
+        security: Option<SystemSecurity>,
+        economy: Option<PlanetEconomy>,
+    },
+}
+
+impl Serialize for StartLocation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            StartLocation::serialize(self, serializer)
+        } else {
+            BinaryStartLocation::serialize(self, serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for StartLocation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            StartLocation::deserialize(deserializer)
+        } else {
+            BinaryStartLocation::deserialize(deserializer)
+        }
+    }
+}
+
+/// Predefined start of a new game, as described in the scenario resource.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Scenario {
+    pub name: String,
+    pub description: String,
+    /// Map seed overriding the config, needed when starting in a named system.
+    #[serde(default)]
+    pub seed: Option<u32>,
+    /// Starting credits, those of the config unless given.
+    #[serde(default)]
+    pub credits: Option<u32>,
+    /// Name of the starting ship model, the base ship unless given.
+    #[serde(default)]
+    pub ship: Option<String>,
+    /// Units of each commodity in the cargo hold of the starting ship.
+    #[serde(default)]
+    pub cargo: HashMap<Commodity, u32>,
+    #[serde(default)]
+    pub location: StartLocation,
+}
+
+/// Checks that the given value is a finite number above the given minimum.
+fn check_above(problems: &mut Vec<Problem>, path: String, value: f64, min: f64) {
+    if !value.is_finite() || value <= min {
+        problems.push(Problem::new(path, format!("must be greater than {}", min)));
+    }
+}
+
+/// Checks that the given ids are unique, reporting duplicates at the given
+/// list path.
+fn check_unique<'a, I: Iterator<Item = &'a str>>(problems: &mut Vec<Problem>, path: &str, ids: I) {
+    let mut seen = HashSet::new();
+    for (index, id) in ids.enumerate() {
+        if !seen.insert(id) {
+            problems.push(Problem::new(
+                format!("{}[{}]", path, index),
+                format!("duplicate entry {}", id),
+            ));
+        }
+    }
+}
+
+/// Checks that the given commodities are in the catalogue, if it is valid.
+fn check_commodities<'a, I>(
+    problems: &mut Vec<Problem>,
+    path: &str,
+    commodities: I,
+    catalogue: Option<&CommodityResource>,
+) where
+    I: Iterator<Item = &'a Commodity>,
+{
+    if let Some(catalogue) = catalogue {
+        for commodity in commodities {
+            if !catalogue
+                .commodities
+                .iter()
+                .any(|definition| &definition.id == commodity)
+            {
+                problems.push(Problem::new(
+                    format!("{}.{}", path, commodity.id()),
+                    "unknown commodity",
+                ));
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+/// Resource used in name generation of celestial objects.
+pub struct AstronomicalNamesResource {
+    pub scientific_names: Vec<String>,
+    pub greek: Vec<String>,
+    pub roman: Vec<String>,
+    pub decorators: Vec<String>,
+}
+
+impl AstronomicalNamesResource {
+    /// Returns all problems breaking the constraints of the resource.
+    pub fn problems(&self) -> Vec<Problem> {
+        [
+            ("scientific_names", &self.scientific_names),
+            ("greek", &self.greek),
+            ("roman", &self.roman),
+            ("decorators", &self.decorators),
+        ]
+        .iter()
+        .filter(|&&(_, names)| names.is_empty())
+        .map(|&(path, _)| Problem::new(path, "must not be empty"))
+        .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Resource with the name corpora used to train the name generator, and which
+/// corpora make up the naming style of each faction.
+pub struct NameStyleResource {
+    pub styles: HashMap<Faction, Vec<String>>,
+    /// Style used for factions without a style of their own.
+    pub default_style: Vec<String>,
+    /// Names by corpus, filled in from `utils/*.txt` at build time.
+    pub corpora: HashMap<String, Vec<String>>,
+}
+
+impl NameStyleResource {
+    /// Returns all problems breaking the constraints of the resource.
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+        if self.default_style.is_empty() {
+            problems.push(Problem::new("default_style", "must not be empty"));
+        }
+        let styles = self
+            .styles
+            .iter()
+            .map(|(faction, corpora)| (format!("styles.{}", faction), corpora))
+            .chain(Some((String::from("default_style"), &self.default_style)));
+        for (path, corpora) in styles {
+            for (index, corpus) in corpora.iter().enumerate() {
+                match self.corpora.get(corpus) {
+                    Some(names) if !names.is_empty() => {}
+                    Some(_) => problems.push(Problem::new(
+                        format!("corpora.{}", corpus),
+                        "must not be empty",
+                    )),
+                    None => problems.push(Problem::new(
+                        format!("{}[{}]", path, index),
+                        format!("unknown corpus {}", corpus),
+                    )),
+                }
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Resource with all ships available in the game.
+pub struct ShipResource {
+    pub ships: Vec<ShipCharacteristics>,
+}
+
+impl ShipResource {
+    /// Returns all problems breaking the constraints of the resource, checking
+    /// factions against the given faction resource if it is valid.
+    pub fn problems(&self, factions: Option<&FactionResource>) -> Vec<Problem> {
+        let mut problems = vec![];
+        if self.ships.is_empty() {
+            problems.push(Problem::new("ships", "must not be empty"));
+        }
+        check_unique(
+            &mut problems,
+            "ships",
+            self.ships.iter().map(|ship| ship.name.as_str()),
+        );
+
+        for (index, ship) in self.ships.iter().enumerate() {
+            let path = |field: &str| format!("ships[{}].{}", index, field);
+            check_above(&mut problems, path("range"), ship.range, 0.);
+            check_above(&mut problems, path("fuel"), f64::from(ship.fuel), 0.);
+            check_above(&mut problems, path("mass"), f64::from(ship.mass), 0.);
+            if let (Some(faction), Some(factions)) = (ship.faction.as_ref(), factions) {
+                if factions.get(faction).is_none() {
+                    problems.push(Problem::new(path("faction"), "unknown faction"));
+                }
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+/// Resource containing all production/consumptions for factions and planets.
+pub struct AgentResource {
+    pub faction_ideals: HashMap<Faction, HashMap<Commodity, u64>>,
+    pub faction_production: HashMap<Faction, HashMap<Commodity, u64>>,
+    pub planet_ideals: HashMap<PlanetEconomy, HashMap<Commodity, u64>>,
+    pub planet_production: HashMap<PlanetEconomy, HashMap<Commodity, u64>>,
+}
+
+impl AgentResource {
+    /// Returns all problems breaking the constraints of the resource, checking
+    /// against the given faction and commodity resources if they are valid.
+    pub fn problems(
+        &self,
+        factions: Option<&FactionResource>,
+        catalogue: Option<&CommodityResource>,
+    ) -> Vec<Problem> {
+        let mut problems = vec![];
+
+        // Factions defining their own ideals and production need no entry.
+        if let Some(factions) = factions {
+            for definition in &factions.factions {
+                if definition.ideals.is_empty() && !self.faction_ideals.contains_key(&definition.id)
+                {
+                    problems.push(Problem::new(
+                        format!("faction_ideals.{}", definition.id),
+                        "missing faction",
+                    ));
+                }
+                if definition.production.is_empty()
+                    && !self.faction_production.contains_key(&definition.id)
+                {
+                    problems.push(Problem::new(
+                        format!("faction_production.{}", definition.id),
+                        "missing faction",
+                    ));
+                }
+            }
+        }
+        for economy in PlanetEconomy::values() {
+            if !self.planet_ideals.contains_key(economy) {
+                problems.push(Problem::new(
+                    format!("planet_ideals.{:?}", economy),
+                    "missing planet economy",
+                ));
+            }
+            if !self.planet_production.contains_key(economy) {
+                problems.push(Problem::new(
+                    format!("planet_production.{:?}", economy),
+                    "missing planet economy",
+                ));
+            }
+        }
+
+        for (field, table) in &[
+            ("faction_ideals", &self.faction_ideals),
+            ("faction_production", &self.faction_production),
+        ] {
+            for (faction, amounts) in table.iter() {
+                check_commodities(
+                    &mut problems,
+                    &format!("{}.{}", field, faction),
+                    amounts.keys(),
+                    catalogue,
+                );
+            }
+        }
+        for (field, table) in &[
+            ("planet_ideals", &self.planet_ideals),
+            ("planet_production", &self.planet_production),
+        ] {
+            for (economy, amounts) in table.iter() {
+                check_commodities(
+                    &mut problems,
+                    &format!("{}.{:?}", field, economy),
+                    amounts.keys(),
+                    catalogue,
+                );
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Resource with the definitions of all factions in the game.
+pub struct FactionResource {
+    pub factions: Vec<FactionDefinition>,
+}
+
+impl FactionResource {
+    /// Returns the definition of the given faction.
+    pub fn get(&self, faction: &Faction) -> Option<&FactionDefinition> {
+        self.factions
+            .iter()
+            .find(|definition| &definition.id == faction)
+    }
+
+    /// Returns all problems breaking the constraints of the resource, checking
+    /// commodities against the given catalogue if it is valid.
+    pub fn problems(&self, catalogue: Option<&CommodityResource>) -> Vec<Problem> {
+        let mut problems = vec![];
+        if self.factions.is_empty() {
+            problems.push(Problem::new("factions", "must not be empty"));
+        }
+        check_unique(
+            &mut problems,
+            "factions",
+            self.factions.iter().map(|definition| definition.id.id()),
+        );
+
+        for (index, definition) in self.factions.iter().enumerate() {
+            let path = |field: &str| format!("factions[{}].{}", index, field);
+            check_above(&mut problems, path("weight"), definition.weight, 0.);
+            if definition.security.values().any(|&weight| weight < 0.)
+                || definition.security.values().sum::<f64>() <= 0.
+            {
+                problems.push(Problem::new(
+                    path("security"),
+                    "weights must be non-negative and not all zero",
+                ));
+            }
+            for other in definition.relations.keys() {
+                if self.get(other).is_none() {
+                    problems.push(Problem::new(
+                        format!("{}.{}", path("relations"), other),
+                        "unknown faction",
+                    ));
+                }
+            }
+            check_commodities(
+                &mut problems,
+                &path("illegal"),
+                definition.illegal.iter(),
+                catalogue,
+            );
+            check_commodities(
+                &mut problems,
+                &path("legal"),
+                definition.legal.iter(),
+                catalogue,
+            );
+            check_commodities(
+                &mut problems,
+                &path("ideals"),
+                definition.ideals.keys(),
+                catalogue,
+            );
+            check_commodities(
+                &mut problems,
+                &path("production"),
+                definition.production.keys(),
+                catalogue,
+            );
+        }
+        problems
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Resource with the catalogue of all tradable commodities.
+pub struct CommodityResource {
+    pub commodities: Vec<CommodityDefinition>,
+}
+
+impl CommodityResource {
+    /// Returns all problems breaking the constraints of the resource.
+    pub fn problems(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+        if self.commodities.is_empty() {
+            problems.push(Problem::new("commodities", "must not be empty"));
+        }
+        check_unique(
+            &mut problems,
+            "commodities",
+            self.commodities.iter().map(|definition| definition.id.id()),
+        );
+
+        for (index, definition) in self.commodities.iter().enumerate() {
+            let path = |field: &str| format!("commodities[{}].{}", index, field);
+            check_above(
+                &mut problems,
+                path("base_price"),
+                definition.base_price as f64,
+                0.,
+            );
+            if !definition.mass.is_finite() || definition.mass < 0. {
+                problems.push(Problem::new(path("mass"), "must not be negative"));
+            }
+            if !(definition.perishability >= 0. && definition.perishability <= 1.) {
+                problems.push(Problem::new(
+                    path("perishability"),
+                    "must be between 0 and 1",
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+/// Resource with the predefined scenarios a new game can start from.
+pub struct ScenarioResource {
+    pub scenarios: Vec<Scenario>,
+}
+
+impl ScenarioResource {
+    /// Returns all problems breaking the constraints of the resource, checking
+    /// against the given ship, faction and commodity resources if they are
+    /// valid.
+    pub fn problems(
+        &self,
+        ships: Option<&ShipResource>,
+        factions: Option<&FactionResource>,
+        catalogue: Option<&CommodityResource>,
+    ) -> Vec<Problem> {
+        let mut problems = vec![];
+        check_unique(
+            &mut problems,
+            "scenarios",
+            self.scenarios.iter().map(|scenario| scenario.name.as_str()),
+        );
+
+        for (index, scenario) in self.scenarios.iter().enumerate() {
+            let path = |field: &str| format!("scenarios[{}].{}", index, field);
+            check_commodities(
+                &mut problems,
+                &path("cargo"),
+                scenario.cargo.keys(),
+                catalogue,
+            );
+
+            // The cargo has to fit in the starting ship, the base ship unless
+            // given.
+            if let Some(ships) = ships {
+                let ship = match scenario.ship {
+                    Some(ref name) => ships.ships.iter().find(|ship| &ship.name == name),
+                    None => ships.ships.first(),
+                };
+                match ship {
+                    Some(ship) if scenario.cargo.values().sum::<u32>() > ship.cargo => problems
+                        .push(Problem::new(
+                            path("cargo"),
+                            format!("exceeds the capacity of the {}", ship.name),
+                        )),
+                    Some(_) => {}
+                    None => problems.push(Problem::new(path("ship"), "unknown ship")),
+                }
+            }
+
+            match scenario.location {
+                StartLocation::Nearest { x, y } if !x.is_finite() || !y.is_finite() => {
+                    problems.push(Problem::new(path("location"), "must be finite"))
+                }
+                StartLocation::Random {
+                    faction: Some(ref faction),
+                    ..
+                } => {
+                    if let Some(factions) = factions {
+                        if factions.get(faction).is_none() {
+                            problems.push(Problem::new(
+                                format!("{}.faction", path("location")),
+                                "unknown faction",
+                            ));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        problems
+    }
+}
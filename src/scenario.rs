@@ -0,0 +1,151 @@
+use rand::Rng;
+
+use astronomicals::{system::System, Galaxy};
+use utils::Point;
+
+pub use resources::types::{Scenario, StartLocation};
+
+impl StartLocation {
+    /// Finds the starting system in the given galaxy, if any system matches.
+    pub fn find<'a, R: Rng>(&self, galaxy: &'a Galaxy, rng: &mut R) -> Option<&'a System> {
+        match *self {
+            StartLocation::Center => galaxy
+                .nearest(&Point::origin())
+                .and_then(|location| galaxy.system_at(location)),
+            StartLocation::Nearest { x, y } => galaxy
+                .nearest(&Point::new(x, y))
+                .and_then(|location| galaxy.system_at(location)),
+            StartLocation::System { ref name } => galaxy
+                .systems()
+                .find(|system| system.name.eq_ignore_ascii_case(name)),
+            StartLocation::Random {
+                ref faction,
+                ref security,
+                ref economy,
+            } => {
+                let mut candidates = galaxy
+                    .systems()
+                    .filter(|system| faction.as_ref().map_or(true, |f| *f == system.faction))
+                    .filter(|system| security.as_ref().map_or(true, |s| *s == system.security))
+                    .filter(|system| {
+                        economy.as_ref().map_or(true, |economy| {
+                            system
+                                .satelites
+                                .iter()
+                                .any(|planet| planet.economic_type == *economy)
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                // Systems are stored unordered, sort to pick deterministically.
+                candidates.sort_by_key(|system| system.id);
+                rng.choose(&candidates).cloned()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        planet::{PlanetBuilder, PlanetEconomy, PlanetType},
+        star::{Star, StarType},
+        system::{SystemBuilder, SystemId, SystemSecurity, SystemState},
+    };
+    use bincode;
+    use entities::Faction;
+    use rand::{ChaChaRng, SeedableRng};
+    use serde_json;
+
+    fn system(id: u32, x: f64, faction: &str, economy: PlanetEconomy) -> System {
+        let planet = PlanetBuilder::default()
+            .name(format!("Planet {}", id))
+            .mass(1.)
+            .gravity(1.)
+            .orbit_distance(500.)
+            .surface_temperature(288.)
+            .planet_type(PlanetType::Earth)
+            .economic_type(economy)
+            .build()
+            .unwrap();
+        SystemBuilder::default()
+            .id(SystemId(id))
+            .location(Point::new(x, 0.))
+            .name(format!("System {}", id))
+            .faction(Faction::new(faction))
+            .security(SystemSecurity::Medium)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![planet])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_find() {
+        let galaxy = Galaxy::new(
+            vec![],
+            vec![
+                system(0, -5., "Empire", PlanetEconomy::Military),
+                system(1, 1., "Federation", PlanetEconomy::Industrial),
+                system(2, 10., "Federation", PlanetEconomy::Agriculture),
+            ],
+        );
+        let seed: &[_] = &[1];
+        let mut rng = ChaChaRng::from_seed(seed);
+        let mut find =
+            |location: StartLocation| location.find(&galaxy, &mut rng).map(|system| system.id.0);
+
+        assert_eq!(find(StartLocation::Center), Some(1));
+        assert_eq!(find(StartLocation::Nearest { x: 8., y: 3. }), Some(2));
+        assert_eq!(
+            find(StartLocation::System {
+                name: String::from("system 0"),
+            }),
+            Some(0)
+        );
+        assert_eq!(
+            find(StartLocation::System {
+                name: String::from("Sol"),
+            }),
+            None
+        );
+        assert_eq!(
+            find(StartLocation::Random {
+                faction: Some(Faction::new("Federation")),
+                security: Some(SystemSecurity::Medium),
+                economy: Some(PlanetEconomy::Agriculture),
+            }),
+            Some(2)
+        );
+        assert_eq!(
+            find(StartLocation::Random {
+                faction: Some(Faction::new("Empire")),
+                security: None,
+                economy: Some(PlanetEconomy::Agriculture),
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_start_location_formats() {
+        let location = StartLocation::Random {
+            faction: Some(Faction::new("Federation")),
+            security: None,
+            economy: Some(PlanetEconomy::Agriculture),
+        };
+        let json = serde_json::to_value(&location).unwrap();
+        assert_eq!(json["kind"], "Random");
+        assert_eq!(
+            serde_json::from_value::<StartLocation>(json).unwrap(),
+            location
+        );
+
+        let bytes = bincode::serialize(&location).unwrap();
+        assert_eq!(
+            bincode::deserialize::<StartLocation>(&bytes).unwrap(),
+            location
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use astronomicals::System;
-use entities::Faction;
+use economy::Commodity;
+use entities::Standing;
 use resources::ShipResource;
-use std::fmt;
+use std::{collections::HashMap, fmt};
+
+pub use resources::types::{ShipCharacteristics, ShipType};
 
 /// Ship currently owned by the player.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -9,6 +12,9 @@ pub struct Ship {
     integrity: u32,
     fuel: u32,
     base: ShipCharacteristics,
+    /// Units of each commodity in the cargo hold.
+    #[serde(default)]
+    cargo: HashMap<Commodity, u32>,
 }
 
 impl Ship {
@@ -17,6 +23,7 @@ impl Ship {
             integrity: model.integrity,
             fuel: model.fuel,
             base: model,
+            cargo: HashMap::new(),
         }
     }
 
@@ -45,43 +52,54 @@ impl Ship {
         self.fuel += amount;
     }
 
+    /// Add the given amount to the integrity.
+    pub fn repair(&mut self, amount: u32) {
+        self.integrity += amount;
+    }
+
     /// Returns a reference to the ship's characteristics.
     pub fn characteristics(&self) -> &ShipCharacteristics {
         &self.base
     }
-}
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-/// Represents the characteristics of a given ship model.
-pub struct ShipCharacteristics {
-    pub name: String,
-    pub manufacturer: String,
-    pub faction: Option<Faction>,
-    pub kind: ShipType,
-    pub description: String,
-    pub integrity: u32,
-    pub size: Dimensions,
-    pub mass: u32,
-    pub slots: u32,
-    pub cost: u32,
-    pub range: f64,
-    pub fuel: u32,
-    pub cargo: u32,
-    pub detectability: u32,
-    pub maneuverability: u32,
-    pub defense: u32,
-    pub shield: u32,
-}
+    /// Returns the units of each commodity in the cargo hold.
+    pub fn cargo(&self) -> &HashMap<Commodity, u32> {
+        &self.cargo
+    }
 
-impl ShipCharacteristics {}
+    /// Returns the number of units in the cargo hold.
+    pub fn cargo_used(&self) -> u32 {
+        self.cargo.values().sum()
+    }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum ShipType {
-    Assault,
-    Corvette,
-    Freighter,
+    /// Loads the given amount of the commodity into the cargo hold, returning
+    /// false without loading anything if it does not fit.
+    pub fn load(&mut self, commodity: &Commodity, amount: u32) -> bool {
+        if self.cargo_used() + amount > self.base.cargo {
+            return false;
+        }
+        *self.cargo.entry(commodity.clone()).or_insert(0) += amount;
+        true
+    }
+
+    /// Unloads up to the given amount of the commodity from the cargo hold,
+    /// returning the amount unloaded.
+    pub fn unload(&mut self, commodity: &Commodity, amount: u32) -> u32 {
+        let unloaded = match self.cargo.get_mut(commodity) {
+            Some(stored) => {
+                let unloaded = amount.min(*stored);
+                *stored -= unloaded;
+                unloaded
+            }
+            None => 0,
+        };
+        self.cargo.retain(|_, &mut stored| stored > 0);
+        unloaded
+    }
 }
 
+impl ShipCharacteristics {}
+
 impl fmt::Display for ShipType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -92,20 +110,6 @@ impl fmt::Display for ShipType {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-/// The size of a ship.
-pub struct Dimensions {
-    length: f64,
-    width: f64,
-    height: f64,
-}
-
-impl fmt::Display for Dimensions {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}L, {}W, {}H", self.length, self.width, self.height)
-    }
-}
-
 /// Holds the different ships in the game.
 pub struct Shipyard {
     ships: Vec<ShipCharacteristics>,
@@ -118,22 +122,34 @@ impl Shipyard {
     }
 
     /// Extend shipyard with more ships.
-    pub fn add_ships(&mut self, resource: ShipResource) {
-        self.ships.extend(resource.ships);
+    pub fn add_ships(&mut self, resource: &ShipResource) {
+        self.ships.extend(resource.ships.iter().cloned());
     }
 
-    /// Get all available ships.
-    pub fn get_available(&self, system: &System) -> Vec<ShipCharacteristics> {
+    /// Get all ships available in the given system given the player's standing,
+    /// at the prices the player is offered there.
+    pub fn get_available(&self, system: &System, standing: &Standing) -> Vec<ShipCharacteristics> {
+        let price_factor = standing.price_factor(system);
         self.ships
             .iter()
-            .cloned()
-            .filter(|ref ship| {
+            .filter(|ship| {
                 // Only return if the faction matches, if any faction is specified.
                 if let Some(ref faction) = ship.faction {
-                    return *faction == system.faction;
+                    if *faction != system.faction {
+                        return false;
+                    }
+                }
+                // Only return if the player's rank with the faction is high enough.
+                if let Some(min_rank) = ship.min_rank {
+                    return standing.faction(&system.faction).rank() >= min_rank;
                 }
                 true
             })
+            .cloned()
+            .map(|mut ship| {
+                ship.cost = (f64::from(ship.cost) * price_factor).round() as u32;
+                ship
+            })
             .collect::<Vec<_>>()
     }
 
@@ -141,4 +157,49 @@ impl Shipyard {
     pub fn create_base_ship(&self) -> Ship {
         Ship::new(self.ships[0].clone())
     }
+
+    /// Create a new ship of the model with the given name, if any.
+    pub fn create_ship(&self, name: &str) -> Option<Ship> {
+        self.ships
+            .iter()
+            .find(|ship| ship.name == name)
+            .map(|ship| Ship::new(ship.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use astronomicals::{
+        star::{Star, StarType},
+        system::{SystemBuilder, SystemId, SystemSecurity, SystemState},
+    };
+    use entities::{Faction, StandingEvent};
+    use resources::fetch_resource;
+    use utils::Point;
+
+    #[test]
+    fn test_allied_ship_prices() {
+        let system = SystemBuilder::default()
+            .id(SystemId(0))
+            .location(Point::origin())
+            .name(String::from("Test"))
+            .faction(Faction::new("Empire"))
+            .security(SystemSecurity::Medium)
+            .state(SystemState::Boom)
+            .star(Star::new(1., 1., StarType::Single))
+            .satelites(vec![])
+            .build()
+            .unwrap();
+        let mut shipyard = Shipyard::new();
+        shipyard.add_ships(&fetch_resource::<ShipResource>().unwrap());
+
+        let mut standing = Standing::default();
+        let cost = shipyard.get_available(&system, &standing)[0].cost;
+        for _ in 0..40 {
+            standing.record(StandingEvent::Trade(20_000), &system);
+        }
+        let allied_cost = shipyard.get_available(&system, &standing)[0].cost;
+        assert_eq!(f64::from(allied_cost), (f64::from(cost) * 0.9).round());
+    }
 }
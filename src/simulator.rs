@@ -1,38 +1,74 @@
+use rand::{ChaChaRng, SeedableRng};
 use std::sync::Arc;
 
 use economy::Economy;
 use game::Game;
-use game_config::GameConfig;
+use game_config::{Difficulty, GameConfig};
 use generators::generate_galaxy;
 use player::Player;
-use resources::{fetch_resource, ShipResource};
-use utils::Point;
+use resources::{fetch_resource, ScenarioResource, ShipResource};
+use scenario::StartLocation;
 
 pub struct Simulator {
     game_state: Option<Arc<Game>>,
     game_config: GameConfig,
+    slot: String,
 }
 
 impl Simulator {
-    pub fn new() -> Self {
-        // Load GameConfig from disk
-        let game_config = GameConfig::retrieve();
+    /// Creates a simulator using the given config, saving games in the given
+    /// slot.
+    pub fn new(game_config: GameConfig, slot: &str) -> Self {
         info!("Initial config is: {:#?}", game_config);
         Simulator {
             game_config,
             game_state: None,
+            slot: String::from(slot),
         }
     }
 
+    /// Returns the config used for new games.
+    pub fn game_config(&self) -> &GameConfig {
+        &self.game_config
+    }
+
+    /// Sets the difficulty, map seed and scenario, if any, used for new games.
+    /// The presets of the difficulty are only applied when it changes, so that
+    /// hand edited configs are kept.
+    pub fn configure(&mut self, difficulty: Difficulty, seed: u32, scenario: Option<String>) {
+        if difficulty != self.game_config.difficulty {
+            self.game_config.set_difficulty(difficulty);
+        }
+        self.game_config.map_seed = seed;
+        self.game_config.scenario = scenario;
+        info!("Configured new game as: {:#?}", self.game_config);
+    }
+
     pub fn new_game(&mut self) -> Arc<Game> {
-        let game_state = Game::new();
+        // The config is validated to only name existing scenarios.
+        let scenario = self.game_config.scenario.as_ref().and_then(|name| {
+            fetch_resource::<ScenarioResource>()
+                .unwrap()
+                .get(name)
+                .cloned()
+        });
+        if let Some(seed) = scenario.as_ref().and_then(|scenario| scenario.seed) {
+            self.game_config.map_seed = seed;
+        }
+
+        let game_state = Game::new(&self.slot);
+        game_state
+            .clock
+            .lock()
+            .unwrap()
+            .set_time_scale(self.game_config.time_scale);
 
         // Generate galaxy
         info!("Generating galaxy...");
         let galaxy = generate_galaxy(&self.game_config);
 
         info!("Setting up economy...");
-        *game_state.economy.lock().unwrap() = Economy::new(&galaxy);
+        *game_state.economy.lock().unwrap() = Economy::new(&galaxy, self.game_config.agent_credits);
 
         *game_state.galaxy.lock().unwrap() = galaxy;
 
@@ -41,20 +77,45 @@ impl Simulator {
             .shipyard
             .lock()
             .unwrap()
-            .add_ships(fetch_resource::<ShipResource>().unwrap());
+            .add_ships(&fetch_resource::<ShipResource>().unwrap());
 
         info!("Creating player...");
-        *game_state.player.lock().unwrap() = Player::new(
-            self.game_config.starting_credits,
-            game_state.shipyard.lock().unwrap().create_base_ship(),
-            // TODO: Replace starting point in config.
-            game_state
-                .galaxy
-                .lock()
-                .unwrap()
-                .nearest(&Point::origin())
-                .unwrap(),
-        );
+        {
+            let galaxy = game_state.galaxy.lock().unwrap();
+            let shipyard = game_state.shipyard.lock().unwrap();
+            let seed: &[_] = &[self.game_config.map_seed];
+            let mut rng = ChaChaRng::from_seed(seed);
+
+            let location = scenario
+                .as_ref()
+                .map_or(&self.game_config.start, |scenario| &scenario.location);
+            let start = location.find(&galaxy, &mut rng).unwrap_or_else(|| {
+                warn!("No system matches start {:?}, using center", location);
+                StartLocation::Center.find(&galaxy, &mut rng).unwrap()
+            });
+
+            let mut ship = scenario
+                .as_ref()
+                .and_then(|scenario| scenario.ship.as_ref())
+                .and_then(|name| shipyard.create_ship(name))
+                .unwrap_or_else(|| shipyard.create_base_ship());
+            if let Some(ref scenario) = scenario {
+                for (commodity, &amount) in &scenario.cargo {
+                    if !ship.load(commodity, amount) {
+                        warn!("No room for {} {} in starting ship", amount, commodity);
+                    }
+                }
+            }
+
+            *game_state.player.lock().unwrap() = Player::new(
+                scenario
+                    .as_ref()
+                    .and_then(|scenario| scenario.credits)
+                    .unwrap_or(self.game_config.starting_credits),
+                ship,
+                start,
+            );
+        }
 
         game_state.update();
         game_state.save_all();
@@ -62,8 +123,32 @@ impl Simulator {
         game_state
     }
 
+    /// Loads the game saved in the slot, which keeps the time scale it was
+    /// saved with.
     pub fn load_game(&mut self) -> Option<Arc<Game>> {
-        self.game_state = Game::load();
+        self.game_state = Game::load(&self.slot);
         self.game_state.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configure_keeps_edits() {
+        let config = GameConfig {
+            starting_credits: 12_345,
+            ..GameConfig::default()
+        };
+        let mut simulator = Simulator::new(config, "test");
+
+        simulator.configure(Difficulty::Normal, 7, None);
+        assert_eq!(simulator.game_config().starting_credits, 12_345);
+        assert_eq!(simulator.game_config().map_seed, 7);
+
+        simulator.configure(Difficulty::Hard, 7, None);
+        assert_eq!(simulator.game_config().starting_credits, 250);
+        assert_eq!(simulator.game_config().difficulty, Difficulty::Hard);
+    }
+}
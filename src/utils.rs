@@ -5,6 +5,7 @@ use std::{
     mem::swap,
     ops::{Add, AddAssign, MulAssign},
 };
+use tui::style::Color;
 
 /// Generic Point type for geometry.
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
@@ -29,11 +30,25 @@ impl Point {
         ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
     }
 
+    /// Returns the squared euclidian distance to another point, cheaper when
+    /// only comparing distances.
+    pub fn distance_squared(&self, other: &Point) -> f64 {
+        (self.x - other.x).powi(2) + (self.y - other.y).powi(2)
+    }
+
     /// Returns the hash of the point coordinates.
     /// Hash based on algorithm used is presented in the paper:
     /// Optimized Spatial Hashing for Collision Detection of Deformable Objects.
     pub fn hash(&self) -> u64 {
-        ((self.x * 73_856_093f64) as u64 ^ (self.y * 19_349_663f64) as u64)
+        // Cast through i64 since negative floats saturate to zero as u64.
+        (self.x * 73_856_093f64) as i64 as u64 ^ (self.y * 19_349_663f64) as i64 as u64
+    }
+
+    /// Returns a seed for generators from the hash, folding in the upper half
+    /// which a plain cast to u32 drops.
+    pub fn seed(&self) -> u32 {
+        let hash = self.hash();
+        (hash ^ (hash >> 32)) as u32
     }
 }
 
@@ -101,33 +116,6 @@ impl PointN for Point {
     }
 }
 
-/// Point with weight associated so that it can be ordered.
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
-pub struct OrdPoint {
-    pub point: Point,
-    pub weight: u32,
-}
-
-impl Ord for OrdPoint {
-    fn cmp(&self, other: &OrdPoint) -> Ordering {
-        other.weight.partial_cmp(&self.weight).unwrap()
-    }
-}
-
-impl PartialOrd for OrdPoint {
-    fn partial_cmp(&self, other: &OrdPoint) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl PartialEq for OrdPoint {
-    fn eq(&self, other: &OrdPoint) -> bool {
-        self.weight == other.weight
-    }
-}
-
-impl Eq for OrdPoint {}
-
 /// Returns the edit distance between strings `a` and `b` using Levenshtein
 /// distance.
 /// The runtime complexity is `O(m*n)`, where `m` and `n` are the
@@ -172,9 +160,117 @@ pub fn edit_distance(a: &str, b: &str) -> i32 {
     pre[len_b - 1]
 }
 
+/// Parses a color given either by name or as a "#rrggbb" hex string,
+/// defaulting to white.
+pub fn parse_color(color: &str) -> Color {
+    match color {
+        "Black" => Color::Black,
+        "Red" => Color::Red,
+        "Green" => Color::Green,
+        "Yellow" => Color::Yellow,
+        "Blue" => Color::Blue,
+        "Magenta" => Color::Magenta,
+        "Cyan" => Color::Cyan,
+        "Gray" => Color::Gray,
+        "DarkGray" => Color::DarkGray,
+        "LightRed" => Color::LightRed,
+        "LightGreen" => Color::LightGreen,
+        "LightYellow" => Color::LightYellow,
+        "LightBlue" => Color::LightBlue,
+        "LightMagenta" => Color::LightMagenta,
+        "LightCyan" => Color::LightCyan,
+        hex if hex.starts_with('#') && hex.len() == 7 => {
+            let channel = |range| u8::from_str_radix(&hex[range], 16).unwrap_or(255);
+            Color::Rgb(channel(1..3), channel(3..5), channel(5..7))
+        }
+        _ => Color::White,
+    }
+}
+
+/// Returns the convex hull of the given points in counter-clockwise order,
+/// using Andrew's monotone chain algorithm.
+pub fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| {
+        (a.x, a.y)
+            .partial_cmp(&(b.x, b.y))
+            .unwrap_or(Ordering::Equal)
+    });
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    // Cross product of the vectors o->a and o->b, positive for a left turn.
+    let cross =
+        |o: &Point, a: &Point, b: &Point| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+    let mut hull: Vec<Point> = vec![];
+    // Lower hull followed by the upper hull.
+    for pass in 0..2 {
+        let start = hull.len();
+        let ordered: Box<Iterator<Item = &Point>> = if pass == 0 {
+            Box::new(points.iter())
+        } else {
+            Box::new(points.iter().rev())
+        };
+        for point in ordered {
+            while hull.len() >= start + 2
+                && cross(&hull[hull.len() - 2], &hull[hull.len() - 1], point) <= 0.
+            {
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+        // The last point is the first of the next chain.
+        hull.pop();
+    }
+    hull
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_convex_hull() {
+        let points = [
+            Point::new(0., 0.),
+            Point::new(2., 0.),
+            Point::new(1., 1.),
+            Point::new(2., 2.),
+            Point::new(0., 2.),
+            Point::new(1., 0.),
+        ];
+        assert_eq!(
+            convex_hull(&points),
+            vec![
+                Point::new(0., 0.),
+                Point::new(2., 0.),
+                Point::new(2., 2.),
+                Point::new(0., 2.),
+            ]
+        );
+        assert_eq!(convex_hull(&points[..2]).len(), 2);
+    }
+
+    #[test]
+    fn test_hash() {
+        // Points mirrored around the axes are hashed apart, which they were
+        // not while negative coordinates saturated to zero.
+        let points = [
+            Point::new(3., 4.),
+            Point::new(-3., 4.),
+            Point::new(3., -4.),
+            Point::new(-3., -4.),
+            Point::origin(),
+        ];
+        let hashes = points.iter().map(Point::hash).collect::<HashSet<_>>();
+        let seeds = points.iter().map(Point::seed).collect::<HashSet<_>>();
+        assert_eq!(hashes.len(), 5);
+        assert_eq!(seeds.len(), 5);
+        assert_eq!(Point::new(-1.5, 2.).seed(), Point::new(-1.5, 2.).seed());
+    }
 
     #[test]
     fn test_edit_distance() {